@@ -0,0 +1,203 @@
+//! Automatic rotating backups of the novels and history.
+//!
+//! Every backup is a single JSON file in `data/backups/` that contains both the
+//! `Database` and the `NovelHistory`. Only the newest `backup_retention` files are
+//! kept, older ones are removed whenever a new backup is made.
+
+use crate::database::Database;
+use crate::error::ErisError;
+use crate::file::write_atomic;
+use crate::history::NovelHistory;
+use crate::profile::profile_dir;
+use crate::BACKUP_DIR;
+use anyhow::Context;
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// File name prefix of the backup files, `backup_<timestamp>_<reason>.json`.
+const BACKUP_PREFIX: &str = "backup_";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupReason {
+    /// First start of the day.
+    Daily,
+    /// Application was closed.
+    Quit,
+    /// Before a JSON file was imported.
+    Import,
+    /// Before another backup was restored.
+    Restore,
+}
+
+impl ToString for BackupReason {
+    fn to_string(&self) -> String {
+        match self {
+            BackupReason::Daily => fl!("backup-reason-daily"),
+            BackupReason::Quit => fl!("backup-reason-quit"),
+            BackupReason::Import => fl!("backup-reason-import"),
+            BackupReason::Restore => fl!("backup-reason-restore"),
+        }
+    }
+}
+
+impl BackupReason {
+    /// Name used in the backup file name.
+    fn file_name_part(&self) -> &'static str {
+        match self {
+            BackupReason::Daily => "daily",
+            BackupReason::Quit => "quit",
+            BackupReason::Import => "import",
+            BackupReason::Restore => "restore",
+        }
+    }
+}
+
+/// Contents of a backup file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backup {
+    pub created: i64,
+    pub reason: BackupReason,
+    pub db: Database,
+    pub history: NovelHistory,
+}
+
+/// Summary of a backup file shown in the restore dialog.
+#[derive(Debug, Clone)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub created: i64,
+    pub reason: BackupReason,
+    pub novel_count: usize,
+    pub history_count: usize,
+}
+
+impl BackupInfo {
+    /// Local time of the backup in a human readable format.
+    pub fn created_string(&self) -> String {
+        Local.timestamp(self.created, 0).format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+impl Backup {
+    /// Write a new backup of `db` and `history` and remove the ones that no longer fit in `retention`.
+    pub fn create(
+        db: &Database,
+        history: &NovelHistory,
+        reason: BackupReason,
+        retention: u32,
+    ) -> Result<PathBuf, anyhow::Error> {
+        let backup_dir = profile_dir(BACKUP_DIR);
+        fs::create_dir_all(&backup_dir).context(ErisError::WriteToDisk)?;
+
+        let backup = Backup {
+            created: Local::now().timestamp(),
+            reason,
+            db: db.clone(),
+            history: history.clone(),
+        };
+
+        let path = backup_dir.join(format!(
+            "{}{}_{}.json",
+            BACKUP_PREFIX,
+            backup.created,
+            reason.file_name_part()
+        ));
+        write_atomic(&path, |writer| {
+            serde_json::to_writer(writer, &backup).context(ErisError::SerializeToFile)
+        })?;
+        info!("Backup created: {:?}", path);
+
+        prune(retention);
+
+        Ok(path)
+    }
+
+    /// Read the whole backup in `path`.
+    pub fn open(path: &Path) -> Result<Self, anyhow::Error> {
+        let f = File::open(path).context(ErisError::ReadFromDisk)?;
+        serde_json::from_reader(BufReader::new(f)).context(ErisError::DeserializeFromFile)
+    }
+}
+
+/// All the backups, newest first. Files that cannot be read are skipped.
+pub fn list_backups() -> Vec<BackupInfo> {
+    let mut backups: Vec<BackupInfo> = backup_files()
+        .into_iter()
+        .filter_map(|path| match Backup::open(&path) {
+            Ok(backup) => Some(BackupInfo {
+                path,
+                created: backup.created,
+                reason: backup.reason,
+                novel_count: backup.db.novels.as_ref().map_or(0, |n| n.len()),
+                history_count: backup.history.items.len(),
+            }),
+            Err(e) => {
+                warn!("Skipping unreadable backup {:?}. {:#}", path, e);
+                None
+            }
+        })
+        .collect();
+
+    backups.sort_by(|a, b| b.created.cmp(&a.created));
+    backups
+}
+
+/// Whether a backup has already been made today.
+pub fn has_backup_today() -> bool {
+    let today = Local::today();
+
+    backup_files()
+        .iter()
+        .filter_map(|path| backup_timestamp(path))
+        .any(|ts| Local.timestamp(ts, 0).date() == today)
+}
+
+/// Remove the oldest backups so that only `retention` of them are left.
+fn prune(retention: u32) {
+    let mut files: Vec<(i64, PathBuf)> = backup_files()
+        .into_iter()
+        .filter_map(|path| backup_timestamp(&path).map(|ts| (ts, path)))
+        .collect();
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+
+    for (_, path) in files.into_iter().skip(retention as usize) {
+        match fs::remove_file(&path) {
+            Ok(_) => debug!("Removed old backup {:?}", path),
+            Err(e) => error!("Cannot remove old backup {:?}. {}", path, e),
+        }
+    }
+}
+
+/// Paths of all the files in the backup directory that look like backups.
+fn backup_files() -> Vec<PathBuf> {
+    let entries = match fs::read_dir(profile_dir(BACKUP_DIR)) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| backup_timestamp(path).is_some())
+        .collect()
+}
+
+/// Timestamp from a `backup_<timestamp>_<reason>.json` file name.
+fn backup_timestamp(path: &Path) -> Option<i64> {
+    if path.extension()? != "json" {
+        return None;
+    }
+
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix(BACKUP_PREFIX)?
+        .split('_')
+        .next()?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,109 @@
+//! Matches of window titles to novels that wait for the user to confirm them.
+//!
+//! A match below the confidence to count chapters on its own is queued instead.
+//! Confirming it makes the matched text a window title keyword of the novel, so
+//! the next time it is an exact match.
+
+use crate::recognition::NovelMatch;
+use std::collections::HashSet;
+
+/// The oldest pending match is dropped when there would be more.
+pub const MAX_PENDING: usize = 10;
+
+#[derive(Debug, Clone, Default)]
+pub struct ConfirmationQueue {
+    pending: Vec<NovelMatch>,
+    /// Text and novel id of the matches the user said no to.
+    dismissed: HashSet<(String, String)>,
+}
+
+impl ConfirmationQueue {
+    /// Queue `matched`, unless it is already queued or was dismissed.
+    /// Returns `true` if the queue changed.
+    pub fn push(&mut self, matched: NovelMatch) -> bool {
+        let key = (matched.text.clone(), matched.novel_id.clone());
+        if self.dismissed.contains(&key)
+            || self
+                .pending
+                .iter()
+                .any(|pending| pending.text == matched.text && pending.novel_id == matched.novel_id)
+        {
+            return false;
+        }
+
+        self.pending.push(matched);
+        if self.pending.len() > MAX_PENDING {
+            self.pending.remove(0);
+        }
+
+        true
+    }
+
+    /// Pending matches, oldest first.
+    pub fn pending(&self) -> &[NovelMatch] {
+        &self.pending
+    }
+
+    /// Take the match of `text` to the novel out of the queue. Other matches of
+    /// the same text are dropped too, since it now belongs to this novel.
+    pub fn confirm(&mut self, text: &str, novel_id: &str) -> Option<NovelMatch> {
+        let index = self
+            .pending
+            .iter()
+            .position(|pending| pending.text == text && pending.novel_id == novel_id)?;
+        let matched = self.pending.remove(index);
+        self.pending.retain(|pending| pending.text != text);
+
+        Some(matched)
+    }
+
+    /// Drop the match of `text` to the novel and do not queue it again.
+    pub fn dismiss(&mut self, text: &str, novel_id: &str) {
+        self.pending
+            .retain(|pending| !(pending.text == text && pending.novel_id == novel_id));
+        self.dismissed.insert((text.to_string(), novel_id.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recognition::MatchReason;
+
+    fn matched(text: &str, novel_id: &str) -> NovelMatch {
+        NovelMatch {
+            novel_id: novel_id.to_string(),
+            novel_title: novel_id.to_uppercase(),
+            text: text.to_string(),
+            confidence: 0.7,
+            reason: MatchReason::Similar,
+        }
+    }
+
+    #[test]
+    fn test_queue() {
+        let mut queue = ConfirmationQueue::default();
+
+        assert!(queue.push(matched("Wandering Inn", "inn")));
+        assert!(!queue.push(matched("Wandering Inn", "inn")));
+        assert!(queue.push(matched("Wandering Inn", "other")));
+        assert!(queue.push(matched("Lord of Mysteries", "lotm")));
+
+        assert_eq!(
+            queue.confirm("Wandering Inn", "inn"),
+            Some(matched("Wandering Inn", "inn"))
+        );
+        assert_eq!(queue.pending(), &[matched("Lord of Mysteries", "lotm")]);
+        assert_eq!(queue.confirm("Wandering Inn", "inn"), None);
+
+        queue.dismiss("Lord of Mysteries", "lotm");
+        assert!(queue.pending().is_empty());
+        assert!(!queue.push(matched("Lord of Mysteries", "lotm")));
+
+        for i in 0..MAX_PENDING + 2 {
+            queue.push(matched(&format!("Title {}", i), "inn"));
+        }
+        assert_eq!(queue.pending().len(), MAX_PENDING);
+        assert_eq!(queue.pending()[0].text, "Title 2");
+    }
+}
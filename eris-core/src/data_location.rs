@@ -0,0 +1,286 @@
+//! Moving the whole data directory, every profile included, to another location.
+//!
+//! The library is copied first and the location file in the default data directory
+//! is only replaced once the copy is complete, so a failed move leaves the current
+//! library as it was. The old data directory is only removed on the next start, since
+//! the running Eris may still have files open in it.
+
+use crate::database::DB_EXPORT_PREFIX;
+use crate::error::ErisError;
+use crate::file::{backup_path, write_atomic};
+use crate::paths::{data_dir_source, default_data_dir, set_data_dir, DataDirSource, DATA_DIR_LOCATION_FILE};
+use anyhow::Context;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// File in the data directory with the old data directories to remove, one on each line.
+const MOVED_FROM_FILE: &str = "eris.moved_from";
+
+/// Move the data directory to `target` and use it from now on.
+///
+/// `target` has to be empty or not exist yet.
+pub fn move_data_dir(target: &Path) -> Result<(), ErisError> {
+    let (current, source) = data_dir_source();
+    if matches!(source, DataDirSource::Flag | DataDirSource::Env) {
+        return Err(ErisError::DataDirOverridden);
+    }
+    check_target(&current, target)?;
+
+    if let Err(e) = copy_dir(&current, target) {
+        error!("Cannot copy {:?} to {:?}. {:#}", current, target, e);
+        remove_dir_contents(target);
+        return Err(ErisError::WriteToDisk);
+    }
+
+    // The library is moved once the default data directory points to the copy
+    let default_dir = default_data_dir();
+    let location_file = default_dir.join(DATA_DIR_LOCATION_FILE);
+    let result = if target == default_dir {
+        remove_location_file(&location_file)
+    } else {
+        fs::create_dir_all(&default_dir)
+            .context(ErisError::WriteToDisk)
+            .and_then(|_| {
+                write_atomic(&location_file, |writer| {
+                    writer
+                        .write_all(target.to_string_lossy().as_bytes())
+                        .context(ErisError::WriteToDisk)
+                })
+            })
+    };
+    if let Err(e) = result {
+        error!("Cannot update {:?}. {:#}", location_file, e);
+        remove_dir_contents(target);
+        return Err(ErisError::WriteToDisk);
+    }
+
+    let source = if target == default_dir {
+        DataDirSource::Default
+    } else {
+        DataDirSource::Moved
+    };
+    set_data_dir(target.to_path_buf(), source);
+    info!("Moved the data directory from {:?} to {:?}", current, target);
+
+    // The old copy is not used anymore, but it is removed on the next start only
+    let mut moved_from = read_moved_from(&current);
+    moved_from.push(current.clone());
+    let moved_from = moved_from
+        .iter()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if let Err(e) = fs::write(target.join(MOVED_FROM_FILE), moved_from) {
+        warn!(
+            "Cannot remember to remove {:?}, it has to be removed by hand. {}",
+            current, e
+        );
+    }
+
+    Ok(())
+}
+
+/// Remove the data directories the library was moved from, now that nothing uses them.
+pub fn remove_moved_from_dirs() {
+    let (current, _) = data_dir_source();
+    remove_moved_from(&current, &default_data_dir());
+}
+
+fn remove_moved_from(current: &Path, default_dir: &Path) {
+    for dir in read_moved_from(current) {
+        // Never the library in use, in case it was moved back
+        if current.starts_with(&dir) {
+            continue;
+        }
+
+        info!("Removing the old data directory {:?}", dir);
+        remove_eris_entries(&dir);
+        // Only goes if nothing else was left in it
+        if dir != default_dir {
+            let _ = fs::remove_dir(&dir);
+        }
+    }
+
+    let _ = fs::remove_file(current.join(MOVED_FROM_FILE));
+}
+
+fn read_moved_from(dir: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(dir.join(MOVED_FROM_FILE))
+        .map(|text| {
+            text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| PathBuf::from(line.trim()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `target` cannot be inside the current data directory and has to be empty.
+fn check_target(current: &Path, target: &Path) -> Result<(), ErisError> {
+    if target.starts_with(current) {
+        return Err(ErisError::DataDirInsideCurrent);
+    }
+
+    if let Ok(entries) = fs::read_dir(target) {
+        if entries
+            .filter_map(|entry| entry.ok())
+            .any(|entry| !is_location_file(&entry.path()))
+        {
+            return Err(ErisError::DataDirNotEmpty(target.to_path_buf()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Recursively copy the contents of `from` into `to`, leaving out the location file and
+/// the old data directories to remove.
+fn copy_dir(from: &Path, to: &Path) -> Result<(), anyhow::Error> {
+    fs::create_dir_all(to).with_context(|| format!("Cannot create {:?}", to))?;
+
+    for entry in fs::read_dir(from).with_context(|| format!("Cannot read {:?}", from))? {
+        let path = entry?.path();
+        if is_location_file(&path) || path.file_name() == Some(OsStr::new(MOVED_FROM_FILE)) {
+            continue;
+        }
+
+        let new_path = to.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            copy_dir(&path, &new_path)?;
+        } else {
+            fs::copy(&path, &new_path).with_context(|| format!("Cannot copy {:?}", path))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove everything in `dir` besides the location file.
+fn remove_dir_contents(dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if is_location_file(&path) {
+            continue;
+        }
+
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(e) = result {
+            warn!("Cannot remove {:?}. {}", path, e);
+        }
+    }
+}
+
+/// Remove what Eris keeps in the old data directory `dir`. Anything else is left
+/// in place, in case the line in the moved from file was wrong.
+fn remove_eris_entries(dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let name = path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+        if is_location_file(&path) {
+            continue;
+        }
+        if !is_eris_entry(name) {
+            info!("Keeping {:?} in the old data directory, it is not from Eris", path);
+            continue;
+        }
+
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(e) = result {
+            warn!("Cannot remove {:?}. {}", path, e);
+        }
+    }
+}
+
+/// Whether the entry `name` of a data directory is one of the novels and images, history,
+/// settings, logs, backups, exports or profiles, with their `.bak` and set aside copies.
+fn is_eris_entry(name: &str) -> bool {
+    const DIRS: [&str; 3] = ["db", "backups", "profiles"];
+    const PREFIXES: [&str; 4] = ["eris.", "title_rules.toml", DB_EXPORT_PREFIX, "history_v"];
+
+    DIRS.contains(&name) || PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+fn remove_location_file(location_file: &Path) -> Result<(), anyhow::Error> {
+    for path in [location_file.to_path_buf(), backup_path(location_file)] {
+        if path.exists() {
+            fs::remove_file(&path).context(ErisError::WriteToDisk)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_location_file(path: &Path) -> bool {
+    let location_file = PathBuf::from(DATA_DIR_LOCATION_FILE);
+
+    path.file_name() == Some(OsStr::new(DATA_DIR_LOCATION_FILE))
+        || path.file_name() == backup_path(&location_file).file_name()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_data_dir() {
+        let root = std::env::temp_dir().join(format!("eris-data-location-{}", std::process::id()));
+        let current = root.join("current");
+        let target = root.join("target");
+        fs::create_dir_all(current.join("db/images")).unwrap();
+        fs::write(current.join("db/eris.db"), "db").unwrap();
+        fs::write(current.join(DATA_DIR_LOCATION_FILE), "somewhere").unwrap();
+        fs::write(current.join("eris.history"), "history").unwrap();
+
+        assert!(matches!(
+            check_target(&current, &current.join("db")),
+            Err(ErisError::DataDirInsideCurrent)
+        ));
+        assert!(matches!(
+            check_target(&current, &root),
+            Err(ErisError::DataDirNotEmpty(_))
+        ));
+        assert!(check_target(&current, &target).is_ok());
+
+        copy_dir(&current, &target).unwrap();
+        assert_eq!(fs::read_to_string(target.join("db/eris.db")).unwrap(), "db");
+        assert!(target.join("db/images").is_dir());
+        assert!(target.join("eris.history").exists());
+        assert!(!target.join(DATA_DIR_LOCATION_FILE).exists());
+
+        // The old directory goes once the copy is in use
+        fs::write(
+            target.join(MOVED_FROM_FILE),
+            format!("{}\n{}", current.display(), target.display()),
+        )
+        .unwrap();
+        // Not from Eris so it is kept
+        fs::write(current.join("notes.txt"), "notes").unwrap();
+        remove_moved_from(&target, &current);
+        assert!(!current.join("db").exists());
+        assert!(!current.join("eris.history").exists());
+        assert!(current.join("notes.txt").exists());
+        assert!(current.join(DATA_DIR_LOCATION_FILE).exists());
+        assert!(target.join("db/eris.db").exists());
+        assert!(!target.join(MOVED_FROM_FILE).exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,213 @@
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use bincode::deserialize_from;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ErisError;
+use crate::file::{backup_path, set_aside, write_atomic};
+use crate::novel::Novel;
+use crate::profile::profile_dir;
+use crate::schema::{deserialize_versioned, newer_version, serialize_versioned, Versioned, LEGACY_VERSION};
+use crate::{DATA_DIR, DB_FILE};
+use anyhow::Context;
+use chrono::Local;
+
+/// File name prefix of the database JSON exports.
+pub const DB_EXPORT_PREFIX: &str = "db_v";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename = "database", default)]
+pub struct Database {
+    #[serde(rename = "novel")]
+    pub novels: Option<Vec<Novel>>,
+    /// Last time the database file was saved.
+    pub last_update: i64,
+    /// Set when the database file could not be read. Prevents
+    /// overwriting the (possibly still salvageable) file on disk.
+    #[serde(skip)]
+    pub read_only: bool,
+}
+impl Default for Database {
+    fn default() -> Self {
+        Self::new(Some(vec![]))
+    }
+}
+
+impl Database {
+    pub fn new(novels: Option<Vec<Novel>>) -> Database {
+        Database {
+            novels,
+            last_update: Local::now().timestamp(),
+            read_only: false,
+        }
+    }
+
+    /// Push a new `Novel` into the database vector of novels.
+    pub fn push_novel(&mut self, novel: Novel) {
+        if let Some(novels) = &mut self.novels {
+            novels.push(novel);
+        }
+    }
+    /// Serialize and write database into file.
+    pub fn write_to_file(&mut self) -> Result<(), anyhow::Error> {
+        if self.read_only {
+            return Err(ErisError::ReadOnlyDatabase.into());
+        }
+
+        self.last_update = Local::now().timestamp();
+
+        let path = profile_dir(DB_FILE);
+        write_atomic(&path, |writer| serialize_versioned(writer, &*self))
+    }
+
+    /// Try to deserialize file contents into `Database`
+    fn from_path(path: &Path) -> Result<Self, anyhow::Error> {
+        let f = File::open(path).context(ErisError::ReadFromDisk)?;
+        let reader = BufReader::new(f);
+
+        deserialize_versioned(reader)
+    }
+}
+
+impl Versioned for Database {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, reader: &mut dyn Read) -> Result<Self, anyhow::Error> {
+        match version {
+            // Files from before the schema header have the same layout as version 1
+            LEGACY_VERSION => Ok(deserialize_from(reader)?),
+            _ => Err(ErisError::UnsupportedVersion(version).into()),
+        }
+    }
+}
+
+/// What happened when the database file could not be read on startup.
+#[derive(Debug, Clone)]
+pub struct DatabaseRecovery {
+    /// Every file that was tried, in order, with the reason it failed.
+    pub attempts: Vec<String>,
+    /// `true` if the database was restored from the backup file.
+    pub restored_from_backup: bool,
+    /// Latest database JSON export which can be offered for import
+    /// when nothing else worked.
+    pub json_export: Option<PathBuf>,
+    /// Schema version of a file written by a newer Eris. The file is kept as it is
+    /// and the library is read-only.
+    pub newer_version: Option<u32>,
+}
+
+impl DatabaseRecovery {
+    /// A file of the library was written by a newer Eris.
+    pub fn newer_version(attempt: String, version: u32) -> Self {
+        DatabaseRecovery {
+            attempts: vec![attempt],
+            restored_from_backup: false,
+            // Importing would replace the file
+            json_export: None,
+            newer_version: Some(version),
+        }
+    }
+}
+
+/// Try to deserialize the database from the file or create a new
+/// databse instance if the file doesn't exist.
+///
+/// If the database file cannot be read then the backup generation is tried next.
+/// When that fails too an empty read-only database is returned along with
+/// the information about what was tried.
+pub(crate) fn read_database() -> (Database, Option<DatabaseRecovery>) {
+    let path = profile_dir(DB_FILE);
+    let bak_path = backup_path(&path);
+    debug!("Database file exists: {:?}", &path.exists());
+
+    // Create the db file if it doesn't exist
+    if !path.exists() && !bak_path.exists() {
+        return (Database::new(Some(vec![])), None);
+    }
+
+    let mut attempts = vec![];
+
+    // Open the db file and try to deserialize its contents into `Database`
+    match Database::from_path(&path) {
+        Ok(db) => return (db, None),
+        Err(e) => {
+            error!("`{}`: {:#}", path.display(), e);
+            // Restoring the backup would set the newer file aside
+            if let Some(version) = newer_version(&e) {
+                let mut db = Database::new(Some(vec![]));
+                db.read_only = true;
+                let recovery = DatabaseRecovery::newer_version(format!("{}: {:#}", path.display(), e), version);
+                return (db, Some(recovery));
+            }
+            attempts.push(format!("{}: {:#}", path.display(), e));
+        }
+    }
+
+    // Then try the previous generation
+    match Database::from_path(&bak_path) {
+        Ok(db) => {
+            warn!("Database restored from the backup file `{}`", bak_path.display());
+            // Move the broken file out of the way so the next save
+            // does not replace the good backup with it
+            set_aside_database_file();
+
+            let recovery = DatabaseRecovery {
+                attempts,
+                restored_from_backup: true,
+                json_export: None,
+                newer_version: None,
+            };
+            return (db, Some(recovery));
+        }
+        Err(e) => {
+            error!("`{}`: {:#}", bak_path.display(), e);
+            attempts.push(format!("{}: {:#}", bak_path.display(), e));
+        }
+    }
+
+    let mut db = Database::new(Some(vec![]));
+    db.read_only = true;
+
+    let recovery = DatabaseRecovery {
+        attempts,
+        restored_from_backup: false,
+        json_export: latest_db_export(),
+        newer_version: None,
+    };
+
+    (db, Some(recovery))
+}
+
+/// Rename the unreadable database file to `eris.db.corrupt-<timestamp>`
+/// so it is kept for manual salvaging but never used again.
+pub fn set_aside_database_file() {
+    set_aside(&profile_dir(DB_FILE), "corrupt");
+}
+
+/// Find the most recent `db_v<version>_<timestamp>.json` export from the data directory.
+pub(crate) fn latest_db_export() -> Option<PathBuf> {
+    let entries = fs::read_dir(profile_dir(DATA_DIR)).ok()?;
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            if !file_name.starts_with(DB_EXPORT_PREFIX) || !file_name.ends_with(".json") {
+                return None;
+            }
+            // The timestamp is the last `_` separated part of the name
+            let timestamp = file_name
+                .trim_end_matches(".json")
+                .rsplit('_')
+                .next()?
+                .parse::<i64>()
+                .ok()?;
+
+            Some((timestamp, entry.path()))
+        })
+        .max_by_key(|(timestamp, _)| *timestamp)
+        .map(|(_, path)| path)
+}
@@ -0,0 +1,150 @@
+//! Timers for how long each recognized novel has been read.
+//!
+//! A chapter is counted as read once its window has been active for the delay, where
+//! active means focused while the user is not idle. Every novel has its own timer, so
+//! novels open in different tabs or windows at the same time are all counted.
+
+use std::collections::HashMap;
+
+/// Longest time in seconds between two updates that is counted. A longer gap means the
+/// updates stalled, e.g. the machine was asleep, and only this much of it is counted.
+pub const MAX_STEP: i64 = 30;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Dwell {
+    /// Window title the novel was recognized from, another title starts the timer again.
+    title: String,
+    /// Seconds the window has been active.
+    active: i64,
+    /// The delay has passed and the chapter was counted.
+    counted: bool,
+}
+
+/// A recognized novel with its window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenNovel {
+    pub id: String,
+    pub title: String,
+    /// The window is focused and the user is not idle.
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DwellTimers {
+    timers: HashMap<String, Dwell>,
+    /// Unix timestamp of the last update.
+    updated: Option<i64>,
+}
+
+impl DwellTimers {
+    /// Update the timers with the novels open at `now`.
+    ///
+    /// The time since the last update is added to the timers of the active novels.
+    /// Timers of the novels that are not open anymore are dropped. If a novel is open
+    /// more than once the first active window is used. Returns the ids of the novels
+    /// whose `delay` passed on this update.
+    pub fn update(&mut self, open: &[OpenNovel], now: i64, delay: i64) -> Vec<String> {
+        let step = self
+            .updated
+            .replace(now)
+            .map_or(0, |updated| (now - updated).clamp(0, MAX_STEP));
+
+        self.timers.retain(|id, _| open.iter().any(|novel| &novel.id == id));
+
+        let mut done = vec![];
+        for (i, novel) in open.iter().enumerate() {
+            let first = open
+                .iter()
+                .position(|other| other.id == novel.id && other.active)
+                .or_else(|| open.iter().position(|other| other.id == novel.id));
+            if first != Some(i) {
+                continue;
+            }
+
+            let dwell = self.timers.entry(novel.id.clone()).or_insert_with(|| Dwell {
+                title: novel.title.clone(),
+                active: 0,
+                counted: false,
+            });
+            if dwell.title != novel.title {
+                *dwell = Dwell {
+                    title: novel.title.clone(),
+                    active: 0,
+                    counted: false,
+                };
+            } else if novel.active {
+                dwell.active += step;
+            }
+
+            if !dwell.counted && dwell.active >= delay {
+                dwell.counted = true;
+                done.push(novel.id.clone());
+            } else if !dwell.counted {
+                debug!("Seconds left till {} is counted: {}", novel.id, delay - dwell.active);
+            }
+        }
+
+        done
+    }
+
+    /// Forget every timer, e.g. when nothing is being read.
+    pub fn clear(&mut self) {
+        self.timers.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(novels: &[(&str, &str, bool)]) -> Vec<OpenNovel> {
+        novels
+            .iter()
+            .map(|(id, title, active)| OpenNovel {
+                id: id.to_string(),
+                title: title.to_string(),
+                active: *active,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_update() {
+        let mut timers = DwellTimers::default();
+        let both = open(&[("a", "A - Chapter 1", true), ("b", "B - Chapter 5", true)]);
+
+        assert!(timers.update(&both, 0, 60).is_empty());
+        assert!(timers.update(&both, 30, 60).is_empty());
+        // Another chapter of `a` starts its timer again, the active window of `b` is used
+        let next = open(&[
+            ("a", "A - Chapter 2", true),
+            ("b", "B - Chapter 9", false),
+            ("b", "B - Chapter 5", true),
+        ]);
+        assert!(timers.update(&next, 50, 60).is_empty());
+        assert_eq!(timers.update(&next, 60, 60), vec!["b"]);
+        // Counted once only
+        assert!(timers.update(&next, 90, 60).is_empty());
+        assert_eq!(timers.update(&next, 110, 60), vec!["a"]);
+
+        // Closing and opening again starts over
+        timers.update(&open(&[("a", "A - Chapter 2", true)]), 120, 60);
+        assert!(timers.update(&next, 130, 60).is_empty());
+    }
+
+    #[test]
+    fn test_only_active_time_counts() {
+        let mut timers = DwellTimers::default();
+        let background = open(&[("a", "A - Chapter 1", false)]);
+        let focused = open(&[("a", "A - Chapter 1", true)]);
+
+        // In the background, e.g. not focused or the user is idle, all night long
+        for now in (0..8 * 3600).step_by(3) {
+            assert!(timers.update(&background, now, 60).is_empty());
+        }
+        // A long gap between the updates is not counted in full
+        assert!(timers.update(&focused, 10 * 3600, 60).is_empty());
+        assert!(timers.update(&focused, 10 * 3600 + 20, 60).is_empty());
+        assert_eq!(timers.update(&focused, 10 * 3600 + 30, 60), vec!["a"]);
+    }
+}
@@ -0,0 +1,51 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ErisError {
+    #[error("Failed to open and read file on disk.")]
+    ReadFromDisk,
+    #[error("Failed to write file to disk.")]
+    WriteToDisk,
+    #[error("Failed to deserialize from a file.")]
+    DeserializeFromFile,
+    #[error("Failed to serialize into a file.")]
+    SerializeToFile,
+    #[error("File was written with a newer schema version ({0}) than this version of Eris supports.")]
+    UnsupportedVersion(u32),
+    #[error("Database is in read-only mode, changes are not saved.")]
+    ReadOnlyDatabase,
+    #[error("File is not valid JSON. {0}")]
+    InvalidJson(String),
+    #[error("File is not a novel or history export made by Eris.")]
+    UnknownExportKind,
+    #[error("Export version {0} is not supported by this version of Eris.")]
+    UnsupportedExportVersion(String),
+    #[error("Export contains invalid data: {0}")]
+    InvalidExportData(String),
+    #[error("File is not a novel export.")]
+    NotDatabaseExport,
+    #[error("The data directory is set with `--data-dir`, `--portable` or `ERIS_DATA_DIR` and cannot be moved.")]
+    DataDirOverridden,
+    #[error("Cannot move the library into itself.")]
+    DataDirInsideCurrent,
+    #[error("The new data directory {0:?} is not empty.")]
+    DataDirNotEmpty(std::path::PathBuf),
+    #[error("Url {0} is not supported.")]
+    UnsupportedUrl(String),
+    #[error("Could not download the novel. {0}")]
+    FetchNovel(String),
+    #[error("Could not read the novel from {0}.")]
+    ParseNovel(String),
+    #[error("No novel `{0}` in the library.")]
+    NovelNotFound(String),
+    #[error("`{0}` is already in the library.")]
+    NovelExists(String),
+    #[error("Unknown error.")]
+    Unknown,
+}
+
+impl From<anyhow::Error> for ErisError {
+    fn from(_err: anyhow::Error) -> Self {
+        ErisError::Unknown
+    }
+}
@@ -0,0 +1,199 @@
+//! JSON exports of the novels and history.
+
+use crate::database::Database;
+use crate::error::ErisError;
+use crate::file::write_atomic;
+use crate::history::NovelHistory;
+use crate::profile::profile_dir;
+use anyhow::Context;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Change this if `Database` structure changes.
+const DB_VERSION: &str = "1.0";
+/// Change this if `NovelHistory` structure changes.
+const HISTORY_VERSION: &str = "1.0";
+/// Key of the export kind in the JSON root object.
+const KIND_KEY: &str = "eris_export";
+/// Key of the export version in the JSON root object.
+const VERSION_KEY: &str = "eris_version";
+
+/// What a JSON export contains.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    Database,
+    History,
+}
+
+/// The data in the export next to the `eris_export` and `eris_version` keys,
+/// so older versions of Eris can still read the file.
+#[derive(Serialize)]
+struct Export<'a, T> {
+    #[serde(rename = "eris_export")]
+    kind: ExportKind,
+    #[serde(rename = "eris_version")]
+    version: &'static str,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+/// Validated contents of a JSON export.
+#[derive(Debug)]
+pub enum ImportData {
+    Database(Database),
+    History(NovelHistory),
+}
+
+pub fn export_db_to_json(db: &Database) -> Result<PathBuf, ErisError> {
+    let path = profile_dir(&format!("data/db_v{}_{}.json", DB_VERSION, Local::now().timestamp()));
+    write_export(&path, ExportKind::Database, DB_VERSION, db)?;
+    debug!("DB exported to json. Filename: {:?}", path);
+
+    Ok(path)
+}
+
+pub fn export_history_to_json(history: &NovelHistory) -> Result<PathBuf, ErisError> {
+    let path = profile_dir(&format!(
+        "data/history_v{}_{}.json",
+        HISTORY_VERSION,
+        Local::now().timestamp()
+    ));
+    write_export(&path, ExportKind::History, HISTORY_VERSION, history)?;
+    debug!("History exported to json. Filename: {:?}", path);
+
+    Ok(path)
+}
+
+fn write_export<T: Serialize>(path: &Path, kind: ExportKind, version: &'static str, data: &T) -> Result<(), ErisError> {
+    let export = Export { kind, version, data };
+
+    write_atomic(path, |writer| {
+        serde_json::to_writer(writer, &export).context(ErisError::SerializeToFile)
+    })
+    .map_err(|e| {
+        error!("Cannot write export {:?}. {:#}", path, e);
+        ErisError::WriteToDisk
+    })
+}
+
+/// Read the JSON export in `path`, detect what it contains and validate it.
+pub fn read_export(path: &Path) -> Result<ImportData, ErisError> {
+    let json_data = fs::read_to_string(path).map_err(|e| {
+        error!("Cannot read {:?}. {}", path, e);
+        ErisError::ReadFromDisk
+    })?;
+
+    parse_export(&json_data)
+}
+
+/// Detect the kind and version of the export from its content and deserialize it.
+///
+/// Exports from before the `eris_export` key existed are recognized from their
+/// root keys, `novel` for the database and `items` for the history.
+pub fn parse_export(json_data: &str) -> Result<ImportData, ErisError> {
+    let root: Value = serde_json::from_str(json_data).map_err(|e| ErisError::InvalidJson(e.to_string()))?;
+    let object = root.as_object().ok_or(ErisError::UnknownExportKind)?;
+
+    let (kind, version) = match object.get(KIND_KEY) {
+        Some(kind) => {
+            let kind: ExportKind = serde_json::from_value(kind.clone()).map_err(|_| ErisError::UnknownExportKind)?;
+            let version = object
+                .get(VERSION_KEY)
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string())
+                .ok_or_else(|| ErisError::InvalidExportData(format!("`{}` is missing", VERSION_KEY)))?;
+            (kind, version)
+        }
+        None if object.contains_key("novel") => (ExportKind::Database, DB_VERSION.to_string()),
+        None if object.contains_key("items") => (ExportKind::History, HISTORY_VERSION.to_string()),
+        None => return Err(ErisError::UnknownExportKind),
+    };
+
+    match kind {
+        ExportKind::Database => {
+            if version != DB_VERSION {
+                return Err(ErisError::UnsupportedExportVersion(version));
+            }
+            let db: Database = serde_json::from_value(root).map_err(|e| ErisError::InvalidExportData(e.to_string()))?;
+            validate_db(&db)?;
+
+            Ok(ImportData::Database(db))
+        }
+        ExportKind::History => {
+            if version != HISTORY_VERSION {
+                return Err(ErisError::UnsupportedExportVersion(version));
+            }
+            let history: NovelHistory =
+                serde_json::from_value(root).map_err(|e| ErisError::InvalidExportData(e.to_string()))?;
+
+            Ok(ImportData::History(history))
+        }
+    }
+}
+
+/// Novels need a unique, non-empty id for everything else to work.
+fn validate_db(db: &Database) -> Result<(), ErisError> {
+    let mut ids = HashSet::new();
+    for novel in db.novels.as_deref().unwrap_or_default() {
+        if novel.id.is_empty() {
+            return Err(ErisError::InvalidExportData(format!(
+                "novel `{}` has no id",
+                novel.title
+            )));
+        }
+        if !ids.insert(novel.id.as_str()) {
+            return Err(ErisError::InvalidExportData(format!(
+                "novel id `{}` is not unique",
+                novel.id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_export_kind() {
+        let db = Database::new(Some(vec![]));
+        let json = serde_json::to_string(&Export {
+            kind: ExportKind::Database,
+            version: DB_VERSION,
+            data: &db,
+        })
+        .unwrap();
+        assert!(matches!(parse_export(&json), Ok(ImportData::Database(_))));
+
+        // Exports without the kind key
+        let legacy_db = serde_json::to_string(&db).unwrap();
+        assert!(matches!(parse_export(&legacy_db), Ok(ImportData::Database(_))));
+        let legacy_history = serde_json::to_string(&NovelHistory::default()).unwrap();
+        assert!(matches!(parse_export(&legacy_history), Ok(ImportData::History(_))));
+    }
+
+    #[test]
+    fn test_invalid_exports() {
+        assert!(matches!(parse_export("{"), Err(ErisError::InvalidJson(_))));
+        assert!(matches!(parse_export("[]"), Err(ErisError::UnknownExportKind)));
+        assert!(matches!(
+            parse_export(r#"{"settings": {}}"#),
+            Err(ErisError::UnknownExportKind)
+        ));
+        assert!(matches!(
+            parse_export(r#"{"eris_export": "history", "eris_version": "9.0", "items": []}"#),
+            Err(ErisError::UnsupportedExportVersion(_))
+        ));
+        assert!(matches!(
+            parse_export(r#"{"eris_export": "history", "eris_version": "1.0", "items": 1}"#),
+            Err(ErisError::InvalidExportData(_))
+        ));
+    }
+}
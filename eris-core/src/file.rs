@@ -0,0 +1,154 @@
+use crate::error::ErisError;
+use anyhow::Context;
+use chrono::Local;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Extension for the previous generation of a persisted file.
+pub const BACKUP_EXTENSION: &str = "bak";
+/// Extension for the file that is being written before it replaces the real one.
+const TEMP_EXTENSION: &str = "tmp";
+
+/// Returns `path` with `extension` appended to the whole file name.
+///
+/// Example: `data/eris.history` -> `data/eris.history.bak`
+pub fn with_appended_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    file_name.push(".");
+    file_name.push(extension);
+
+    path.with_file_name(file_name)
+}
+
+/// Path to the backup (previous generation) of the given file.
+pub fn backup_path(path: &Path) -> PathBuf {
+    with_appended_extension(path, BACKUP_EXTENSION)
+}
+
+/// Rename an unreadable file to `<path>.<reason>-<timestamp>` so it is kept for
+/// manual salvaging but never read or overwritten again.
+pub fn set_aside(path: &Path, reason: &str) {
+    if !path.exists() {
+        return;
+    }
+
+    let aside_path = with_appended_extension(path, &format!("{}-{}", reason, Local::now().timestamp()));
+    match fs::rename(path, &aside_path) {
+        Ok(_) => info!("Moved {:?} to {:?}", path, aside_path),
+        Err(e) => error!("Cannot move {:?} out of the way. {}", path, e),
+    }
+}
+
+/// Crash-safe replacement of the file in `path`.
+///
+/// The contents are written by `write_contents` into a temporary file next to `path`,
+/// which is then flushed, synced to disk and renamed over the target. The current file,
+/// if any, is kept as `<path>.bak` so a half-written file can never replace a good one.
+pub fn write_atomic<F>(path: &Path, write_contents: F) -> Result<(), anyhow::Error>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), anyhow::Error>,
+{
+    let tmp_path = with_appended_extension(path, TEMP_EXTENSION);
+
+    if let Err(e) = write_synced(&tmp_path, write_contents) {
+        // Leave no garbage behind, the original file is still intact
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    // Keep the current generation around before it gets replaced.
+    // Copy instead of rename so that there is always a valid file in `path`.
+    if path.exists() {
+        let bak_path = backup_path(path);
+        let bak_tmp_path = with_appended_extension(&bak_path, TEMP_EXTENSION);
+
+        fs::copy(path, &bak_tmp_path).context(ErisError::WriteToDisk)?;
+        File::open(&bak_tmp_path)
+            .and_then(|f| f.sync_all())
+            .context(ErisError::WriteToDisk)?;
+        fs::rename(&bak_tmp_path, &bak_path).context(ErisError::WriteToDisk)?;
+    }
+
+    fs::rename(&tmp_path, path).context(ErisError::WriteToDisk)?;
+    sync_parent_dir(path);
+
+    Ok(())
+}
+
+/// Create the file, let `write_contents` fill it and make sure it all hits the disk.
+fn write_synced<F>(path: &Path, write_contents: F) -> Result<(), anyhow::Error>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), anyhow::Error>,
+{
+    let f = File::create(path).context(ErisError::WriteToDisk)?;
+    let mut writer = BufWriter::new(f);
+
+    write_contents(&mut writer)?;
+
+    writer.flush().context(ErisError::WriteToDisk)?;
+    writer.get_ref().sync_all().context(ErisError::WriteToDisk)?;
+
+    Ok(())
+}
+
+/// Sync the directory entry so the rename itself survives a power loss.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Err(e) = File::open(parent).and_then(|dir| dir.sync_all()) {
+            warn!("Could not sync directory {:?}. {}", parent, e);
+        }
+    }
+}
+
+/// Windows does not allow opening directories as files, rename is durable enough there.
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = std::env::temp_dir().join(format!("eris-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("eris.db");
+        let write = |contents: &'static str| {
+            write_atomic(&path, |writer| {
+                writer.write_all(contents.as_bytes())?;
+                Ok(())
+            })
+        };
+
+        write("first").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        assert!(!backup_path(&path).exists());
+
+        // The current file becomes the backup
+        write("second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "first");
+
+        // A failed write leaves both as they were
+        let result = write_atomic(&path, |writer| {
+            writer.write_all(b"half")?;
+            Err(anyhow::anyhow!("failed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "first");
+
+        // No temporary file is left behind
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["eris.db", "eris.db.bak"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,16 +1,18 @@
-use crate::app::error::ErisError;
-use crate::app::novel::{Novel, NovelContentAmount};
-use crate::ui::novel_list::ListStatus;
-use crate::{data_dir, HISTORY_FILE};
+use crate::error::ErisError;
+use crate::file::{set_aside, write_atomic};
+use crate::novel::{ListStatus, Novel, NovelContentAmount};
+use crate::profile::profile_dir;
+use crate::schema::{deserialize_versioned, newer_version, serialize_versioned, Versioned, LEGACY_VERSION};
+use crate::HISTORY_FILE;
 use anyhow::Context;
-use bincode::{deserialize_from, serialize_into};
+use bincode::deserialize_from;
 use chrono::{Local, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, Read};
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub enum HistoryAction {
     NovelAdd,
     NovelDelete,
@@ -27,6 +29,12 @@ pub struct NovelHistoryItem {
     pub content: Option<NovelContentAmount>,
     pub list_status: Option<ListStatus>,
     pub named_chapter: Option<String>,
+    /// The chapter as written in the window title it was read from, like `Chapters 10-12`.
+    #[serde(default)]
+    pub chapter_label: Option<String>,
+    /// Part of the chapter that was read, 0 if the title had none.
+    #[serde(default)]
+    pub part: i32,
     pub time: i64,
 }
 
@@ -46,6 +54,8 @@ impl NovelHistoryItem {
             content,
             list_status,
             named_chapter,
+            chapter_label: None,
+            part: 0,
             time: Local::now().timestamp_millis(),
         }
     }
@@ -111,15 +121,22 @@ impl NovelHistoryItem {
     }
 
     /// Adds a new history record for when the read volume or chapter changes in the novel settings.
-    pub fn new_history_chapter_read(novel: &Novel, chapter_title: Option<String>) -> Self {
-        NovelHistoryItem::add_item(
-            novel.id.clone(),
-            novel.title.clone(),
-            HistoryAction::ContentRead,
-            Some(novel.settings.content_read.clone()),
-            None,
-            chapter_title,
-        )
+    pub fn new_history_chapter_read(
+        novel: &Novel,
+        chapter_title: Option<String>,
+        chapter_label: Option<String>,
+    ) -> Self {
+        NovelHistoryItem {
+            chapter_label,
+            ..NovelHistoryItem::add_item(
+                novel.id.clone(),
+                novel.title.clone(),
+                HistoryAction::ContentRead,
+                Some(novel.settings.content_read.clone()),
+                None,
+                chapter_title,
+            )
+        }
     }
 
     /// Returns a human readable string that depends on one or more variables.
@@ -142,10 +159,14 @@ impl NovelHistoryItem {
                         let _ = write!(content_string, "{} {}", fl!("volume"), content.volumes);
                     }
                     if content.chapters > 0.0 {
+                        let chapter = match &self.chapter_label {
+                            Some(label) => label.clone(),
+                            None => format!("{} {}", fl!("chapter"), content.chapters),
+                        };
                         if let Some(title) = &self.named_chapter {
-                            let _ = write!(content_string, "{} {} - {} ", fl!("chapter"), content.chapters, title);
+                            let _ = write!(content_string, "{} - {} ", chapter, title);
                         } else {
-                            let _ = write!(content_string, "{} {} ", fl!("chapter"), content.chapters);
+                            let _ = write!(content_string, "{} ", chapter);
                         }
                     }
                     if content.side_stories > 0 {
@@ -213,27 +234,137 @@ impl NovelHistory {
     /// Write the history data into a file.
     pub fn write_to_file(&self) -> Result<(), ErisError> {
         debug!("history:write_to_file");
-        let path = &data_dir(HISTORY_FILE);
-        let f = File::create(&path).unwrap();
-        let writer = BufWriter::new(f);
-
-        serialize_into(writer, self).context(ErisError::WriteToDisk)?;
+        let path = &profile_dir(HISTORY_FILE);
+        write_atomic(path, |writer| serialize_versioned(writer, self))?;
 
         Ok(())
     }
 
     /// Try to read the history data from a file.
+    ///
+    /// A file written by a newer Eris is kept and `ErisError::UnsupportedVersion` returned.
     pub fn open() -> Result<Self, ErisError> {
-        let path = &data_dir(HISTORY_FILE);
+        let path = &profile_dir(HISTORY_FILE);
         if path.as_path().exists() {
             let f = File::open(&path).context(ErisError::ReadFromDisk)?;
             let reader = BufReader::new(&f);
 
-            if let Ok(history) = deserialize_from(reader).context(ErisError::Unknown) {
-                return Ok(history);
+            match deserialize_versioned(reader) {
+                Ok(history) => return Ok(history),
+                Err(e) => {
+                    error!("Cannot read history file. {:#}", e);
+                    if let Some(version) = newer_version(&e) {
+                        return Err(ErisError::UnsupportedVersion(version));
+                    }
+                    // Keep the unreadable file instead of silently overwriting it later
+                    set_aside(path, "unreadable");
+                }
             }
         }
 
         Ok(NovelHistory::default())
     }
 }
+
+impl Versioned for NovelHistory {
+    const VERSION: u32 = 3;
+
+    fn migrate(version: u32, reader: &mut dyn Read) -> Result<Self, anyhow::Error> {
+        match version {
+            // Files from before the schema header have the same layout as version 1
+            LEGACY_VERSION | 1 => {
+                let v2: legacy::NovelHistoryV2 = deserialize_from::<_, legacy::NovelHistoryV1>(reader)?.into();
+                Ok(v2.into())
+            }
+            2 => Ok(deserialize_from::<_, legacy::NovelHistoryV2>(reader)?.into()),
+            _ => Err(ErisError::UnsupportedVersion(version).into()),
+        }
+    }
+}
+
+/// Frozen layouts of the history file.
+mod legacy {
+    use super::{HistoryAction, NovelHistory, NovelHistoryItem};
+    use crate::novel::{ListStatus, NovelContentAmount};
+    use serde::{Deserialize, Serialize};
+
+    /// `NovelHistoryItem` before `chapter_label` was added.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct NovelHistoryItemV1 {
+        pub novel_id: String,
+        pub novel_name: String,
+        pub action: HistoryAction,
+        pub content: Option<NovelContentAmount>,
+        pub list_status: Option<ListStatus>,
+        pub named_chapter: Option<String>,
+        pub time: i64,
+    }
+
+    /// `NovelHistory` schema version 1.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct NovelHistoryV1 {
+        pub items: Vec<NovelHistoryItemV1>,
+    }
+
+    /// `NovelHistoryItem` before `part` was added.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct NovelHistoryItemV2 {
+        pub novel_id: String,
+        pub novel_name: String,
+        pub action: HistoryAction,
+        pub content: Option<NovelContentAmount>,
+        pub list_status: Option<ListStatus>,
+        pub named_chapter: Option<String>,
+        pub chapter_label: Option<String>,
+        pub time: i64,
+    }
+
+    /// `NovelHistory` schema version 2.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct NovelHistoryV2 {
+        pub items: Vec<NovelHistoryItemV2>,
+    }
+
+    impl From<NovelHistoryV1> for NovelHistoryV2 {
+        fn from(old: NovelHistoryV1) -> Self {
+            NovelHistoryV2 {
+                items: old
+                    .items
+                    .into_iter()
+                    .map(|item| NovelHistoryItemV2 {
+                        novel_id: item.novel_id,
+                        novel_name: item.novel_name,
+                        action: item.action,
+                        content: item.content,
+                        list_status: item.list_status,
+                        named_chapter: item.named_chapter,
+                        chapter_label: None,
+                        time: item.time,
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl From<NovelHistoryV2> for NovelHistory {
+        fn from(old: NovelHistoryV2) -> Self {
+            NovelHistory {
+                items: old
+                    .items
+                    .into_iter()
+                    .map(|item| NovelHistoryItem {
+                        novel_id: item.novel_id,
+                        novel_name: item.novel_name,
+                        action: item.action,
+                        content: item.content,
+                        list_status: item.list_status,
+                        named_chapter: item.named_chapter,
+                        chapter_label: item.chapter_label,
+                        part: 0,
+                        time: item.time,
+                    })
+                    .collect(),
+            }
+        }
+    }
+}
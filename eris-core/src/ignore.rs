@@ -0,0 +1,114 @@
+//! Ignore keywords that make novel recognition skip a window title.
+//!
+//! A keyword is found anywhere in the title, ignoring case. With a `re:` prefix it is a
+//! case-insensitive regex instead. A keyword followed by ` @ <source>`, like
+//! `Comments @ Royal Road`, only skips the titles that also have the source in them,
+//! which can be a site or an app like the browser.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Text(String),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    /// The keyword as it was written.
+    keyword: String,
+    pattern: Pattern,
+    /// Lowercase source that the title must have for the rule to apply.
+    source: Option<String>,
+}
+
+/// A window title that was skipped and the keyword that skipped it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IgnoredTitle {
+    pub title: String,
+    pub keyword: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Rules for the ignore `keywords`. Empty keywords and invalid regexes are skipped.
+    pub fn new(keywords: &[String]) -> Self {
+        let rules = keywords
+            .iter()
+            .filter_map(|keyword| {
+                let (pattern, source) = match keyword.rsplit_once(" @ ") {
+                    Some((pattern, source)) => (pattern.trim(), Some(source.trim().to_lowercase())),
+                    None => (keyword.trim(), None),
+                };
+                let pattern = match pattern.strip_prefix("re:") {
+                    Some(regex) => Pattern::Regex(
+                        RegexBuilder::new(regex)
+                            .case_insensitive(true)
+                            .build()
+                            .map_err(|e| error!("Invalid ignore keyword `{}`. {}", keyword, e))
+                            .ok()?,
+                    ),
+                    None if pattern.is_empty() => return None,
+                    None => Pattern::Text(pattern.to_lowercase()),
+                };
+
+                Some(IgnoreRule {
+                    keyword: keyword.clone(),
+                    pattern,
+                    source: source.filter(|source| !source.is_empty()),
+                })
+            })
+            .collect();
+
+        IgnoreRules { rules }
+    }
+
+    /// The first keyword that skips `title`.
+    pub fn find(&self, title: &str) -> Option<&str> {
+        let lowercase = title.to_lowercase();
+
+        self.rules
+            .iter()
+            .find(|rule| {
+                let in_source = rule.source.iter().all(|source| lowercase.contains(source));
+                in_source
+                    && match &rule.pattern {
+                        Pattern::Text(text) => lowercase.contains(text),
+                        Pattern::Regex(regex) => regex.is_match(title),
+                    }
+            })
+            .map(|rule| rule.keyword.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let keywords = [
+            "Manga",
+            "",
+            r"re:^chapter \d+ - comments",
+            "Forum @ Royal Road",
+            "re:( @ Broken",
+        ];
+        let rules = IgnoreRules::new(&keywords.map(String::from));
+
+        assert_eq!(rules.find("Chapter 5 - Solo Leveling MANGA - Firefox"), Some("Manga"));
+        assert_eq!(
+            rules.find("Chapter 12 - Comments - Novel - Firefox"),
+            Some(r"re:^chapter \d+ - comments")
+        );
+        assert_eq!(rules.find("Forum - Royal Road - Firefox"), Some("Forum @ Royal Road"));
+        // Only on the source of the rule
+        assert_eq!(rules.find("Forum - Scribble Hub - Firefox"), None);
+        assert_eq!(rules.find("Chapter 12 - Novel - Royal Road"), None);
+    }
+}
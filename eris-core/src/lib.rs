@@ -0,0 +1,61 @@
+//! Novels, reading history, storage and site parsers of Eris without any GUI.
+//!
+//! The GTK application, the command-line interface and anything else that wants
+//! to work on an Eris library use this crate. `library::Library` is the entry point
+//! for making changes, it saves them and tells what happened with `LibraryEvent`s.
+
+#[macro_use]
+extern crate log;
+
+use const_format::formatcp;
+
+macro_rules! fl {
+    ($message_id:literal) => {{
+        i18n_embed_fl::fl!($crate::localize::LANGUAGE_LOADER, $message_id)
+    }};
+
+    ($message_id:literal, $($args:expr),*) => {{
+        i18n_embed_fl::fl!($crate::localize::LANGUAGE_LOADER, $message_id, $($args), *)
+    }};
+}
+
+pub mod backup;
+pub mod confirmations;
+pub mod data_location;
+pub mod database;
+pub mod dwell;
+pub mod error;
+pub mod export;
+pub mod file;
+pub mod history;
+pub mod ignore;
+pub mod library;
+pub mod localize;
+pub mod markers;
+pub mod merge;
+pub mod novel;
+pub mod parsers;
+pub mod paths;
+pub mod profile;
+pub mod progress;
+pub mod recognition;
+pub mod schema;
+pub mod storage;
+pub mod sync;
+pub mod title_rules;
+pub mod titles;
+pub mod trace;
+
+pub const DATA_DIR: &str = "data";
+pub const DB_FILE: &str = formatcp!("{DATA_DIR}/db/eris.db");
+pub const HISTORY_FILE: &str = formatcp!("{DATA_DIR}/eris.history");
+pub const SQLITE_FILE: &str = formatcp!("{DATA_DIR}/db/eris.sqlite");
+pub const BACKUP_DIR: &str = formatcp!("{DATA_DIR}/backups");
+pub const TITLE_RULES_FILE: &str = formatcp!("{DATA_DIR}/title_rules.toml");
+pub const SYNC_STATE_FILE: &str = formatcp!("{DATA_DIR}/eris.sync");
+pub const DATA_IMAGE_DIR: &str = formatcp!("{DATA_DIR}/db/images");
+pub const PROFILES_DIR: &str = formatcp!("{DATA_DIR}/profiles");
+pub const APPLICATION_ID: &str = "com.github.temeez.eris";
+
+/// How long to wait, in seconds, before the same novel can be updated from its site again.
+pub const NOVEL_UPDATE_COOLDOWN: i64 = 3600;
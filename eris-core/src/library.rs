@@ -0,0 +1,465 @@
+//! Changes to the novels and history of a library.
+//!
+//! `Library` keeps the `Database` and `NovelHistory` in memory like the application
+//! does, saves every change through the storage backend, records it in the history
+//! and the sync journal, and returns `LibraryEvent`s so a frontend knows what to refresh.
+
+use crate::database::{Database, DatabaseRecovery};
+use crate::error::ErisError;
+use crate::history::{HistoryAction, NovelHistory, NovelHistoryItem};
+use crate::merge::MergePreview;
+use crate::novel::{ListStatus, Novel, NovelContentAmount};
+use crate::parsers::novel_url;
+use crate::progress::ProgressChange;
+use crate::storage::Storage;
+use crate::sync::Journal;
+use crate::HISTORY_FILE;
+use chrono::Local;
+use std::sync::Arc;
+
+/// What changed in the library.
+#[derive(Debug, Clone)]
+pub enum LibraryEvent {
+    NovelAdded(Novel),
+    /// Details of the novel were updated, e.g. from its site.
+    NovelUpdated(Novel),
+    /// Novel was moved to another list from the `from` list.
+    NovelMoved {
+        novel: Novel,
+        from: ListStatus,
+    },
+    /// What was read of the novel changed.
+    ChapterRead(Novel),
+    NovelRemoved(Novel),
+    HistoryAdded(NovelHistoryItem),
+}
+
+pub struct Library {
+    pub db: Database,
+    pub history: NovelHistory,
+    storage: Arc<dyn Storage>,
+    journal: Option<Journal>,
+}
+
+impl Library {
+    pub fn new(storage: Arc<dyn Storage>, db: Database, history: NovelHistory) -> Self {
+        Library {
+            db,
+            history,
+            storage,
+            journal: None,
+        }
+    }
+
+    /// Read the novels and history from `storage`.
+    ///
+    /// If the novels cannot be read the library is read-only and the details
+    /// of what went wrong are returned with it.
+    pub fn open(storage: Arc<dyn Storage>) -> Result<(Self, Option<DatabaseRecovery>), ErisError> {
+        let (mut db, mut recovery) = storage.read_database();
+        let history = match storage.read_history() {
+            Ok(history) => history,
+            // Nothing is saved so the history file of the newer Eris is not overwritten
+            Err(ErisError::UnsupportedVersion(version)) => {
+                db.read_only = true;
+                let attempt = format!("{}: {}", HISTORY_FILE, ErisError::UnsupportedVersion(version));
+                recovery = Some(DatabaseRecovery::newer_version(attempt, version));
+                NovelHistory::default()
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok((Library::new(storage, db, history), recovery))
+    }
+
+    /// Also write every change to the sync `journal`.
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Write the changes to the sync `journal` from now on, or stop writing them.
+    pub fn set_journal(&mut self, journal: Option<Journal>) {
+        self.journal = journal;
+    }
+
+    pub fn storage(&self) -> Arc<dyn Storage> {
+        self.storage.clone()
+    }
+
+    /// Save the changes to `storage` from now on, e.g. after the data folder was moved.
+    pub fn set_storage(&mut self, storage: Arc<dyn Storage>) {
+        self.storage = storage;
+    }
+
+    pub fn novels(&self) -> &[Novel] {
+        self.db.novels.as_deref().unwrap_or_default()
+    }
+
+    pub fn get(&self, novel_id: &str) -> Option<&Novel> {
+        self.novels().iter().find(|n| n.id == novel_id)
+    }
+
+    /// Find a novel by its id or by its url.
+    pub fn find(&self, id_or_url: &str) -> Option<&Novel> {
+        let url = novel_url(id_or_url);
+        self.get(id_or_url)
+            .or_else(|| self.novels().iter().find(|n| n.slug.as_deref() == Some(url.as_str())))
+    }
+
+    /// Add a new `novel`, e.g. one fetched from its site.
+    pub fn add(&mut self, novel: Novel) -> Result<Vec<LibraryEvent>, anyhow::Error> {
+        self.check_writable()?;
+        let exists =
+            self.get(&novel.id).is_some() || novel.slug.as_deref().map_or(false, |url| self.find(url).is_some());
+        if exists {
+            return Err(ErisError::NovelExists(novel.id).into());
+        }
+
+        let item = NovelHistoryItem::new_history_add_novel(&novel);
+        let mut events = vec![LibraryEvent::NovelAdded(novel.clone())];
+        events.extend(self.save(novel, item)?);
+
+        Ok(events)
+    }
+
+    /// Replace the details of an existing novel with the newly fetched `novel`.
+    /// The personal settings of the existing novel are kept.
+    pub fn update(&mut self, mut novel: Novel) -> Result<Vec<LibraryEvent>, anyhow::Error> {
+        self.check_writable()?;
+        let existing = self.novel(&novel.id)?;
+        novel.settings = existing.settings;
+        novel.last_scrape = Local::now().timestamp();
+
+        let item = NovelHistoryItem::new_history_update_novel(&novel);
+        let mut events = vec![LibraryEvent::NovelUpdated(novel.clone())];
+        events.extend(self.save(novel, item)?);
+
+        Ok(events)
+    }
+
+    /// Save the changes the user made to `novel`, like its settings, without a history item.
+    pub fn edit(&mut self, novel: Novel) -> Result<Vec<LibraryEvent>, anyhow::Error> {
+        self.check_writable()?;
+        self.novel(&novel.id)?;
+        self.write(&novel)?;
+
+        Ok(vec![LibraryEvent::NovelUpdated(novel)])
+    }
+
+    /// Move the novel with `novel_id` to the `status` list.
+    pub fn move_to(&mut self, novel_id: &str, status: ListStatus) -> Result<Vec<LibraryEvent>, anyhow::Error> {
+        self.check_writable()?;
+        let mut novel = self.novel(novel_id)?;
+        let from = novel.settings.list_status;
+        if from == status {
+            return Ok(vec![]);
+        }
+        novel.settings.list_status = status;
+
+        let item = NovelHistoryItem::new_history_novel_list_change(&novel);
+        let mut events = vec![LibraryEvent::NovelMoved {
+            novel: novel.clone(),
+            from,
+        }];
+        events.extend(self.save(novel, item)?);
+
+        Ok(events)
+    }
+
+    /// Set what was read of the novel with `novel_id` to `read`.
+    pub fn mark_read(
+        &mut self,
+        novel_id: &str,
+        read: NovelContentAmount,
+        chapter_title: Option<String>,
+    ) -> Result<Vec<LibraryEvent>, anyhow::Error> {
+        self.check_writable()?;
+        let mut novel = self.novel(novel_id)?;
+        novel.settings.content_read = read;
+        novel.settings.last_read = Local::now().timestamp();
+
+        let item = NovelHistoryItem::new_history_chapter_read(&novel, chapter_title, None);
+        let mut events = vec![LibraryEvent::ChapterRead(novel.clone())];
+        events.extend(self.save(novel, item)?);
+
+        Ok(events)
+    }
+
+    /// Apply the progress `change` that `progress::decide` made for the novel with `novel_id`.
+    ///
+    /// With `manual_change` the user set the numbers, so the time the novel was last
+    /// read is kept and the lists sorted by it do not jump around.
+    pub fn read_progress(
+        &mut self,
+        novel_id: &str,
+        change: &ProgressChange,
+        manual_change: bool,
+    ) -> Result<Vec<LibraryEvent>, anyhow::Error> {
+        self.check_writable()?;
+        let mut novel = self.novel(novel_id)?;
+        let from = novel.settings.list_status;
+        let items = change.apply(&mut novel);
+        if !manual_change {
+            novel.settings.last_read = Local::now().timestamp();
+        }
+        self.write(&novel)?;
+
+        let mut events = vec![LibraryEvent::ChapterRead(novel.clone())];
+        if novel.settings.list_status != from {
+            events.push(LibraryEvent::NovelMoved { novel, from });
+        }
+        for item in items {
+            events.extend(self.add_history_item(&item, None));
+        }
+
+        Ok(events)
+    }
+
+    /// Remove the novel with `novel_id`.
+    pub fn remove(&mut self, novel_id: &str) -> Result<Vec<LibraryEvent>, anyhow::Error> {
+        self.check_writable()?;
+        let novel = self.novel(novel_id)?;
+        if let Some(novels) = &mut self.db.novels {
+            novels.retain(|n| n.id != novel.id);
+        }
+        self.storage.delete_novel(&mut self.db, &novel.id)?;
+
+        let item = NovelHistoryItem::new_history_delete_novel(&novel);
+        let mut events = vec![LibraryEvent::NovelRemoved(novel)];
+        events.extend(self.add_history_item(&item, None));
+
+        Ok(events)
+    }
+
+    /// Merge the previewed import into the library and save it.
+    pub fn apply_merge(&mut self, preview: &MergePreview) -> Result<Vec<LibraryEvent>, anyhow::Error> {
+        self.check_writable()?;
+        preview.apply(&mut self.db, &mut self.history);
+
+        if !preview.history_added.is_empty() {
+            self.storage.write_history(&self.history)?;
+        }
+        if !(preview.added.is_empty() && preview.updated.is_empty() && preview.conflicts.is_empty()) {
+            self.storage.write_database(&mut self.db)?;
+        }
+
+        let events = preview
+            .added
+            .iter()
+            .cloned()
+            .map(LibraryEvent::NovelAdded)
+            .chain(preview.changed_novels().cloned().map(LibraryEvent::NovelUpdated))
+            .chain(preview.history_added.iter().cloned().map(LibraryEvent::HistoryAdded))
+            .collect();
+
+        Ok(events)
+    }
+
+    /// Write every novel and the whole history, e.g. before the library is moved.
+    pub fn write_all(&mut self) -> Result<(), anyhow::Error> {
+        self.check_writable()?;
+        self.storage.write_database(&mut self.db)?;
+        self.storage.write_history(&self.history)
+    }
+
+    /// Changes are not saved on top of a database that could not be read.
+    fn check_writable(&self) -> Result<(), ErisError> {
+        if self.db.read_only {
+            return Err(ErisError::ReadOnlyDatabase);
+        }
+
+        Ok(())
+    }
+
+    fn novel(&self, novel_id: &str) -> Result<Novel, ErisError> {
+        self.get(novel_id)
+            .cloned()
+            .ok_or_else(|| ErisError::NovelNotFound(novel_id.to_string()))
+    }
+
+    /// Save the new or changed `novel` and record `item` in the history.
+    fn save(&mut self, novel: Novel, item: NovelHistoryItem) -> Result<Option<LibraryEvent>, anyhow::Error> {
+        self.write(&novel)?;
+
+        // Other devices need the whole novel to add or update it
+        let novel = match item.action {
+            HistoryAction::NovelAdd | HistoryAction::NovelUpdate => Some(novel),
+            _ => None,
+        };
+
+        Ok(self.add_history_item(&item, novel))
+    }
+
+    /// Save the new or changed `novel` without touching the history.
+    fn write(&mut self, novel: &Novel) -> Result<(), anyhow::Error> {
+        let novels = self.db.novels.get_or_insert_with(Vec::new);
+        match novels.iter_mut().find(|n| n.id == novel.id) {
+            Some(existing) => *existing = novel.clone(),
+            None => novels.push(novel.clone()),
+        }
+
+        self.storage.write_novel(&mut self.db, novel)
+    }
+
+    /// Record `item`, unless it is the same as the last item apart from the time.
+    ///
+    /// History is not worth failing the change for, so errors are only logged.
+    fn add_history_item(&mut self, item: &NovelHistoryItem, novel: Option<Novel>) -> Option<LibraryEvent> {
+        if let Some(last) = self.history.items.last() {
+            if last.novel_id == item.novel_id
+                && last.action == item.action
+                && last.named_chapter == item.named_chapter
+                && last.content == item.content
+            {
+                return None;
+            }
+        }
+
+        self.history.items.push(item.clone());
+        if let Err(e) = self.storage.write_history_item(&self.history, item) {
+            error!("Cannot write history. {:#}", e);
+        }
+
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.append(item, novel) {
+                error!("Cannot write to the sync journal. {:#}", e);
+            }
+        }
+
+        Some(LibraryEvent::HistoryAdded(item.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel::test_novel;
+    use crate::progress::ProgressHistory;
+
+    /// Storage that keeps nothing.
+    struct NoStorage;
+
+    impl Storage for NoStorage {
+        fn read_database(&self) -> (Database, Option<DatabaseRecovery>) {
+            (Database::default(), None)
+        }
+
+        fn write_novel(&self, _db: &mut Database, _novel: &Novel) -> Result<(), anyhow::Error> {
+            Ok(())
+        }
+
+        fn delete_novel(&self, _db: &mut Database, _novel_id: &str) -> Result<(), anyhow::Error> {
+            Ok(())
+        }
+
+        fn write_database(&self, _db: &mut Database) -> Result<(), anyhow::Error> {
+            Ok(())
+        }
+
+        fn read_history(&self) -> Result<NovelHistory, ErisError> {
+            Ok(NovelHistory::default())
+        }
+
+        fn write_history_item(&self, _history: &NovelHistory, _item: &NovelHistoryItem) -> Result<(), anyhow::Error> {
+            Ok(())
+        }
+
+        fn write_history(&self, _history: &NovelHistory) -> Result<(), anyhow::Error> {
+            Ok(())
+        }
+    }
+
+    fn novel(id: &str) -> Novel {
+        let mut novel = test_novel(id, id);
+        novel.slug = Some(format!("https://www.royalroad.com/fiction/1/{}", id));
+        novel
+    }
+
+    #[test]
+    fn test_library_changes() {
+        let (mut library, _) = Library::open(Arc::new(NoStorage)).unwrap();
+
+        let events = library.add(novel("first")).unwrap();
+        assert!(matches!(&events[..], [LibraryEvent::NovelAdded(n), LibraryEvent::HistoryAdded(_)] if n.id == "first"));
+        assert!(library.add(novel("first")).is_err());
+        assert_eq!(
+            library
+                .find("https://www.royalroad.com/fiction/1/first")
+                .map(|n| n.id.as_str()),
+            Some("first")
+        );
+
+        let events = library.move_to("first", ListStatus::Reading).unwrap();
+        assert!(matches!(
+            &events[0],
+            LibraryEvent::NovelMoved {
+                from: ListStatus::PlanToRead,
+                ..
+            }
+        ));
+        assert!(library.move_to("first", ListStatus::Reading).unwrap().is_empty());
+
+        library
+            .mark_read("first", NovelContentAmount::new(1, 12.0, 0), None)
+            .unwrap();
+        let mut updated = novel("first");
+        updated.title = "First".to_string();
+        library.update(updated).unwrap();
+        let first = library.get("first").unwrap();
+        assert_eq!(first.title, "First");
+        assert_eq!(first.settings.list_status, ListStatus::Reading);
+        assert_eq!(first.settings.content_read, NovelContentAmount::new(1, 12.0, 0));
+
+        library.remove("first").unwrap();
+        assert!(library.novels().is_empty());
+        assert_eq!(library.history.items.len(), 5);
+        assert!(library.mark_read("first", NovelContentAmount::default(), None).is_err());
+
+        library.db.read_only = true;
+        assert!(library.add(novel("second")).is_err());
+    }
+
+    #[test]
+    fn test_read_progress() {
+        let (mut library, _) = Library::open(Arc::new(NoStorage)).unwrap();
+        library.add(novel("first")).unwrap();
+        let change = ProgressChange {
+            content_read: NovelContentAmount::new(0, 3.0, 0),
+            move_to: Some(ListStatus::Reading),
+            history: vec![
+                ProgressHistory::ListChange,
+                ProgressHistory::ChapterRead {
+                    chapter_title: None,
+                    chapter_label: None,
+                    part: 0,
+                },
+            ],
+        };
+
+        let events = library.read_progress("first", &change, false).unwrap();
+        assert!(matches!(
+            &events[..],
+            [
+                LibraryEvent::ChapterRead(_),
+                LibraryEvent::NovelMoved {
+                    from: ListStatus::PlanToRead,
+                    ..
+                },
+                LibraryEvent::HistoryAdded(_),
+                LibraryEvent::HistoryAdded(_)
+            ]
+        ));
+        assert_eq!(
+            library.get("first").unwrap().settings.content_read,
+            NovelContentAmount::new(0, 3.0, 0)
+        );
+
+        // The same chapter again is not recorded twice
+        let events = library
+            .mark_read("first", NovelContentAmount::new(0, 3.0, 0), None)
+            .unwrap();
+        assert!(matches!(&events[..], [LibraryEvent::ChapterRead(_)]));
+        assert_eq!(library.history.items.len(), 3);
+    }
+}
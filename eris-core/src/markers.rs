@@ -0,0 +1,252 @@
+//! Volume, chapter and side story markers of window titles in other languages than English.
+//!
+//! A marker is a regex for the lowercase title where `<num>` stands for the number,
+//! e.g. `第<num>章`. The number can be written with ASCII or full-width digits or
+//! with CJK numerals, and volume numbers also with Roman numerals.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Digits, full-width digits or CJK numerals, with an optional decimal part for the digits.
+const NUMBER: &str = r"[0-9０-９]+(?:[.．][0-9０-９]+)?|[〇零一二两三四五六七八九十百千万]+";
+/// `NUMBER` or a Roman numeral as a word of its own.
+const VOLUME_NUMBER: &str = r"[0-9０-９]+(?:[.．][0-9０-９]+)?|[〇零一二两三四五六七八九十百千万]+|\b[ivxlcdm]+\b";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct TitleMarkers {
+    /// Language the markers are for, like `zh`. Only for telling the sets apart.
+    pub language: String,
+    pub volume: Vec<String>,
+    pub chapter: Vec<String>,
+    pub side_story: Vec<String>,
+}
+
+impl TitleMarkers {
+    fn new(language: &str, volume: &[&str], chapter: &[&str], side_story: &[&str]) -> Self {
+        let strings = |markers: &[&str]| markers.iter().map(|m| m.to_string()).collect();
+
+        TitleMarkers {
+            language: language.to_string(),
+            volume: strings(volume),
+            chapter: strings(chapter),
+            side_story: strings(side_story),
+        }
+    }
+
+    /// Marker sets used when none are set.
+    pub fn defaults() -> Vec<TitleMarkers> {
+        vec![
+            // Roman numerals, the rest of English is handled by the generic recognition
+            TitleMarkers::new("en", &[r"vol(?:ume)?\.? ?<num>"], &[], &[]),
+            TitleMarkers::new(
+                "zh",
+                &["第<num>卷"],
+                &["第<num>章", "第<num>回", "第<num>节"],
+                &["番外<num>", "番外篇<num>"],
+            ),
+            TitleMarkers::new("ja", &["第<num>巻"], &["第<num>話", "第<num>章"], &["番外編<num>"]),
+            TitleMarkers::new(
+                "ko",
+                &["제<num>권", "<num>권"],
+                &["제<num>화", "<num>화", "제<num>장"],
+                &["외전 ?<num>"],
+            ),
+            TitleMarkers::new(
+                "es",
+                &[r"volumen <num>", r"tomo <num>", r"libro <num>"],
+                &[r"cap[ií]tulo <num>", r"cap\. ?<num>"],
+                &[r"historia paralela <num>"],
+            ),
+            TitleMarkers::new(
+                "pt",
+                &[r"volume <num>", r"livro <num>"],
+                &[r"cap[ií]tulo <num>", r"cap\. ?<num>"],
+                &[r"hist[óo]ria paralela <num>"],
+            ),
+            TitleMarkers::new(
+                "de",
+                &[r"band <num>", r"buch <num>"],
+                &[r"kapitel <num>", r"kap\. ?<num>"],
+                &[r"nebengeschichte <num>"],
+            ),
+            TitleMarkers::new(
+                "fr",
+                &[r"tome <num>", r"livre <num>"],
+                &[r"chapitre <num>"],
+                &[r"histoire parall[èe]le <num>"],
+            ),
+        ]
+    }
+}
+
+/// Markers of one kind from every set, compiled. Invalid markers are logged and skipped.
+fn compile(markers: &[TitleMarkers], kind: fn(&TitleMarkers) -> &Vec<String>, number: &str) -> Vec<Regex> {
+    markers
+        .iter()
+        .flat_map(|set| kind(set).iter())
+        .filter_map(|marker| {
+            let pattern = marker.to_lowercase().replace("<num>", &format!("({})", number));
+            Regex::new(&pattern)
+                .map_err(|e| error!("Invalid title marker `{}`. {}", marker, e))
+                .ok()
+        })
+        .collect()
+}
+
+/// Number after the first of `markers` found in the lowercase `title`.
+fn find(markers: &[Regex], title: &str) -> Option<f32> {
+    markers
+        .iter()
+        .filter_map(|re| re.captures(title))
+        .find_map(|caps| parse_number(caps.get(1)?.as_str()))
+}
+
+/// Volume number in the lowercase `title`.
+pub fn find_volume(markers: &[TitleMarkers], title: &str) -> Option<i32> {
+    find(&compile(markers, |set| &set.volume, VOLUME_NUMBER), title).map(|number| number as i32)
+}
+
+/// Chapter number in the lowercase `title`.
+pub fn find_chapter(markers: &[TitleMarkers], title: &str) -> Option<f32> {
+    find(&compile(markers, |set| &set.chapter, NUMBER), title)
+}
+
+/// Side story number in the lowercase `title`.
+pub fn find_side_story(markers: &[TitleMarkers], title: &str) -> Option<i32> {
+    find(&compile(markers, |set| &set.side_story, NUMBER), title).map(|number| number as i32)
+}
+
+/// Parse a number written with ASCII or full-width digits, CJK numerals or Roman numerals.
+pub fn parse_number(text: &str) -> Option<f32> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    let ascii = ascii_digits(text);
+    if ascii.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return ascii.parse().ok();
+    }
+
+    parse_cjk(text)
+        .or_else(|| parse_roman(text))
+        .map(|number| number as f32)
+}
+
+/// Replace full-width digits and decimal points with ASCII ones.
+pub fn ascii_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap(),
+            '．' => '.',
+            _ => c,
+        })
+        .collect()
+}
+
+fn cjk_digit(c: char) -> Option<u32> {
+    "〇一二三四五六七八九"
+        .chars()
+        .position(|d| d == c)
+        .map(|i| i as u32)
+        .or(match c {
+            '零' => Some(0),
+            '两' => Some(2),
+            _ => None,
+        })
+}
+
+/// `百二十三` is 123, `一二三` is read digit by digit.
+fn parse_cjk(text: &str) -> Option<u32> {
+    if !text.chars().any(|c| "十百千万".contains(c)) {
+        return text.chars().try_fold(0, |number, c| Some(number * 10 + cjk_digit(c)?));
+    }
+
+    let (mut total, mut section, mut digit) = (0, 0, None);
+    for c in text.chars() {
+        match c {
+            '十' | '百' | '千' => {
+                let unit = match c {
+                    '十' => 10,
+                    '百' => 100,
+                    _ => 1000,
+                };
+                // `十二` is 12, without the one in front
+                section += digit.take().unwrap_or(1) * unit;
+            }
+            '万' => {
+                total += (section + digit.take().unwrap_or(0)).max(1) * 10_000;
+                section = 0;
+            }
+            _ => digit = Some(cjk_digit(c)?),
+        }
+    }
+
+    Some(total + section + digit.unwrap_or(0))
+}
+
+fn parse_roman(text: &str) -> Option<u32> {
+    let values = text
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            'i' => Some(1),
+            'v' => Some(5),
+            'x' => Some(10),
+            'l' => Some(50),
+            'c' => Some(100),
+            'd' => Some(500),
+            'm' => Some(1000),
+            _ => None,
+        })
+        .collect::<Option<Vec<u32>>>()?;
+
+    let mut number: i32 = 0;
+    for (i, value) in values.iter().enumerate() {
+        match values.get(i + 1) {
+            Some(next) if next > value => number -= *value as i32,
+            _ => number += *value as i32,
+        }
+    }
+
+    if number > 0 {
+        Some(number as u32)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("12"), Some(12.0));
+        assert_eq!(parse_number("１２．５"), Some(12.5));
+        assert_eq!(parse_number("一百二十三"), Some(123.0));
+        assert_eq!(parse_number("十二"), Some(12.0));
+        assert_eq!(parse_number("两千零五"), Some(2005.0));
+        assert_eq!(parse_number("一万二千"), Some(12000.0));
+        assert_eq!(parse_number("一二三"), Some(123.0));
+        assert_eq!(parse_number("iv"), Some(4.0));
+        assert_eq!(parse_number("XIV"), Some(14.0));
+        assert_eq!(parse_number("abc"), None);
+    }
+
+    #[test]
+    fn test_find_markers() {
+        let markers = TitleMarkers::defaults();
+
+        assert_eq!(find_chapter(&markers, "第一百二十三章 风起"), Some(123.0));
+        assert_eq!(find_volume(&markers, "第３卷"), Some(3));
+        assert_eq!(find_chapter(&markers, "제12화"), Some(12.0));
+        assert_eq!(find_side_story(&markers, "외전 3"), Some(3));
+        assert_eq!(find_chapter(&markers, "capítulo 45"), Some(45.0));
+        assert_eq!(find_chapter(&markers, "kapitel 7"), Some(7.0));
+        assert_eq!(find_volume(&markers, "volume iv"), Some(4));
+        // Roman numerals have to be words of their own
+        assert_eq!(find_volume(&markers, "volume civilization"), None);
+        assert_eq!(find_chapter(&markers, "the wandering inn"), None);
+    }
+}
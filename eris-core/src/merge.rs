@@ -0,0 +1,269 @@
+//! Merging novels and history imported from another library into the current one.
+//!
+//! Novels are matched by `id` and then by `slug`. A matched novel keeps whichever
+//! `content_read` is further along, but the rest of its personal settings are never
+//! overwritten; if those differ the novel is reported as a conflict. History items
+//! are combined and deduplicated by `(novel_id, action, time)`.
+
+use crate::database::Database;
+use crate::history::{HistoryAction, NovelHistory, NovelHistoryItem};
+use crate::novel::{Novel, NovelContentAmount};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/// Personal setting that differs between the current and the imported novel.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ConflictField {
+    ListStatus,
+    Score,
+    Rereading,
+    Notes,
+}
+
+impl ToString for ConflictField {
+    fn to_string(&self) -> String {
+        match self {
+            ConflictField::ListStatus => fl!("merge-field-list"),
+            ConflictField::Score => fl!("merge-field-score"),
+            ConflictField::Rereading => fl!("merge-field-rereading"),
+            ConflictField::Notes => fl!("merge-field-notes"),
+        }
+    }
+}
+
+/// Novel that exists in both libraries with differing personal settings.
+#[derive(Debug, Clone)]
+pub struct NovelConflict {
+    /// Current novel with the further `content_read` of the two.
+    pub merged: Novel,
+    pub fields: Vec<ConflictField>,
+}
+
+/// Everything that an import would change, shown to the user before applying it.
+#[derive(Debug, Clone, Default)]
+pub struct MergePreview {
+    /// Novels that do not exist in the current library.
+    pub added: Vec<Novel>,
+    /// Current novels with the further `content_read` from the import.
+    pub updated: Vec<Novel>,
+    pub conflicts: Vec<NovelConflict>,
+    /// Number of imported novels that would not change anything.
+    pub unchanged: usize,
+    /// History items that do not exist in the current history.
+    pub history_added: Vec<NovelHistoryItem>,
+}
+
+impl MergePreview {
+    /// Compare the `incoming` novels against the `current` ones.
+    pub fn novels(current: &[Novel], incoming: &[Novel]) -> Self {
+        let mut preview = MergePreview::default();
+
+        for novel in incoming {
+            let existing = current
+                .iter()
+                .find(|n| n.id == novel.id)
+                .or_else(|| current.iter().find(|n| n.slug.is_some() && n.slug == novel.slug));
+
+            let existing = match existing {
+                Some(existing) => existing,
+                None => {
+                    // Same novel twice in the import, add it only once
+                    if !preview.added.iter().any(|n| n.id == novel.id) {
+                        preview.added.push(novel.clone());
+                    }
+                    continue;
+                }
+            };
+
+            let mut merged = existing.clone();
+            let read_further = is_further(&novel.settings.content_read, &existing.settings.content_read);
+            if read_further {
+                merged.settings.content_read = novel.settings.content_read.clone();
+                merged.settings.last_read = merged.settings.last_read.max(novel.settings.last_read);
+            }
+
+            let fields = conflicting_fields(existing, novel);
+            if !fields.is_empty() {
+                preview.conflicts.push(NovelConflict { merged, fields });
+            } else if read_further {
+                preview.updated.push(merged);
+            } else {
+                preview.unchanged += 1;
+            }
+        }
+
+        preview
+    }
+
+    /// Compare the `incoming` history items against the `current` ones.
+    pub fn history(current: &NovelHistory, incoming: &NovelHistory) -> Self {
+        let mut seen: HashSet<(&str, &HistoryAction, i64)> = current.items.iter().map(history_key).collect();
+
+        let history_added = incoming
+            .items
+            .iter()
+            .filter(|item| seen.insert(history_key(item)))
+            .cloned()
+            .collect();
+
+        MergePreview {
+            history_added,
+            ..Default::default()
+        }
+    }
+
+    /// Nothing would change if this was applied.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.conflicts.is_empty() && self.history_added.is_empty()
+    }
+
+    /// All the novels that the merge changes, in the same order as the preview lists them.
+    pub fn changed_novels(&self) -> impl Iterator<Item = &Novel> {
+        self.updated.iter().chain(self.conflicts.iter().map(|c| &c.merged))
+    }
+
+    /// Merge the previewed changes into `db` and `history`.
+    pub fn apply(&self, db: &mut Database, history: &mut NovelHistory) {
+        let novels = db.novels.get_or_insert_with(Vec::new);
+        for merged in self.changed_novels() {
+            if let Some(novel) = novels.iter_mut().find(|n| n.id == merged.id) {
+                *novel = merged.clone();
+            }
+        }
+        novels.extend(self.added.iter().cloned());
+
+        if !self.history_added.is_empty() {
+            history.items.extend(self.history_added.iter().cloned());
+            history.items.sort_by_key(|item| item.time);
+        }
+    }
+}
+
+/// Has `a` read further than `b`. Volumes first since LN chapters restart every volume.
+fn is_further(a: &NovelContentAmount, b: &NovelContentAmount) -> bool {
+    a.volumes
+        .cmp(&b.volumes)
+        .then(a.chapters.partial_cmp(&b.chapters).unwrap_or(Ordering::Equal))
+        .then(a.side_stories.cmp(&b.side_stories))
+        == Ordering::Greater
+}
+
+/// Personal settings that differ between the two novels, ignoring what was read.
+fn conflicting_fields(current: &Novel, incoming: &Novel) -> Vec<ConflictField> {
+    let mut fields = vec![];
+    if current.settings.list_status != incoming.settings.list_status {
+        fields.push(ConflictField::ListStatus);
+    }
+    if current.settings.score != incoming.settings.score {
+        fields.push(ConflictField::Score);
+    }
+    if current.settings.rereading != incoming.settings.rereading {
+        fields.push(ConflictField::Rereading);
+    }
+    if current.settings.notes != incoming.settings.notes {
+        fields.push(ConflictField::Notes);
+    }
+
+    fields
+}
+
+fn history_key(item: &NovelHistoryItem) -> (&str, &HistoryAction, i64) {
+    (&item.novel_id, &item.action, item.time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel::{test_novel, ListStatus};
+
+    fn novel(id: &str, slug: Option<&str>, chapters: f32) -> Novel {
+        let mut novel = test_novel(id, id);
+        novel.slug = slug.map(|s| s.to_string());
+        novel.settings.content_read = NovelContentAmount::new(0, chapters, 0);
+        novel
+    }
+
+    fn history_item(novel_id: &str, action: HistoryAction, time: i64) -> NovelHistoryItem {
+        NovelHistoryItem {
+            novel_id: novel_id.to_string(),
+            novel_name: novel_id.to_string(),
+            action,
+            content: None,
+            list_status: None,
+            named_chapter: None,
+            chapter_label: None,
+            part: 0,
+            time,
+        }
+    }
+
+    #[test]
+    fn test_merge_novels() {
+        let current = vec![
+            novel("a", None, 10.0),
+            novel("b", Some("slug-b"), 5.0),
+            novel("c", None, 3.0),
+        ];
+        let mut renamed = novel("b-renamed", Some("slug-b"), 8.0);
+        renamed.title = "B".to_string();
+        let mut conflicting = novel("c", None, 1.0);
+        conflicting.settings.list_status = ListStatus::Dropped;
+        let incoming = vec![novel("a", None, 9.0), renamed, conflicting, novel("d", None, 1.0)];
+
+        let preview = MergePreview::novels(&current, &incoming);
+
+        assert_eq!(preview.unchanged, 1);
+        assert_eq!(preview.added.len(), 1);
+        assert_eq!(preview.added[0].id, "d");
+        // Matched by slug, keeps the current id and title
+        assert_eq!(preview.updated.len(), 1);
+        assert_eq!(preview.updated[0].id, "b");
+        assert_eq!(preview.updated[0].settings.content_read.chapters, 8.0);
+        // Current settings and the higher read count are kept
+        assert_eq!(preview.conflicts.len(), 1);
+        assert_eq!(preview.conflicts[0].fields, vec![ConflictField::ListStatus]);
+        assert_eq!(preview.conflicts[0].merged.settings.list_status, ListStatus::default());
+        assert_eq!(preview.conflicts[0].merged.settings.content_read.chapters, 3.0);
+
+        let mut db = Database::new(Some(current));
+        preview.apply(&mut db, &mut NovelHistory::default());
+        let novels = db.novels.unwrap();
+        assert_eq!(novels.len(), 4);
+        assert_eq!(novels[1].settings.content_read.chapters, 8.0);
+    }
+
+    #[test]
+    fn test_volume_is_further_than_chapter() {
+        let current = vec![novel("a", None, 50.0)];
+        let mut incoming = novel("a", None, 2.0);
+        incoming.settings.content_read.volumes = 1;
+
+        let preview = MergePreview::novels(&current, &[incoming]);
+        assert_eq!(preview.updated.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_history() {
+        let mut current = NovelHistory {
+            items: vec![
+                history_item("a", HistoryAction::ContentRead, 1),
+                history_item("a", HistoryAction::ContentRead, 3),
+            ],
+        };
+        let incoming = NovelHistory {
+            items: vec![
+                history_item("a", HistoryAction::ContentRead, 1),
+                history_item("a", HistoryAction::NovelUpdate, 1),
+                history_item("a", HistoryAction::ContentRead, 2),
+                history_item("a", HistoryAction::ContentRead, 2),
+            ],
+        };
+
+        let preview = MergePreview::history(&current, &incoming);
+        assert_eq!(preview.history_added.len(), 2);
+
+        preview.apply(&mut Database::default(), &mut current);
+        let times: Vec<i64> = current.items.iter().map(|i| i.time).collect();
+        assert_eq!(times, vec![1, 1, 2, 3]);
+    }
+}
@@ -1,18 +1,69 @@
-use crate::appop::parsers::NovelParser;
-use crate::ui::novel_list::ListStatus;
-use crate::utils::Resources;
+use crate::parsers::NovelParser;
 use chrono::prelude::*;
-use gdk_pixbuf::Pixbuf;
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
-use std::io::Cursor;
 use std::ops::Index;
 use std::path::PathBuf;
 use std::str::FromStr;
 use url::Url;
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[repr(i32)]
+pub enum ListStatus {
+    Reading = 0,
+    PlanToRead,
+    OnHold,
+    Completed,
+    Dropped,
+}
+
+impl Default for ListStatus {
+    fn default() -> Self {
+        ListStatus::PlanToRead
+    }
+}
+
+impl ToString for ListStatus {
+    fn to_string(&self) -> String {
+        match self {
+            ListStatus::Reading => fl!("reading"),
+            ListStatus::PlanToRead => fl!("plan-to-read"),
+            ListStatus::OnHold => fl!("on-hold"),
+            ListStatus::Completed => fl!("completed"),
+            ListStatus::Dropped => fl!("dropped"),
+        }
+    }
+}
+
+impl ListStatus {
+    pub fn vec() -> Vec<String> {
+        vec![
+            ListStatus::Reading.to_string(),
+            ListStatus::PlanToRead.to_string(),
+            ListStatus::OnHold.to_string(),
+            ListStatus::Completed.to_string(),
+            ListStatus::Dropped.to_string(),
+        ]
+    }
+
+    pub fn from_i32(i: i32) -> ListStatus {
+        match i {
+            0 => ListStatus::Reading,
+            1 => ListStatus::PlanToRead,
+            2 => ListStatus::OnHold,
+            3 => ListStatus::Completed,
+            4 => ListStatus::Dropped,
+            _ => ListStatus::Reading,
+        }
+    }
+
+    pub fn to_i32(self) -> i32 {
+        self.to_owned() as i32
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ChapterRead {
     pub volume: i32,
@@ -214,6 +265,21 @@ impl NovelContentAmount {
         }
     }
 
+    /// Read amount typed by the user, like `v2c34ss1`. A plain number is a chapter.
+    /// `None` if nothing in `value` could be understood.
+    pub fn parse(value: &str) -> Option<NovelContentAmount> {
+        if let Ok(chapters) = value.trim().parse::<f32>() {
+            return Some(NovelContentAmount::new(0, chapters, 0));
+        }
+
+        let amount = NovelContentAmount::from_string(value.to_string());
+        if amount == NovelContentAmount::default() && !value.contains('0') {
+            return None;
+        }
+
+        Some(amount)
+    }
+
     pub fn to_string(&self, pretty: bool) -> String {
         let vol_string = if self.volumes > 0 {
             format!("v{}", self.volumes)
@@ -304,12 +370,6 @@ impl Novel {
     }
 
     /// Returns the correct icon `Pixbuf` that matches the novel `status`.
-    pub fn status_pix(&self) -> Pixbuf {
-        let file = format!("icons/{}.png", self.status.to_str());
-        let resource = Resources::get(&file).unwrap().data;
-        Pixbuf::from_read(Cursor::new(resource)).expect("Cannot load pixbuf from resource.")
-    }
-
     /// Shorten original language names.
     ///
     /// E.g: English -> EN
@@ -423,13 +483,6 @@ impl Novel {
         let dt = Utc.timestamp(self.last_scrape, 0);
         return dt.format("%d %B %Y, %H:%M:%S").to_string();
     }
-
-    /// If the `Novel` has `slug` then open it in browser.
-    pub fn open_slug(&self) {
-        if let Some(slug) = self.slug.clone() {
-            if webbrowser::open(&slug).is_ok() {}
-        }
-    }
 }
 
 /// Joins a list of Strings into one `String`.
@@ -594,3 +647,31 @@ pub struct NovelFile {
     /// Cover image extension
     pub cover_ext: Option<String>,
 }
+
+/// A web novel with nothing read and no content, for tests.
+#[cfg(test)]
+pub(crate) fn test_novel(id: &str, title: &str) -> Novel {
+    Novel {
+        id: id.to_string(),
+        title: title.to_string(),
+        image: vec![],
+        alternative_titles: None,
+        description: None,
+        author: vec![],
+        artist: vec![],
+        genre: vec![],
+        tags: vec![],
+        novel_type: NovelType::WebNovel,
+        original_language: "Korean".to_string(),
+        translated: None,
+        content: NovelContentAmount::default(),
+        status: NovelStatus::Ongoing,
+        year: 2020,
+        original_publisher: vec![],
+        english_publisher: vec![],
+        source: None,
+        slug: None,
+        last_scrape: 0,
+        settings: NovelSettings::default(),
+    }
+}
@@ -4,16 +4,19 @@ use select::document::Document;
 use std::fs::File;
 use std::io;
 use std::str::FromStr;
-use ureq::Error;
+use std::time::Duration;
+use ureq::{Agent, Error};
 
-pub mod export_import_json;
 mod novelupdates;
 mod royalroad;
 mod scribblehub;
 mod webnovel;
 
-use crate::app::novel::{Novel, NovelStatus, NovelType};
-use crate::{data_dir, DATA_IMAGE_DIR};
+use crate::error::ErisError;
+use crate::novel::{Novel, NovelStatus, NovelType};
+use crate::paths::data_dir;
+use crate::profile::profile_path;
+use crate::DATA_IMAGE_DIR;
 pub use novelupdates::NovelUpdates;
 pub use royalroad::RoyalRoad;
 pub use scribblehub::ScribbleHub;
@@ -94,6 +97,45 @@ impl FromStr for NovelParser {
     }
 }
 
+/// The url that is saved as the `slug` of the novel.
+pub fn novel_url(url: &str) -> String {
+    // Add slash and the end of the url if using novelupdates.com
+    if url.contains("novelupdates.com") && !url.ends_with('/') {
+        return format!("{}/", url);
+    }
+
+    url.to_string()
+}
+
+/// Download the novel page in `url` and parse it into a `Novel` without any personal settings.
+pub fn fetch_novel(url: &str) -> Result<Novel, ErisError> {
+    let novel_parser = NovelParser::from_url(url);
+    if !novel_parser.is_supported() {
+        return Err(ErisError::UnsupportedUrl(url.to_string()));
+    }
+
+    let agent: Agent = ureq::AgentBuilder::new()
+        .timeout_read(Duration::from_secs(2))
+        .timeout_write(Duration::from_secs(2))
+        .build();
+
+    let html = match agent.get(url).call() {
+        Ok(response) => response
+            .into_string()
+            .map_err(|e| ErisError::FetchNovel(e.to_string()))?,
+        Err(Error::Status(code, _response)) => {
+            /* the server returned an unexpected status
+            code (such as 400, 500 etc) */
+            return Err(ErisError::FetchNovel(format!("Url {} returned {}", url, code)));
+        }
+        Err(e) => return Err(ErisError::FetchNovel(e.to_string())),
+    };
+
+    novel_parser
+        .parse(Document::from(html.as_str()), url)
+        .ok_or_else(|| ErisError::ParseNovel(url.to_string()))
+}
+
 pub trait ParseNovel {
     fn parse_novel(&self, slug: &str) -> Option<Novel>;
     fn generate_id(&self, title: &str) -> String {
@@ -175,7 +217,7 @@ pub trait ParseNovel {
 fn cover_image_file(url: &str, file_name: &str) -> String {
     debug!("Cover image url: {:?}", url);
 
-    let file_path_str = format!("{}/{}.jpg", DATA_IMAGE_DIR, file_name);
+    let file_path_str = format!("{}/{}.jpg", profile_path(DATA_IMAGE_DIR), file_name);
     let file_path = data_dir(&file_path_str);
 
     // If the image file does not exist then try to download it
@@ -233,3 +275,12 @@ pub fn novel_title_to_slug(novel_title: &str) -> String {
     // Slugified string from the novel title
     slug::slugify(&pre_slug.replace('_', ""))
 }
+
+/// Caplitaze the `str` and return it as `String`.
+pub(crate) fn capitalize_str(s: &str) -> String {
+    let mut c = s.chars();
+    match c.next() {
+        None => String::new(),
+        Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+    }
+}
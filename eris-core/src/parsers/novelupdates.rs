@@ -1,5 +1,5 @@
-use crate::app::novel::{Novel, NovelContentAmount, NovelSettings, NovelStatus, NovelType};
-use crate::appop::parsers::{cover_image_file, numeric_from_str, ParseNovel};
+use crate::novel::{Novel, NovelContentAmount, NovelSettings, NovelStatus, NovelType};
+use crate::parsers::{cover_image_file, numeric_from_str, ParseNovel};
 use chrono::Local;
 use select::document::Document;
 use select::predicate::{Attr, Class, Name, Predicate};
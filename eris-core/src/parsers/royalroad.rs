@@ -1,5 +1,5 @@
-use crate::app::novel::{Novel, NovelContentAmount, NovelSettings, NovelType};
-use crate::appop::parsers::{cover_image_file, ParseNovel};
+use crate::novel::{Novel, NovelContentAmount, NovelSettings, NovelType};
+use crate::parsers::{cover_image_file, ParseNovel};
 use chrono::{Datelike, Local, NaiveDateTime};
 use select::document::Document;
 use select::predicate::{Attr, Class, Name, Predicate};
@@ -175,7 +175,7 @@ impl ParseNovel for RoyalRoad {
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
-    use crate::appop::parsers::NovelParser;
+    use crate::parsers::NovelParser;
     use std::fs;
     use std::path::Path;
 
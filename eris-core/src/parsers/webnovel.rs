@@ -1,6 +1,6 @@
-use crate::app::novel::{Novel, NovelContentAmount, NovelSettings, NovelStatus, NovelType};
-use crate::appop::parsers::{cover_image_file, ParseNovel};
-use crate::utils::capitalize_str;
+use crate::novel::{Novel, NovelContentAmount, NovelSettings, NovelStatus, NovelType};
+use crate::parsers::capitalize_str;
+use crate::parsers::{cover_image_file, ParseNovel};
 use chrono::{Datelike, Local, NaiveDateTime};
 use regex::Regex;
 use select::document::Document;
@@ -227,7 +227,7 @@ impl ParseNovel for Webnovel {
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
-    use crate::appop::parsers::NovelParser;
+    use crate::parsers::NovelParser;
     use std::fs;
     use std::path::Path;
 
@@ -0,0 +1,122 @@
+//! Where the data directory of the library is.
+
+use crate::DATA_DIR;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use std::env::current_exe;
+use std::path::{Path, PathBuf};
+
+pub fn working_dir(file: &str) -> String {
+    #[cfg(debug_assertions)]
+    let pop_count = 3;
+
+    #[cfg(all(not(debug_assertions), target_os = "windows"))]
+    let pop_count = 2;
+
+    #[cfg(all(not(debug_assertions), target_os = "linux"))]
+    let pop_count = 1;
+
+    #[cfg(all(not(debug_assertions), target_os = "macos"))]
+    let pop_count = 1;
+
+    let dir = match current_exe() {
+        Ok(mut path) => {
+            for _ in 0..pop_count {
+                path.pop();
+            }
+            path
+        }
+        Err(e) => {
+            // Is this even possible?
+            error!("Could not get current_exe path. {:?}", e);
+            PathBuf::new()
+        }
+    };
+
+    if let Some(filepath) = dir.join(file).to_str() {
+        return filepath.to_string();
+    }
+
+    "".to_string()
+}
+
+/// File in the default data directory that points to where the library was moved.
+pub const DATA_DIR_LOCATION_FILE: &str = "eris.location";
+/// Environment variable that overrides the data directory.
+pub const DATA_DIR_ENV: &str = "ERIS_DATA_DIR";
+
+/// Where the data directory in use came from.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DataDirSource {
+    /// `--data-dir` or `--portable` command-line flag.
+    Flag,
+    /// `ERIS_DATA_DIR` environment variable.
+    Env,
+    /// Library was moved here, see `DATA_DIR_LOCATION_FILE`.
+    Moved,
+    Default,
+}
+
+static DATA_DIR_PATH: Lazy<RwLock<Option<(PathBuf, DataDirSource)>>> = Lazy::new(|| RwLock::new(None));
+
+/// Use `dir` as the data directory from now on.
+pub fn set_data_dir(dir: PathBuf, source: DataDirSource) {
+    *DATA_DIR_PATH.write() = Some((dir, source));
+}
+
+/// The data directory in use and where it came from.
+///
+/// Resolved on first use from, in order, the `ERIS_DATA_DIR` environment variable,
+/// the location file of a moved library and the default data directory.
+pub fn data_dir_source() -> (PathBuf, DataDirSource) {
+    if let Some(data_dir) = DATA_DIR_PATH.read().clone() {
+        return data_dir;
+    }
+
+    let resolved = if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        (PathBuf::from(dir), DataDirSource::Env)
+    } else if let Ok(dir) = std::fs::read_to_string(default_data_dir().join(DATA_DIR_LOCATION_FILE)) {
+        (PathBuf::from(dir.trim()), DataDirSource::Moved)
+    } else {
+        (default_data_dir(), DataDirSource::Default)
+    };
+    DATA_DIR_PATH.write().get_or_insert(resolved).clone()
+}
+
+/// Full path of `file`, given relative to the working directory as `data/...`,
+/// in the data directory in use.
+pub fn data_dir(file: &str) -> PathBuf {
+    let (dir, _) = data_dir_source();
+
+    match Path::new(file).strip_prefix(DATA_DIR) {
+        Ok(rest) if rest.as_os_str().is_empty() => dir,
+        Ok(rest) => dir.join(rest),
+        Err(_) => dir.join(file),
+    }
+}
+
+/// The `data` directory next to the executable.
+pub fn portable_data_dir() -> PathBuf {
+    PathBuf::from(working_dir(DATA_DIR))
+}
+
+#[cfg(debug_assertions)]
+pub fn default_data_dir() -> PathBuf {
+    portable_data_dir()
+}
+
+/// The `data` directory next to the executable if it exists,
+/// otherwise the OS specific data directory.
+#[cfg(not(debug_assertions))]
+pub fn default_data_dir() -> PathBuf {
+    // Use local data dir if it exists
+    if portable_data_dir().exists() {
+        return portable_data_dir();
+    }
+    // Otherwise use OS specific "data" directory
+    if let Some(dir) = dirs::data_dir() {
+        return dir.join("Eris").join(DATA_DIR);
+    }
+
+    PathBuf::new()
+}
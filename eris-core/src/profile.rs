@@ -0,0 +1,112 @@
+//! Named library profiles.
+//!
+//! Every profile has its own novels, history, images, backups and settings in
+//! `data/profiles/<name>/`, laid out the same way as the `data` directory. The
+//! default profile is the `data` directory itself so existing libraries keep working.
+//!
+//! The profile is chosen once on startup and switching to another one restarts Eris.
+
+use crate::paths::{data_dir, data_dir_source, DataDirSource};
+use crate::{APPLICATION_ID, DATA_DIR, PROFILES_DIR};
+use once_cell::sync::OnceCell;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Name that always refers to the default profile.
+pub const DEFAULT_PROFILE: &str = "default";
+
+static PROFILE: OnceCell<Option<String>> = OnceCell::new();
+
+/// Use the `name` profile for the rest of the run. Only the first call has any effect.
+pub fn set_profile(name: Option<&str>) {
+    let _ = PROFILE.set(name.and_then(profile_name));
+}
+
+/// Name of the profile in use, `None` for the default one.
+pub fn current_profile() -> Option<&'static str> {
+    PROFILE.get().and_then(|p| p.as_deref())
+}
+
+/// Turn user input into a name usable as a directory, `None` if it means the default profile.
+pub fn profile_name(name: &str) -> Option<String> {
+    let name = slug::slugify(name);
+    if name.is_empty() || name == DEFAULT_PROFILE {
+        return None;
+    }
+
+    Some(name)
+}
+
+/// `file` relative to the directory of the current profile, e.g. `data/eris.conf`
+/// becomes `data/profiles/<name>/eris.conf`.
+pub fn profile_path(file: &str) -> String {
+    path_in_profile(file, current_profile())
+}
+
+/// Full path of `file` in the directory of the current profile.
+pub fn profile_dir(file: &str) -> PathBuf {
+    data_dir(&profile_path(file))
+}
+
+fn path_in_profile(file: &str, profile: Option<&str>) -> String {
+    match (profile, file.strip_prefix(DATA_DIR)) {
+        (Some(name), Some(rest)) => format!("{}/{}{}", PROFILES_DIR, name, rest),
+        _ => file.to_string(),
+    }
+}
+
+/// Names of all the profiles besides the default one, sorted.
+pub fn list_profiles() -> Vec<String> {
+    let entries = match fs::read_dir(data_dir(PROFILES_DIR)) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut profiles: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| profile_name(name).as_deref() == Some(name.as_str()))
+        .collect();
+    profiles.sort();
+
+    profiles
+}
+
+/// Every profile is its own application so they can be open at the same time.
+pub fn application_id() -> String {
+    match current_profile() {
+        Some(name) => format!("{}.profile_{}", APPLICATION_ID, name.replace('-', "_")),
+        None => APPLICATION_ID.to_string(),
+    }
+}
+
+/// Start another Eris with the `name` profile in the same data directory.
+pub fn launch_profile(name: Option<&str>) -> std::io::Result<()> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command.arg("--profile").arg(name.unwrap_or(DEFAULT_PROFILE));
+    if let (data_dir, DataDirSource::Flag) = data_dir_source() {
+        command.arg("--data-dir").arg(data_dir);
+    }
+    command.spawn()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_paths() {
+        assert_eq!(path_in_profile(crate::HISTORY_FILE, None), crate::HISTORY_FILE);
+        assert_eq!(
+            path_in_profile(crate::DATA_IMAGE_DIR, Some("team-queue")),
+            "data/profiles/team-queue/db/images"
+        );
+        assert_eq!(profile_name("Team Queue").as_deref(), Some("team-queue"));
+        assert_eq!(profile_name("Default"), None);
+        assert_eq!(profile_name(" "), None);
+    }
+}
@@ -0,0 +1,498 @@
+//! Rules for how reading a chapter changes what has been read of a novel.
+//!
+//! `decide` only looks at the novel, what was recognized and the history, so the
+//! frontends can apply the `ProgressDecision` to their own lists however they like.
+
+use crate::history::{HistoryAction, NovelHistory, NovelHistoryItem};
+use crate::novel::{ListStatus, Novel, NovelContentAmount, NovelStatus};
+use crate::recognition::NovelRecognitionData;
+use serde::Serialize;
+
+/// Settings the progress rules depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ProgressRules {
+    /// Count the chapter before the one being read as read.
+    pub read_previous: bool,
+    /// Allow moving novels that are still ongoing to the completed list.
+    pub autocomplete_ongoing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ProgressDecision {
+    /// Nothing to change, e.g. the chapter has already been read.
+    Unchanged,
+    Changed(ProgressChange),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressChange {
+    /// What has been read of the novel after the change.
+    pub content_read: NovelContentAmount,
+    /// List the novel should be moved to.
+    pub move_to: Option<ListStatus>,
+    /// What should be recorded in the history, in order.
+    pub history: Vec<ProgressHistory>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ProgressHistory {
+    ListChange,
+    ChapterRead {
+        chapter_title: Option<String>,
+        chapter_label: Option<String>,
+        part: i32,
+    },
+}
+
+impl ProgressChange {
+    /// Change the `novel` and return the history items to record.
+    pub fn apply(&self, novel: &mut Novel) -> Vec<NovelHistoryItem> {
+        novel.settings.content_read = self.content_read.clone();
+        if let Some(status) = self.move_to {
+            novel.settings.list_status = status;
+        }
+
+        self.history
+            .iter()
+            .map(|history| match history {
+                ProgressHistory::ListChange => NovelHistoryItem::new_history_novel_list_change(novel),
+                ProgressHistory::ChapterRead {
+                    chapter_title,
+                    chapter_label,
+                    part,
+                } => NovelHistoryItem {
+                    part: *part,
+                    ..NovelHistoryItem::new_history_chapter_read(novel, chapter_title.clone(), chapter_label.clone())
+                },
+            })
+            .collect()
+    }
+}
+
+/// Decide how reading what is in `data` changes the progress of the `novel`.
+///
+/// With `manual_change` the numbers in `data` were set by the user, so they are
+/// taken as they are, even if they go backwards, and the novel is not moved to
+/// the reading list.
+pub fn decide(
+    novel: &Novel,
+    data: &NovelRecognitionData,
+    history: &NovelHistory,
+    rules: ProgressRules,
+    manual_change: bool,
+) -> ProgressDecision {
+    let read = &novel.settings.content_read;
+
+    // Chapter with this title has already been read
+    if let Some(chapter_title) = &data.chapter_title {
+        if let Some(history_item) = history.find_chapter_title(chapter_title) {
+            if history_item.novel_id == novel.id {
+                return ProgressDecision::Unchanged;
+            }
+        }
+    }
+
+    let read_modifier = if rules.read_previous { 1.0 } else { 0.0 };
+
+    let mut chapters = if manual_change {
+        data.chapter
+    } else if data.chapter == 0.0 && data.reading && data.chapter_title.is_some() {
+        // Something is being read but no valid chapter number was found
+        read.chapters + 1.0
+    } else {
+        data.chapter - read_modifier
+    };
+    chapters = chapters.max(0.0);
+
+    let mut side_stories = if manual_change {
+        data.side_story
+    } else {
+        data.side_story - read_modifier as i32
+    };
+    side_stories = side_stories.max(0);
+
+    let is_finished = matches!(&novel.status, NovelStatus::Completed | NovelStatus::Abandoned);
+
+    // Chapters past the last one of a finished novel with unread side stories
+    // are assumed to be the side stories
+    if is_finished
+        && chapters > novel.content.chapters
+        && novel.content.side_stories > 0
+        && read.side_stories < novel.content.side_stories
+    {
+        side_stories = (chapters - novel.content.chapters) as i32;
+        chapters = novel.content.chapters;
+    }
+
+    // A later part of the chapter that was read last, like `Part 12` after `Part 2`,
+    // is progress even though the chapter number stays the same
+    let later_part = !manual_change
+        && !rules.read_previous
+        && data.part > 0
+        && chapters == read.chapters
+        && data.part > last_read_part(novel, history);
+
+    if !manual_change
+        && !later_part
+        && read.chapters >= chapters
+        && read.volumes >= data.volume
+        && read.side_stories >= side_stories
+    {
+        debug!("Volume/Chapter/Side stories read count was too low!");
+        return ProgressDecision::Unchanged;
+    }
+
+    // Automation never goes backwards in case the title was recognized wrong
+    let content_read = if manual_change {
+        NovelContentAmount::new(data.volume, chapters, side_stories)
+    } else {
+        NovelContentAmount::new(
+            read.volumes.max(data.volume),
+            read.chapters.max(chapters),
+            read.side_stories.max(side_stories),
+        )
+    };
+
+    let can_complete = is_finished || rules.autocomplete_ongoing;
+    let is_completed = data.volume >= novel.content.volumes
+        && chapters >= novel.content.chapters
+        && novel.content.chapters > 0.0
+        && side_stories >= novel.content.side_stories
+        && can_complete;
+
+    let list_status = novel.settings.list_status;
+    let move_to = if list_status == ListStatus::PlanToRead && !manual_change {
+        Some(ListStatus::Reading)
+    } else if list_status != ListStatus::Completed && is_completed {
+        Some(ListStatus::Completed)
+    } else {
+        None
+    };
+
+    let mut history = vec![];
+    if move_to.is_some() {
+        history.push(ProgressHistory::ListChange);
+    }
+    if content_read.chapters > 0.0 || content_read.side_stories > 0 || content_read.volumes > 0 {
+        history.push(ProgressHistory::ChapterRead {
+            chapter_title: data.chapter_title.clone(),
+            chapter_label: data.chapter_label.clone(),
+            part: data.part,
+        });
+    }
+
+    ProgressDecision::Changed(ProgressChange {
+        content_read,
+        move_to,
+        history,
+    })
+}
+
+/// Part of the chapter the `novel` was last read to, 0 if it had none.
+fn last_read_part(novel: &Novel, history: &NovelHistory) -> i32 {
+    history
+        .items
+        .iter()
+        .filter(|item| item.novel_id == novel.id && item.action == HistoryAction::ContentRead)
+        .max_by_key(|item| item.time)
+        .filter(|item| {
+            item.content.as_ref().map(|content| content.chapters) == Some(novel.settings.content_read.chapters)
+        })
+        .map_or(0, |item| item.part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel::test_novel;
+
+    struct Case {
+        name: &'static str,
+        status: NovelStatus,
+        /// Volumes, chapters and side stories of the novel.
+        content: (i32, f32, i32),
+        list: ListStatus,
+        read: (i32, f32, i32),
+        /// Volume, chapter and side story recognized from the title.
+        data: (i32, f32, i32),
+        part: i32,
+        chapter_title: Option<&'static str>,
+        rules: ProgressRules,
+        manual_change: bool,
+        /// New read amount and the list to move to, `None` if unchanged.
+        expected: Option<((i32, f32, i32), Option<ListStatus>)>,
+    }
+
+    const CASE: Case = Case {
+        name: "",
+        status: NovelStatus::Ongoing,
+        content: (0, 100.0, 0),
+        list: ListStatus::Reading,
+        read: (0, 0.0, 0),
+        data: (0, 0.0, 0),
+        part: 0,
+        chapter_title: None,
+        rules: ProgressRules {
+            read_previous: false,
+            autocomplete_ongoing: false,
+        },
+        manual_change: false,
+        expected: None,
+    };
+
+    const READ_PREVIOUS: ProgressRules = ProgressRules {
+        read_previous: true,
+        autocomplete_ongoing: false,
+    };
+
+    fn novel(case: &Case) -> Novel {
+        let amount = |(volumes, chapters, side_stories)| NovelContentAmount::new(volumes, chapters, side_stories);
+        let mut novel = test_novel("novel", "Novel");
+        novel.content = amount(case.content);
+        novel.status = case.status.clone();
+        novel.settings.list_status = case.list;
+        novel.settings.content_read = amount(case.read);
+        novel
+    }
+
+    #[test]
+    fn test_decide() {
+        let cases = [
+            Case {
+                name: "current chapter is read",
+                list: ListStatus::PlanToRead,
+                data: (0, 5.0, 0),
+                expected: Some(((0, 5.0, 0), Some(ListStatus::Reading))),
+                ..CASE
+            },
+            Case {
+                name: "previous chapter is read",
+                read: (0, 2.0, 0),
+                data: (0, 5.0, 0),
+                rules: READ_PREVIOUS,
+                expected: Some(((0, 4.0, 0), None)),
+                ..CASE
+            },
+            Case {
+                name: "previous of the first chapter",
+                data: (0, 0.5, 0),
+                rules: READ_PREVIOUS,
+                expected: None,
+                ..CASE
+            },
+            Case {
+                name: "never goes backwards",
+                read: (2, 10.0, 0),
+                data: (1, 5.0, 0),
+                expected: None,
+                ..CASE
+            },
+            Case {
+                name: "newer volume keeps the chapters",
+                read: (1, 10.0, 0),
+                data: (2, 1.0, 0),
+                expected: Some(((2, 10.0, 0), None)),
+                ..CASE
+            },
+            Case {
+                name: "manual change goes backwards",
+                list: ListStatus::PlanToRead,
+                read: (2, 10.0, 1),
+                data: (0, 5.0, 0),
+                manual_change: true,
+                expected: Some(((0, 5.0, 0), None)),
+                ..CASE
+            },
+            Case {
+                name: "reading without a chapter number",
+                read: (0, 7.0, 0),
+                chapter_title: Some("The Beginning"),
+                expected: Some(((0, 8.0, 0), None)),
+                ..CASE
+            },
+            Case {
+                name: "chapter title already read",
+                read: (0, 7.0, 0),
+                data: (0, 8.0, 0),
+                chapter_title: Some("Already Read"),
+                expected: None,
+                ..CASE
+            },
+            Case {
+                name: "later part of the chapter read last",
+                read: (0, 12.0, 0),
+                data: (0, 12.0, 0),
+                part: 12,
+                expected: Some(((0, 12.0, 0), None)),
+                ..CASE
+            },
+            Case {
+                name: "earlier part of the chapter read last",
+                read: (0, 12.0, 0),
+                data: (0, 12.0, 0),
+                part: 1,
+                expected: None,
+                ..CASE
+            },
+            Case {
+                name: "chapters past the end are side stories",
+                status: NovelStatus::Completed,
+                content: (0, 100.0, 3),
+                read: (0, 100.0, 0),
+                data: (0, 102.0, 0),
+                expected: Some(((0, 100.0, 2), None)),
+                ..CASE
+            },
+            Case {
+                name: "ongoing novel has no side stories to guess",
+                content: (0, 100.0, 3),
+                read: (0, 100.0, 0),
+                data: (0, 102.0, 0),
+                expected: Some(((0, 102.0, 0), None)),
+                ..CASE
+            },
+            Case {
+                name: "last chapter completes",
+                status: NovelStatus::Completed,
+                content: (0, 10.0, 0),
+                read: (0, 9.0, 0),
+                data: (0, 10.0, 0),
+                expected: Some(((0, 10.0, 0), Some(ListStatus::Completed))),
+                ..CASE
+            },
+            Case {
+                name: "last side story completes",
+                status: NovelStatus::Abandoned,
+                content: (0, 10.0, 2),
+                read: (0, 10.0, 1),
+                data: (0, 12.0, 0),
+                expected: Some(((0, 10.0, 2), Some(ListStatus::Completed))),
+                ..CASE
+            },
+            Case {
+                name: "ongoing novel is not completed",
+                content: (0, 10.0, 0),
+                read: (0, 9.0, 0),
+                data: (0, 10.0, 0),
+                expected: Some(((0, 10.0, 0), None)),
+                ..CASE
+            },
+            Case {
+                name: "ongoing novel is completed when allowed",
+                content: (0, 10.0, 0),
+                read: (0, 9.0, 0),
+                data: (0, 10.0, 0),
+                rules: ProgressRules {
+                    read_previous: false,
+                    autocomplete_ongoing: true,
+                },
+                expected: Some(((0, 10.0, 0), Some(ListStatus::Completed))),
+                ..CASE
+            },
+            Case {
+                name: "novel without chapters is not completed",
+                status: NovelStatus::Completed,
+                content: (2, 0.0, 0),
+                data: (2, 0.0, 0),
+                expected: Some(((2, 0.0, 0), None)),
+                ..CASE
+            },
+            Case {
+                name: "plan to read moves to reading before completed",
+                status: NovelStatus::Completed,
+                content: (0, 10.0, 0),
+                list: ListStatus::PlanToRead,
+                data: (0, 10.0, 0),
+                expected: Some(((0, 10.0, 0), Some(ListStatus::Reading))),
+                ..CASE
+            },
+            Case {
+                name: "manual change completes",
+                status: NovelStatus::Completed,
+                content: (0, 10.0, 0),
+                list: ListStatus::OnHold,
+                data: (0, 10.0, 0),
+                manual_change: true,
+                expected: Some(((0, 10.0, 0), Some(ListStatus::Completed))),
+                ..CASE
+            },
+        ];
+
+        let mut history = NovelHistory::default();
+        history.items.push(NovelHistoryItem::new_history_chapter_read(
+            &novel(&CASE),
+            Some("Already Read".to_string()),
+            None,
+        ));
+        // Part 2 of chapter 12 was read last
+        history.items.push(NovelHistoryItem {
+            part: 2,
+            ..NovelHistoryItem::new_history_chapter_read(
+                &novel(&Case {
+                    read: (0, 12.0, 0),
+                    ..CASE
+                }),
+                None,
+                None,
+            )
+        });
+
+        for case in cases {
+            let mut data = NovelRecognitionData::new(
+                case.data.0,
+                case.data.1,
+                case.data.2,
+                case.chapter_title.map(|s| s.to_string()),
+                String::new(),
+                true,
+            );
+            data.part = case.part;
+            let decision = decide(&novel(&case), &data, &history, case.rules, case.manual_change);
+
+            let expected = match case.expected {
+                Some(((volumes, chapters, side_stories), move_to)) => {
+                    let mut history = vec![];
+                    if move_to.is_some() {
+                        history.push(ProgressHistory::ListChange);
+                    }
+                    history.push(ProgressHistory::ChapterRead {
+                        chapter_title: data.chapter_title.clone(),
+                        chapter_label: None,
+                        part: data.part,
+                    });
+                    ProgressDecision::Changed(ProgressChange {
+                        content_read: NovelContentAmount::new(volumes, chapters, side_stories),
+                        move_to,
+                        history,
+                    })
+                }
+                None => ProgressDecision::Unchanged,
+            };
+            assert_eq!(decision, expected, "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut novel = novel(&Case {
+            list: ListStatus::PlanToRead,
+            ..CASE
+        });
+        let mut data = NovelRecognitionData::new(0, 3.0, 0, Some("Three".to_string()), String::new(), true);
+        data.chapter_label = Some("Ch. 2 & 3".to_string());
+
+        let decision = decide(&novel, &data, &NovelHistory::default(), ProgressRules::default(), false);
+        let change = match decision {
+            ProgressDecision::Changed(change) => change,
+            ProgressDecision::Unchanged => panic!("progress did not change"),
+        };
+        let items = change.apply(&mut novel);
+
+        assert_eq!(novel.settings.list_status, ListStatus::Reading);
+        assert_eq!(novel.settings.content_read, NovelContentAmount::new(0, 3.0, 0));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].list_status, Some(ListStatus::Reading));
+        assert_eq!(items[1].named_chapter.as_deref(), Some("Three"));
+        assert_eq!(items[1].chapter_label.as_deref(), Some("Ch. 2 & 3"));
+    }
+}
@@ -0,0 +1,592 @@
+//! Guessing the novel and chapter from the title of a browser or reader window.
+
+use crate::markers::{ascii_digits, find_chapter, find_side_story, find_volume, TitleMarkers};
+use crate::novel::Novel;
+use crate::title_rules::TitleRules;
+use ngrammatic::{CorpusBuilder, Pad};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Confidence a match needs to count chapters without asking, unless set otherwise.
+pub const AUTO_CONFIDENCE: f32 = 0.9;
+/// Matches below this confidence are not matches at all.
+pub const MIN_CONFIDENCE: f32 = 0.5;
+/// Confidence of a window title that starts with the words of a novel title or keyword.
+const PREFIX_CONFIDENCE: f32 = 0.95;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NovelRecognitionData {
+    pub volume: i32,
+    pub chapter: f32,
+    pub side_story: i32,
+    /// Part of the chapter, 0 if the title has none.
+    pub part: i32,
+    pub chapter_title: Option<String>,
+    /// The chapter as written in the title, like `Ch. 45 & 46`.
+    pub chapter_label: Option<String>,
+    pub source: String,
+    pub reading: bool,
+}
+
+impl NovelRecognitionData {
+    pub fn new(
+        volume: i32,
+        chapter: f32,
+        side_story: i32,
+        chapter_title: Option<String>,
+        source: String,
+        reading: bool,
+    ) -> Self {
+        NovelRecognitionData {
+            volume,
+            chapter,
+            side_story,
+            part: 0,
+            chapter_title,
+            chapter_label: None,
+            source,
+            reading,
+        }
+    }
+}
+
+/// Tries to figure out if a chapter is being read currently
+fn is_reading_chapter(strings: &[&str]) -> bool {
+    // Assumed minimum amount of strings in the list
+    // when reading a chapter
+    let strings_len: Vec<(&str, usize)> = vec![("WuxiaWorld", 3), ("BoxNovel", 3), ("Royal Road", 4)];
+
+    for (key, value) in strings_len.iter() {
+        if strings.iter().any(|&s| s.to_lowercase().contains(&key.to_lowercase())) {
+            return &strings.len() >= value;
+        }
+    }
+
+    // If above does not return `true` then check if the title has
+    // the word `chapter` in it.
+    let patterns = ["chapter", "ch. ", "ch ", "ch-"];
+    for pattern in patterns {
+        if strings.iter().any(|&s| s.to_lowercase().contains(pattern)) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Try to parse the novel name from the split title strings.
+///
+/// If `found_chapter` is true then use 0 as the position
+/// for novel name in `strings`.
+pub fn extract_novel_name_from_title(title_strings: &[&str]) -> String {
+    // Vector of tuples for finding the novel name
+    // -> Website name, position in the list
+    // Sites that have the novel name or such as the first item
+    // do not need to be added here
+    let novel_name_title_pos: Vec<(&str, usize)> = vec![("Bad Reader", 1), ("Royal Road", 1)];
+
+    // If perhaps reading a chapter then the novel name is likely not the
+    // first item in the list, otherwise it likely is
+    if is_reading_chapter(title_strings) {
+        for (source, pos) in novel_name_title_pos.iter() {
+            if title_strings.iter().any(|&s| s.contains(source)) {
+                return title_strings[*pos].to_string();
+            }
+        }
+    }
+
+    // Decent default
+    // try the first item in the title string
+    // if there was no match
+    title_strings[0].to_string()
+}
+
+pub fn extract_source_from_title(title_strings: &[&str]) -> String {
+    // Position (from end) of the source (website name) based on the browser being used
+    let position_by_browser = vec![("firefox", 1), ("google", 1), ("opera", 1), ("edge", 2), ("brave", 1)];
+
+    for (browser, pos) in position_by_browser.iter() {
+        if title_strings.iter().any(|&s| s.to_lowercase().contains(browser)) {
+            // If the position is 1 then get the second last item
+            // e.g:
+            //                       --V--
+            // [Foo, Bar, Thing, 12, Source, Browser]
+            let mut safe_pos = (title_strings.len() - 1 - *pos) as i32;
+            // Dirty? way to keep the usize above zero to avoid
+            // `index out of bounds: the len is 2 but the index is 18446744073709551615`
+            if safe_pos < 0 {
+                safe_pos = 0;
+            }
+            return title_strings[safe_pos as usize].to_string();
+        }
+    }
+
+    // Try to return the last item in the list of strings as source
+    if let Some(last) = title_strings.last() {
+        return last.to_string();
+    }
+
+    "?".to_string()
+}
+
+/// Try to get the volume/chapter/part number(s) from the split title.
+///
+/// A range of chapters like `Chapter 120-122` or `Ch. 45 & 46` counts as its last
+/// chapter. A part, like `Part 12` or `Pt. 3`, is kept apart from the chapter number.
+/// Numbers the English patterns do not find are looked for with the `markers` of other
+/// languages.
+pub fn extract_novel_data_from_title(strings: &[&str], markers: &[TitleMarkers]) -> NovelRecognitionData {
+    let mut ignore_part = false;
+    let mut novel_recognition_data = NovelRecognitionData::new(0, 0.0, 0, None, "Source".to_string(), false);
+
+    let volume_res = [
+        r"v(?:ol)?(?:ume)?[\.:;\-_]?\s?(\d+)", // ol or olume or . or : or ; or - or _ or space after `v`
+    ];
+
+    // h or hapter or hapters and . or : or ; or - or _ or space after `c`, then the chapter,
+    // maybe the last chapter of a range and maybe the part, e.g. `chapters 10-12 (part 2)`
+    let chapter_re = Regex::new(
+        r"(?i)c(?:h)?(?:apter)?s?[\.:;\-_]?\s?(\d+(?:\.\d+)?)(?:\s*(?:[-~&+–—]|and|to)\s*(?:c(?:h)?(?:apter)?s?[\.:;\-_]?\s?)?(\d+(?:\.\d+)?))?(?:.*?\b(?:part|pt)\.?\s?(\d+)\)?)?",
+    )
+    .unwrap();
+
+    let sidestory_res = [
+        r"extra.*?[\.:;\-_story|chapter]\s?[\(]?(\d+)", // e.g: extra story 2 or extra chapter 2
+        r"side.*?[\.:;\-_story|chapter]\s?[\(]?(\d+)",  //
+        r"special.*?[\.:;\-_story|chapter]\s?[\(]?(\d+)", //
+    ];
+
+    for title_value in strings {
+        // The chapter label is kept as written, so only the digits are changed for it
+        let ascii_value = &ascii_digits(title_value);
+        let title_value = &ascii_value.to_lowercase();
+
+        //
+        // Find volume number
+        //
+        for re_pattern in volume_res {
+            // Do nothing if volume is already set
+            if novel_recognition_data.volume > 0 {
+                break;
+            }
+
+            let vol_re = Regex::new(re_pattern).unwrap();
+            if let Some(caps) = vol_re.captures(title_value) {
+                let potential_volume = caps.get(1).unwrap().as_str();
+                // Try to parse the volume number str to i32
+                match potential_volume.parse::<i32>() {
+                    Ok(volume_num) => {
+                        novel_recognition_data.volume = volume_num;
+                    }
+                    Err(e) => {
+                        error!("Cannot parse potential volume number to i32 -> {}", e)
+                    }
+                }
+                // Got a capture so no need to look further as the number
+                // would not change anyway
+                break;
+            }
+        }
+
+        //
+        // Find chapter number
+        //
+        if novel_recognition_data.chapter == 0.0 {
+            if let Some(caps) = chapter_re.captures(ascii_value) {
+                let number = |i: usize| caps.get(i).and_then(|m| m.as_str().parse::<f32>().ok());
+                let first = number(1).unwrap_or(0.0);
+                let mut part = caps.get(3).and_then(|m| m.as_str().parse::<i32>().ok());
+
+                novel_recognition_data.chapter = match number(2) {
+                    // Range of chapters, all of them are read
+                    Some(last) if last > first => last,
+                    // A smaller number after the chapter, like `Chapter 12-3`, is its part
+                    Some(last) if last.fract() == 0.0 && part.is_none() => {
+                        part = Some(last as i32);
+                        first
+                    }
+                    _ => first,
+                };
+                novel_recognition_data.part = part.unwrap_or(0);
+                novel_recognition_data.chapter_label = Some(caps[0].trim().to_string());
+                ignore_part = true;
+            }
+        }
+
+        //
+        // Find part number
+        //
+        for re_pattern in sidestory_res {
+            // Do nothing if part can be ignored or side story is already set
+            if ignore_part || novel_recognition_data.side_story > 0 {
+                break;
+            }
+            let part_re = Regex::new(re_pattern).unwrap();
+            if let Some(caps) = part_re.captures(title_value) {
+                let potential_part = caps.get(1).unwrap().as_str();
+
+                match potential_part.parse::<i32>() {
+                    Ok(part_num) => {
+                        novel_recognition_data.side_story = part_num;
+                    }
+                    Err(e) => {
+                        error!("Cannot parse potential part number to i32 -> {}", e);
+                    }
+                }
+                // Got a capture so no need to look further as the number
+                // would not change anyway
+                break;
+            }
+        }
+
+        //
+        // Markers of other languages
+        //
+        if novel_recognition_data.volume == 0 {
+            novel_recognition_data.volume = find_volume(markers, title_value).unwrap_or(0);
+        }
+        if novel_recognition_data.chapter == 0.0 {
+            novel_recognition_data.chapter = find_chapter(markers, title_value).unwrap_or(0.0);
+        }
+        if !ignore_part && novel_recognition_data.side_story == 0 {
+            novel_recognition_data.side_story = find_side_story(markers, title_value).unwrap_or(0);
+        }
+    }
+
+    // Try to guess if currently reading a chapter
+    // novel_recognition_data.reading = is_reading_chapter(strings);
+    // What was the purpose of this thing again?^ hmmm?
+    novel_recognition_data.reading = true;
+
+    //
+    // Find novel title
+    //
+    if novel_recognition_data.reading {
+        // Try to set chapter title if assumed as reading
+        novel_recognition_data.chapter_title = find_chapter_title(strings);
+    }
+
+    // If not reading (probably) then any chapter, etc. numbers
+    // above 0 are probably not right so zero them
+    if !novel_recognition_data.reading {
+        novel_recognition_data.chapter = 0.0;
+        novel_recognition_data.side_story = 0;
+        novel_recognition_data.volume = 0;
+    }
+
+    debug!("Novel recognition data: {:?}", novel_recognition_data);
+
+    novel_recognition_data
+}
+
+fn find_chapter_title(title_strings: &[&str]) -> Option<String> {
+    // Position (from end) of the source (website name) based on the browser being used
+    let position_by_source = vec![("Royal Road", 0), ("Scribble Hub", 1)];
+
+    for (source, pos) in position_by_source.iter() {
+        if title_strings
+            .iter()
+            .any(|&s| s.to_lowercase().contains(&source.to_lowercase()))
+        {
+            // If the position is 1 then get the second item
+            // e.g:
+            //      --V--
+            // [Foo, Bar, Thing, 12, Source, Browser]
+            return Some(title_strings[*pos].to_string());
+        }
+    }
+
+    None
+}
+
+pub fn clean_window_title_string(window_title: Option<String>) -> Option<String> {
+    if let Some(window_title) = window_title {
+        let cleaned = window_title
+            .chars()
+            .map(|x| match x {
+                '|' => '-',
+                '–' => '-', // en dash
+                '—' => '-', // em dash
+                _ => x,
+            })
+            .collect();
+
+        return Some(cleaned);
+    }
+
+    None
+}
+
+/// What was recognized from one window title.
+#[derive(Debug, Clone)]
+pub struct Recognition {
+    /// Window title the strings were split from.
+    pub window_title: String,
+    /// Parts of the window title between the ` - ` separators.
+    pub strings: Vec<String>,
+    /// Name of the title rule that matched.
+    pub rule: Option<String>,
+    pub source: String,
+    pub data: NovelRecognitionData,
+    /// Name of the novel guessed from the title, `?` if it was not needed or found.
+    pub novel_title: String,
+    /// Best match for the novel, also when its confidence is too low for `novel`.
+    pub matched: Option<NovelMatch>,
+    /// The matched novel, if the match has the confidence to count chapters without asking.
+    pub novel: Option<Novel>,
+}
+
+/// Why a novel was matched to a window title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchReason {
+    /// The title of the novel.
+    Title,
+    /// One of the window titles set for the novel, or the start of the title of the novel.
+    Keyword,
+    /// A title similar to the title of the novel.
+    Similar,
+}
+
+/// A novel matched to a part of a window title.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NovelMatch {
+    pub novel_id: String,
+    pub novel_title: String,
+    /// The part of the window title that matched.
+    pub text: String,
+    /// From `MIN_CONFIDENCE` to 1, where 1 is an exact match.
+    pub confidence: f32,
+    pub reason: MatchReason,
+}
+
+impl NovelMatch {
+    fn new(novel: &Novel, text: &str, confidence: f32, reason: MatchReason) -> Self {
+        NovelMatch {
+            novel_id: novel.id.clone(),
+            novel_title: novel.title.clone(),
+            text: text.to_string(),
+            confidence,
+            reason,
+        }
+    }
+}
+
+/// Recognize the novel and chapter from a window title cleaned with `clean_window_title_string`.
+///
+/// The title `rules` are tried first. The novel is only set when the match has at least
+/// the `auto_confidence`. Returns `None` if no rule matches and the title has no ` - `
+/// separators, which every other reading title has.
+pub fn recognize(
+    novels: &[Novel],
+    rules: &TitleRules,
+    markers: &[TitleMarkers],
+    auto_confidence: f32,
+    window_title: &str,
+) -> Option<Recognition> {
+    let captures = rules.captures(window_title);
+    let window_title = window_title.replace('|', "-").replace(".epub", "");
+    if captures.is_none() && !window_title.contains(" -") {
+        return None;
+    }
+
+    // Turn the title string into a vector of trimmed strings
+    let strings: Vec<&str> = window_title.split(" -").map(|t| t.trim()).collect();
+    debug!("Window title str => {:?}", strings);
+
+    let mut novel_title = "?".to_string();
+    let mut source = extract_source_from_title(&strings);
+    // Get potential chapter/side story/volume being read from the title strings
+    let mut data = extract_novel_data_from_title(&strings, markers);
+    if let Some(captures) = &captures {
+        debug!("Title rule `{}` => {:?}", captures.rule, captures);
+        captures.apply(&mut data);
+        if let Some(captured_source) = &captures.source {
+            source = captured_source.clone();
+        }
+    }
+
+    let matched = match captures.as_ref().and_then(|captures| captures.novel.as_ref()) {
+        Some(captured_novel) => match_novel(novels, captured_novel),
+        // Try to find the novel based on all the title strings items in the list
+        None => match_novel_from_title(novels, &strings),
+    };
+    let novel = matched
+        .as_ref()
+        .filter(|matched| matched.confidence >= auto_confidence)
+        .and_then(|matched| novels.iter().find(|novel| novel.id == matched.novel_id));
+    if let (Some(matched), None) = (&matched, novel) {
+        debug!(
+            "{} (did you mean {} [{:.0}% match]?)",
+            matched.text,
+            matched.novel_title,
+            matched.confidence * 100.0
+        );
+    }
+    if novel.is_none() {
+        novel_title = match captures.as_ref().and_then(|captures| captures.novel.clone()) {
+            Some(captured_novel) => captured_novel,
+            // Try to extract the novel title from the title strings
+            None => extract_novel_name_from_title(&strings),
+        };
+    }
+
+    Some(Recognition {
+        strings: strings.iter().map(|s| s.to_string()).collect(),
+        rule: captures.map(|captures| captures.rule),
+        source,
+        data,
+        novel_title,
+        novel: novel.cloned(),
+        matched,
+        window_title,
+    })
+}
+
+/// Find the novel that any of the title strings is an exact match for.
+pub fn find_novel_from_title<'a>(novels: &'a [Novel], title_strings: &[&str]) -> Option<&'a Novel> {
+    title_strings
+        .iter()
+        .find_map(|title| find_novel_by_window_title(novels, title))
+}
+
+/// Try to find `Novel` by `Novel.title` or `Novel.settings.window_titles`,
+/// with a match that has the `AUTO_CONFIDENCE`.
+pub fn find_novel_by_window_title<'a>(novels: &'a [Novel], window_title: &str) -> Option<&'a Novel> {
+    match_novel(novels, window_title)
+        .filter(|matched| matched.confidence >= AUTO_CONFIDENCE)
+        .and_then(|matched| novels.iter().find(|novel| novel.id == matched.novel_id))
+}
+
+/// The best match of any of the title strings, an exact one is taken right away.
+pub fn match_novel_from_title(novels: &[Novel], title_strings: &[&str]) -> Option<NovelMatch> {
+    let mut best: Option<NovelMatch> = None;
+    for title in title_strings {
+        match match_novel(novels, title) {
+            Some(matched) if matched.confidence >= 1.0 => return Some(matched),
+            Some(matched) if best.iter().all(|best| matched.confidence > best.confidence) => best = Some(matched),
+            _ => {}
+        }
+    }
+
+    best
+}
+
+/// Match `window_title` to a novel by its title, its keywords or a similar title.
+pub fn match_novel(novels: &[Novel], window_title: &str) -> Option<NovelMatch> {
+    // First check if the window title is identical to any novel
+    if let Some(novel) = novel_by_title(novels, window_title) {
+        return Some(NovelMatch::new(novel, window_title, 1.0, MatchReason::Title));
+    }
+
+    // Then check for keywords in novel settings
+    if let Some((novel, confidence)) = novel_by_keywords(novels, window_title) {
+        return Some(NovelMatch::new(novel, window_title, confidence, MatchReason::Keyword));
+    }
+
+    // Lastly do a fuzzy search
+    let mut corpus = CorpusBuilder::new().arity(2).pad_full(Pad::Auto).finish();
+    for novel in novels {
+        corpus.add_text(&novel.title.to_lowercase());
+    }
+    let top_result = corpus.search(window_title, MIN_CONFIDENCE).into_iter().next()?;
+    let novel = novel_by_title(novels, &top_result.text)?;
+
+    Some(NovelMatch::new(
+        novel,
+        window_title,
+        top_result.similarity,
+        MatchReason::Similar,
+    ))
+}
+
+/// Find the novel with the `title`, ignoring case.
+pub fn novel_by_title<'a>(novels: &'a [Novel], title: &str) -> Option<&'a Novel> {
+    novels.iter().find(|n| n.title.to_lowercase() == title.to_lowercase())
+}
+
+/// Try to find `Novel` by `Novel.settings.window_titles`, with the confidence of the match.
+fn novel_by_keywords<'a>(novels: &'a [Novel], window_title: &str) -> Option<(&'a Novel, f32)> {
+    let split_title = window_title.split(' ').collect::<Vec<_>>();
+    for novel in novels {
+        if let Some(window_titles) = &novel.settings.window_titles {
+            // Get by exact match
+            if window_titles.iter().any(|i| !i.is_empty() && i == window_title) {
+                return Some((novel, 1.0));
+            } else {
+                if split_checker(&split_title, &novel.title) {
+                    return Some((novel, PREFIX_CONFIDENCE));
+                }
+
+                for one_title in window_titles {
+                    if split_checker(&split_title, one_title) {
+                        return Some((novel, PREFIX_CONFIDENCE));
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Return true if part(s) of a longer string is in a shorter one. Lie.. reverse of "is `foo` in `foobar`".
+///
+/// Example: `Novel Name chapter 12` is in `Novel Name`.
+fn split_checker(split_source: &[&str], target: &str) -> bool {
+    let mut count = 0;
+    let split_target = target.split(' ').collect::<Vec<_>>();
+
+    for i in 0..split_target.len() {
+        if split_source.get(i).unwrap_or(&"").to_lowercase() == split_target.get(i).unwrap().to_lowercase() {
+            count += 1;
+
+            if count == split_target.len() {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(title: &str) -> (f32, i32, Option<String>) {
+        let title = clean_window_title_string(Some(title.to_string())).unwrap();
+        let strings: Vec<&str> = title.split(" -").map(|t| t.trim()).collect();
+        let data = extract_novel_data_from_title(&strings, &TitleMarkers::defaults());
+
+        (data.chapter, data.part, data.chapter_label)
+    }
+
+    #[test]
+    fn test_extract_chapter() {
+        let label = |label: &str| Some(label.to_string());
+
+        assert_eq!(chapter("Chapter 12 - Novel - Firefox"), (12.0, 0, label("Chapter 12")));
+        assert_eq!(
+            chapter("Novel - Chapter 120-122 - Firefox"),
+            (122.0, 0, label("Chapter 120-122"))
+        );
+        assert_eq!(chapter("Ch. 45 & 46 - Novel"), (46.0, 0, label("Ch. 45 & 46")));
+        assert_eq!(
+            chapter("Novel Chapters 10–12 (combined) - Site"),
+            (12.0, 0, label("Chapters 10-12"))
+        );
+        assert_eq!(
+            chapter("Chapter 7 to Chapter 9 - Novel"),
+            (9.0, 0, label("Chapter 7 to Chapter 9"))
+        );
+        assert_eq!(chapter("Chapter 12.5 - Novel"), (12.5, 0, label("Chapter 12.5")));
+        // Parts are not limited to a single digit
+        assert_eq!(
+            chapter("Chapter 14 Part 12 - Novel"),
+            (14.0, 12, label("Chapter 14 Part 12"))
+        );
+        assert_eq!(chapter("Ch 3 (Pt. 2) - Novel"), (3.0, 2, label("Ch 3 (Pt. 2)")));
+        assert_eq!(chapter("Chapter 12-3 - Novel"), (12.0, 3, label("Chapter 12-3")));
+        assert_eq!(chapter("第１２３章 - Novel"), (123.0, 0, None));
+    }
+}
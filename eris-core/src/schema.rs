@@ -0,0 +1,155 @@
+//! Versioned on-disk format for the bincode files.
+//!
+//! Every persisted file starts with a small header: `ERIS` magic bytes followed by
+//! the schema version as a little-endian `u32`. Files written before the header
+//! existed are treated as version `0`.
+//!
+//! bincode is not self-describing so adding a field to a persisted struct changes
+//! its layout. When that happens:
+//! 1. Copy the old struct definitions into a `legacy` module as `FooV<old version>`.
+//! 2. Implement `From<FooV<old>>` for the next version.
+//! 3. Bump `Versioned::VERSION` and add a match arm to `Versioned::migrate` that
+//!    decodes the old layout and upgrades it step by step to the current one.
+
+use crate::error::ErisError;
+use anyhow::Context;
+use bincode::{deserialize_from, serialize_into};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{Cursor, Read, Write};
+
+/// Magic bytes at the start of every versioned file.
+const MAGIC: &[u8; 4] = b"ERIS";
+/// Schema version of files that were written without a header.
+pub const LEGACY_VERSION: u32 = 0;
+
+/// Data that is persisted with a schema version header.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Current schema version of the struct layout.
+    const VERSION: u32;
+
+    /// Decode data written with an older schema `version` and upgrade it to the current one.
+    ///
+    /// Only called with versions lower than `VERSION`.
+    fn migrate(version: u32, reader: &mut dyn Read) -> Result<Self, anyhow::Error>;
+}
+
+/// Write the version header followed by the bincode encoded `value`.
+pub fn serialize_versioned<W: Write, T: Versioned>(mut writer: W, value: &T) -> Result<(), anyhow::Error> {
+    writer.write_all(MAGIC).context(ErisError::WriteToDisk)?;
+    writer
+        .write_all(&T::VERSION.to_le_bytes())
+        .context(ErisError::WriteToDisk)?;
+
+    serialize_into(writer, value).context(ErisError::SerializeToFile)
+}
+
+/// Read the version header and decode the data, migrating it if it is older than the current version.
+pub fn deserialize_versioned<R: Read, T: Versioned>(mut reader: R) -> Result<T, anyhow::Error> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic).context(ErisError::DeserializeFromFile)?;
+
+    if &magic != MAGIC {
+        // No header so the bytes that were just read belong to the data itself
+        debug!("No schema header found, migrating from version {}", LEGACY_VERSION);
+        let mut legacy_reader = Cursor::new(magic).chain(reader);
+        return T::migrate(LEGACY_VERSION, &mut legacy_reader).context(ErisError::DeserializeFromFile);
+    }
+
+    let mut version = [0u8; 4];
+    reader
+        .read_exact(&mut version)
+        .context(ErisError::DeserializeFromFile)?;
+    let version = u32::from_le_bytes(version);
+
+    if version == T::VERSION {
+        deserialize_from(reader).context(ErisError::DeserializeFromFile)
+    } else if version < T::VERSION {
+        info!("Migrating data from schema version {} to {}", version, T::VERSION);
+        T::migrate(version, &mut reader).context(ErisError::DeserializeFromFile)
+    } else {
+        // Written by a newer Eris, do not guess
+        Err(ErisError::UnsupportedVersion(version).into())
+    }
+}
+
+/// Schema version of a file that could not be read because a newer Eris wrote it.
+///
+/// Such a file is still good, so it has to be kept as it is.
+pub fn newer_version(error: &anyhow::Error) -> Option<u32> {
+    error.chain().find_map(|cause| match cause.downcast_ref::<ErisError>() {
+        Some(ErisError::UnsupportedVersion(version)) => Some(*version),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct ThingV1 {
+        name: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Thing {
+        name: String,
+        count: i32,
+    }
+
+    impl From<ThingV1> for Thing {
+        fn from(old: ThingV1) -> Self {
+            Thing {
+                name: old.name,
+                count: 1,
+            }
+        }
+    }
+
+    impl Versioned for Thing {
+        const VERSION: u32 = 2;
+
+        fn migrate(version: u32, reader: &mut dyn Read) -> Result<Self, anyhow::Error> {
+            match version {
+                LEGACY_VERSION | 1 => Ok(deserialize_from::<_, ThingV1>(reader)?.into()),
+                _ => Err(ErisError::UnsupportedVersion(version).into()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let thing = Thing {
+            name: "foo".to_string(),
+            count: 3,
+        };
+        let mut bytes = vec![];
+        serialize_versioned(&mut bytes, &thing).unwrap();
+
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(deserialize_versioned::<_, Thing>(bytes.as_slice()).unwrap(), thing);
+    }
+
+    #[test]
+    fn test_migrate_headerless_file() {
+        let old = ThingV1 {
+            name: "foo".to_string(),
+        };
+        let bytes = bincode::serialize(&old).unwrap();
+
+        let thing = deserialize_versioned::<_, Thing>(bytes.as_slice()).unwrap();
+        assert_eq!(thing.name, "foo");
+        assert_eq!(thing.count, 1);
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&99u32.to_le_bytes());
+
+        let error = deserialize_versioned::<_, Thing>(bytes.as_slice()).unwrap_err();
+        assert_eq!(newer_version(&error), Some(99));
+    }
+}
@@ -0,0 +1,43 @@
+use crate::database::{read_database, Database, DatabaseRecovery};
+use crate::error::ErisError;
+use crate::history::{NovelHistory, NovelHistoryItem};
+use crate::novel::Novel;
+use crate::storage::Storage;
+
+/// The original storage; `eris.db` and `eris.history` bincode files
+/// which are rewritten completely on every change.
+pub struct FileStorage;
+
+impl Storage for FileStorage {
+    fn read_database(&self) -> (Database, Option<DatabaseRecovery>) {
+        read_database()
+    }
+
+    fn write_novel(&self, db: &mut Database, _novel: &Novel) -> Result<(), anyhow::Error> {
+        db.write_to_file()
+    }
+
+    fn delete_novel(&self, db: &mut Database, _novel_id: &str) -> Result<(), anyhow::Error> {
+        db.write_to_file()
+    }
+
+    fn write_database(&self, db: &mut Database) -> Result<(), anyhow::Error> {
+        db.write_to_file()
+    }
+
+    fn read_history(&self) -> Result<NovelHistory, ErisError> {
+        NovelHistory::open()
+    }
+
+    fn write_history_item(&self, history: &NovelHistory, _item: &NovelHistoryItem) -> Result<(), anyhow::Error> {
+        history.write_to_file()?;
+
+        Ok(())
+    }
+
+    fn write_history(&self, history: &NovelHistory) -> Result<(), anyhow::Error> {
+        history.write_to_file()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+//! Persistence of novels and history.
+//!
+//! The application keeps the whole `Database` and `NovelHistory` in memory and tells the
+//! storage backend about every change. The file backend rewrites the whole file,
+//! while the SQLite backend only touches the changed rows.
+
+mod file;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use file::FileStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+use crate::database::{Database, DatabaseRecovery};
+use crate::error::ErisError;
+use crate::history::{NovelHistory, NovelHistoryItem};
+use crate::novel::Novel;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[repr(i32)]
+pub enum StorageBackend {
+    /// `eris.db` and `eris.history` bincode files.
+    File = 0,
+    /// Rows in an SQLite database, `eris.sqlite`.
+    Sqlite,
+}
+
+impl ToString for StorageBackend {
+    fn to_string(&self) -> String {
+        match self {
+            StorageBackend::File => fl!("storage-file"),
+            StorageBackend::Sqlite => fl!("storage-sqlite"),
+        }
+    }
+}
+
+impl StorageBackend {
+    pub fn to_i32(&self) -> i32 {
+        self.to_owned() as i32
+    }
+
+    pub fn vec() -> Vec<String> {
+        vec![StorageBackend::File.to_string(), StorageBackend::Sqlite.to_string()]
+    }
+
+    pub fn from_i32(value: i32) -> StorageBackend {
+        match value {
+            1 => StorageBackend::Sqlite,
+            _ => StorageBackend::File,
+        }
+    }
+}
+
+pub trait Storage: Send + Sync {
+    /// Read all the novels. Returns an empty read-only `Database` and the
+    /// details of what went wrong if the data could not be read.
+    fn read_database(&self) -> (Database, Option<DatabaseRecovery>);
+
+    /// Save a new or changed `novel`. `db` already contains the change.
+    fn write_novel(&self, db: &mut Database, novel: &Novel) -> Result<(), anyhow::Error>;
+
+    /// Remove the novel with `novel_id`. `db` no longer contains it.
+    fn delete_novel(&self, db: &mut Database, novel_id: &str) -> Result<(), anyhow::Error>;
+
+    /// Replace everything that is stored with the contents of `db`. Backends that store
+    /// rows only write the ones that differ.
+    fn write_database(&self, db: &mut Database) -> Result<(), anyhow::Error>;
+
+    /// Read all the history items.
+    fn read_history(&self) -> Result<NovelHistory, ErisError>;
+
+    /// Save a new history `item`. `history` already contains it.
+    fn write_history_item(&self, history: &NovelHistory, item: &NovelHistoryItem) -> Result<(), anyhow::Error>;
+
+    /// Replace all the stored history with the contents of `history`. Backends that store
+    /// rows only write the items that differ.
+    fn write_history(&self, history: &NovelHistory) -> Result<(), anyhow::Error>;
+}
+
+/// Open the storage backend selected in the settings.
+///
+/// Falls back to the file storage if the selected backend is not
+/// available in this build or cannot be opened.
+pub fn open(backend: &StorageBackend) -> Arc<dyn Storage> {
+    match backend {
+        StorageBackend::File => Arc::new(FileStorage),
+        #[cfg(feature = "sqlite")]
+        StorageBackend::Sqlite => match SqliteStorage::open(&crate::profile::profile_dir(crate::SQLITE_FILE)) {
+            Ok(storage) => Arc::new(storage),
+            Err(e) => {
+                error!("Cannot open SQLite storage, using files instead. {:#}", e);
+                Arc::new(FileStorage)
+            }
+        },
+        #[cfg(not(feature = "sqlite"))]
+        StorageBackend::Sqlite => {
+            warn!("This build does not include SQLite support, using files instead.");
+            Arc::new(FileStorage)
+        }
+    }
+}
@@ -0,0 +1,501 @@
+use crate::database::{Database, DatabaseRecovery};
+use crate::error::ErisError;
+use crate::file::backup_path;
+use crate::history::{NovelHistory, NovelHistoryItem};
+use crate::novel::{Novel, NovelSettings};
+use crate::profile::profile_dir;
+use crate::storage::{FileStorage, Storage};
+use crate::{DB_FILE, HISTORY_FILE};
+use anyhow::Context;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS novels (
+        id TEXT PRIMARY KEY,
+        slug TEXT,
+        title TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS novels_slug ON novels (slug);
+    CREATE TABLE IF NOT EXISTS novel_settings (
+        novel_id TEXT PRIMARY KEY,
+        list_status INTEGER NOT NULL,
+        last_read INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        novel_id TEXT NOT NULL,
+        time INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS history_novel_id ON history (novel_id);
+    CREATE INDEX IF NOT EXISTS history_time ON history (time);
+";
+
+/// `meta` key with the time `eris.db` and `eris.history` were last copied into SQLite.
+const FILES_MIGRATED_KEY: &str = "files_migrated";
+
+/// Novels, their settings and history items as rows in an SQLite database.
+///
+/// Row data is stored as JSON so the tables do not need to change every
+/// time a field is added to `Novel` or `NovelHistoryItem`.
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Open or create the SQLite database in `path`.
+    ///
+    /// The first time this is done the existing database and history files
+    /// are copied into it. The files are left untouched.
+    pub fn open(path: &Path) -> Result<Self, anyhow::Error> {
+        let connection = Connection::open(path).context(ErisError::ReadFromDisk)?;
+        connection.execute_batch(SCHEMA).context(ErisError::WriteToDisk)?;
+
+        let storage = SqliteStorage {
+            connection: Mutex::new(connection),
+        };
+        storage.migrate_from_files(path)?;
+
+        Ok(storage)
+    }
+
+    /// Copy `eris.db` and `eris.history` into SQLite the first time, and again
+    /// whenever they were written after the last copy because the file storage
+    /// was used in between. The files are left untouched.
+    fn migrate_from_files(&self, path: &Path) -> Result<(), anyhow::Error> {
+        let mut connection = self.connection.lock();
+
+        let migrated: Option<i64> = connection
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?1",
+                params![FILES_MIGRATED_KEY],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .map(|value| value.parse().unwrap_or(0));
+        // Unchanged files need not be read at all
+        let files = [profile_dir(DB_FILE), profile_dir(HISTORY_FILE)];
+        if let Some(migrated) = migrated {
+            if !files.iter().any(|file| modified_after(file, migrated)) {
+                return Ok(());
+            }
+        }
+
+        let tx = connection.transaction()?;
+
+        if files.iter().any(|file| file.exists()) {
+            let (db, recovery) = FileStorage.read_database();
+            let history = match (recovery.is_some() && db.read_only, migrated) {
+                // Not marked as migrated so it is tried again once the file is fixed
+                (true, None) => return Err(anyhow::anyhow!("Database file could not be read, not migrating.")),
+                (true, Some(_)) => {
+                    warn!("Database file could not be read, keeping the SQLite data.");
+                    return Ok(());
+                }
+                (false, None) => FileStorage.read_history()?,
+                (false, Some(_)) => match FileStorage.read_history() {
+                    Ok(history) => history,
+                    Err(e) => {
+                        warn!("History file could not be read, keeping the SQLite data. {}", e);
+                        return Ok(());
+                    }
+                },
+            };
+
+            match migrated {
+                None => info!("Migrating database and history files into SQLite"),
+                // A copy, or a move of the data directory, changes the time but not the contents
+                Some(migrated) if !files_changed_since(&db, &history, migrated) => {
+                    drop(tx);
+                    return mark_migrated(&connection);
+                }
+                Some(_) => {
+                    warn!("Database or history file was changed after the last copy into SQLite, copying them again");
+                    // Keep the replaced data in case the files were not the newer ones after all
+                    if let Err(e) = fs::copy(path, backup_path(path)) {
+                        error!("Cannot back up {:?}. {}", path, e);
+                    }
+                }
+            }
+
+            replace_novels(&tx, db.novels.as_deref().unwrap_or_default())?;
+            replace_history(&tx, &history.items)?;
+
+            info!(
+                "Migrated {} novels and {} history items into SQLite",
+                db.novels.as_ref().map_or(0, |n| n.len()),
+                history.items.len()
+            );
+        }
+
+        mark_migrated(&tx)?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn read_novels(&self) -> Result<Vec<Novel>, anyhow::Error> {
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare(
+            "SELECT novels.data, novel_settings.data FROM novels
+             LEFT JOIN novel_settings ON novel_settings.novel_id = novels.id
+             ORDER BY novels.rowid",
+        )?;
+
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+
+        let mut novels = vec![];
+        for row in rows {
+            let (novel_data, settings_data) = row?;
+            let mut novel: Value = serde_json::from_str(&novel_data).context(ErisError::DeserializeFromFile)?;
+            let settings = match settings_data {
+                Some(settings_data) => serde_json::from_str(&settings_data).context(ErisError::DeserializeFromFile)?,
+                None => serde_json::to_value(NovelSettings::default())?,
+            };
+            novel["settings"] = settings;
+
+            novels.push(serde_json::from_value(novel).context(ErisError::DeserializeFromFile)?);
+        }
+
+        Ok(novels)
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn read_database(&self) -> (Database, Option<DatabaseRecovery>) {
+        match self.read_novels() {
+            Ok(novels) => (Database::new(Some(novels)), None),
+            Err(e) => {
+                error!("Cannot read novels from SQLite. {:#}", e);
+
+                let mut db = Database::new(Some(vec![]));
+                db.read_only = true;
+
+                let recovery = DatabaseRecovery {
+                    attempts: vec![format!("{}: {:#}", profile_dir(crate::SQLITE_FILE).display(), e)],
+                    restored_from_backup: false,
+                    json_export: None,
+                    newer_version: None,
+                };
+                (db, Some(recovery))
+            }
+        }
+    }
+
+    fn write_novel(&self, db: &mut Database, novel: &Novel) -> Result<(), anyhow::Error> {
+        if db.read_only {
+            return Err(ErisError::ReadOnlyDatabase.into());
+        }
+
+        let mut connection = self.connection.lock();
+        let tx = connection.transaction()?;
+        insert_novel(&tx, novel)?;
+        tx.commit().context(ErisError::WriteToDisk)
+    }
+
+    fn delete_novel(&self, db: &mut Database, novel_id: &str) -> Result<(), anyhow::Error> {
+        if db.read_only {
+            return Err(ErisError::ReadOnlyDatabase.into());
+        }
+
+        let mut connection = self.connection.lock();
+        let tx = connection.transaction()?;
+        tx.execute("DELETE FROM novels WHERE id = ?1", params![novel_id])?;
+        tx.execute("DELETE FROM novel_settings WHERE novel_id = ?1", params![novel_id])?;
+        tx.commit().context(ErisError::WriteToDisk)
+    }
+
+    fn write_database(&self, db: &mut Database) -> Result<(), anyhow::Error> {
+        if db.read_only {
+            return Err(ErisError::ReadOnlyDatabase.into());
+        }
+
+        let mut connection = self.connection.lock();
+        let tx = connection.transaction()?;
+        replace_novels(&tx, db.novels.as_deref().unwrap_or_default())?;
+        tx.commit().context(ErisError::WriteToDisk)
+    }
+
+    fn read_history(&self) -> Result<NovelHistory, ErisError> {
+        let connection = self.connection.lock();
+        let mut statement = connection
+            .prepare("SELECT data FROM history ORDER BY time, id")
+            .context(ErisError::ReadFromDisk)?;
+        let rows = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .context(ErisError::ReadFromDisk)?;
+
+        let mut items = vec![];
+        for row in rows {
+            let data = row.context(ErisError::ReadFromDisk)?;
+            items.push(serde_json::from_str(&data).context(ErisError::DeserializeFromFile)?);
+        }
+
+        Ok(NovelHistory { items })
+    }
+
+    fn write_history_item(&self, _history: &NovelHistory, item: &NovelHistoryItem) -> Result<(), anyhow::Error> {
+        let connection = self.connection.lock();
+        insert_history_item(&connection, item)
+    }
+
+    fn write_history(&self, history: &NovelHistory) -> Result<(), anyhow::Error> {
+        let mut connection = self.connection.lock();
+        let tx = connection.transaction()?;
+        replace_history(&tx, &history.items)?;
+        tx.commit().context(ErisError::WriteToDisk)
+    }
+}
+
+/// Remember that the files have been copied into SQLite up to now.
+fn mark_migrated(connection: &Connection) -> Result<(), anyhow::Error> {
+    connection.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+        params![FILES_MIGRATED_KEY, chrono::Local::now().timestamp().to_string()],
+    )?;
+
+    Ok(())
+}
+
+/// Whether `file` was modified after the Unix timestamp `time`.
+fn modified_after(file: &Path, time: i64) -> bool {
+    let modified = fs::metadata(file)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+
+    matches!(modified, Some(modified) if modified.as_secs() as i64 > time)
+}
+
+/// Whether the file storage saved `db` or added to `history` after the Unix timestamp `time`.
+fn files_changed_since(db: &Database, history: &NovelHistory, time: i64) -> bool {
+    db.last_update > time || history.items.iter().any(|item| item.time / 1000 > time)
+}
+
+/// The novel without its settings and the settings, as the JSON stored in their rows.
+fn novel_rows(novel: &Novel) -> Result<(String, String), anyhow::Error> {
+    // Settings go to their own table so take them out of the novel data
+    let mut novel_data = serde_json::to_value(novel).context(ErisError::SerializeToFile)?;
+    if let Some(object) = novel_data.as_object_mut() {
+        object.remove("settings");
+    }
+    let settings_data = serde_json::to_string(&novel.settings).context(ErisError::SerializeToFile)?;
+
+    Ok((novel_data.to_string(), settings_data))
+}
+
+/// Insert the novel and its settings, or update the existing rows in place so they keep their rowid.
+fn insert_novel(tx: &Transaction, novel: &Novel) -> Result<(), anyhow::Error> {
+    let (novel_data, settings_data) = novel_rows(novel)?;
+
+    tx.execute(
+        "INSERT INTO novels (id, slug, title, data) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(id) DO UPDATE SET slug = excluded.slug, title = excluded.title, data = excluded.data",
+        params![novel.id, novel.slug, novel.title, novel_data],
+    )?;
+    tx.execute(
+        "INSERT INTO novel_settings (novel_id, list_status, last_read, data) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(novel_id) DO UPDATE SET
+             list_status = excluded.list_status, last_read = excluded.last_read, data = excluded.data",
+        params![
+            novel.id,
+            novel.settings.list_status.to_i32(),
+            novel.settings.last_read,
+            settings_data
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Make the stored novels match `novels`, only writing the rows that changed.
+fn replace_novels(tx: &Transaction, novels: &[Novel]) -> Result<(), anyhow::Error> {
+    let mut stored = HashMap::new();
+    {
+        let mut statement = tx.prepare(
+            "SELECT novels.id, novels.data, novel_settings.data FROM novels
+             LEFT JOIN novel_settings ON novel_settings.novel_id = novels.id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                (row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?),
+            ))
+        })?;
+        for row in rows {
+            let (id, data) = row?;
+            stored.insert(id, data);
+        }
+    }
+
+    for novel in novels {
+        let (novel_data, settings_data) = novel_rows(novel)?;
+        if stored.remove(&novel.id) != Some((novel_data, Some(settings_data))) {
+            insert_novel(tx, novel)?;
+        }
+    }
+
+    // What is left is no longer in `novels`
+    for novel_id in stored.keys() {
+        tx.execute("DELETE FROM novels WHERE id = ?1", params![novel_id])?;
+        tx.execute("DELETE FROM novel_settings WHERE novel_id = ?1", params![novel_id])?;
+    }
+
+    Ok(())
+}
+
+fn insert_history_item(connection: &Connection, item: &NovelHistoryItem) -> Result<(), anyhow::Error> {
+    let data = serde_json::to_string(item).context(ErisError::SerializeToFile)?;
+    connection
+        .execute(
+            "INSERT INTO history (novel_id, time, data) VALUES (?1, ?2, ?3)",
+            params![item.novel_id, item.time, data],
+        )
+        .context(ErisError::WriteToDisk)?;
+
+    Ok(())
+}
+
+/// Make the stored history match `items`, only inserting the new items and deleting the removed ones.
+fn replace_history(tx: &Transaction, items: &[NovelHistoryItem]) -> Result<(), anyhow::Error> {
+    // Identical items can be in the history more than once, so count them
+    let mut wanted: HashMap<String, usize> = HashMap::new();
+    for item in items {
+        let data = serde_json::to_string(item).context(ErisError::SerializeToFile)?;
+        *wanted.entry(data).or_default() += 1;
+    }
+
+    let stored = {
+        let mut statement = tx.prepare("SELECT id, data FROM history ORDER BY id")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+    for (id, data) in stored {
+        match wanted.get_mut(&data) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => {
+                tx.execute("DELETE FROM history WHERE id = ?1", params![id])?;
+            }
+        }
+    }
+
+    // What is still wanted is not stored yet
+    for item in items {
+        let data = serde_json::to_string(item).context(ErisError::SerializeToFile)?;
+        if let Some(count) = wanted.get_mut(&data).filter(|count| **count > 0) {
+            *count -= 1;
+            insert_history_item(tx, item)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel::test_novel;
+
+    fn storage() -> SqliteStorage {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(SCHEMA).unwrap();
+
+        SqliteStorage {
+            connection: Mutex::new(connection),
+        }
+    }
+
+    fn rowids(storage: &SqliteStorage, table: &str, column: &str) -> Vec<(String, i64)> {
+        let connection = storage.connection.lock();
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT CAST({} AS TEXT), rowid FROM {} ORDER BY rowid",
+                column, table
+            ))
+            .unwrap();
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        rows.map(|row| row.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_files_changed_since() {
+        let mut db = Database::new(Some(vec![]));
+        db.last_update = 100;
+        let mut history = NovelHistory {
+            items: vec![NovelHistoryItem {
+                time: 90_000,
+                ..NovelHistoryItem::new_history_add_novel(&test_novel("a", "A"))
+            }],
+        };
+
+        assert!(!files_changed_since(&db, &history, 100));
+        assert!(files_changed_since(&db, &history, 99));
+
+        history.items[0].time = 101_000;
+        assert!(files_changed_since(&db, &history, 100));
+    }
+
+    #[test]
+    fn test_write_database_keeps_unchanged_rows() {
+        let storage = storage();
+        let mut db = Database::new(Some(vec![
+            test_novel("a", "A"),
+            test_novel("b", "B"),
+            test_novel("c", "C"),
+        ]));
+        storage.write_database(&mut db).unwrap();
+        let before = rowids(&storage, "novels", "id");
+
+        let mut changed = test_novel("b", "B");
+        changed.settings.content_read.chapters = 5.0;
+        let mut db = Database::new(Some(vec![test_novel("a", "A"), changed.clone(), test_novel("d", "D")]));
+        storage.write_database(&mut db).unwrap();
+
+        let after = rowids(&storage, "novels", "id");
+        assert_eq!(after[..2], before[..2]);
+        assert_eq!(after[2].0, "d");
+        assert_eq!(storage.read_novels().unwrap()[1], changed);
+    }
+
+    #[test]
+    fn test_write_history_keeps_unchanged_rows() {
+        let storage = storage();
+        let novel = test_novel("a", "A");
+        let item = |time| NovelHistoryItem {
+            time,
+            ..NovelHistoryItem::new_history_update_novel(&novel)
+        };
+
+        storage
+            .write_history(&NovelHistory {
+                items: vec![item(1), item(2), item(2)],
+            })
+            .unwrap();
+        let before = rowids(&storage, "history", "time");
+
+        storage
+            .write_history(&NovelHistory {
+                items: vec![item(2), item(2), item(3)],
+            })
+            .unwrap();
+        let after = rowids(&storage, "history", "time");
+
+        assert_eq!(after[..2], before[1..]);
+        assert_eq!(after[2].0, "3");
+        assert_eq!(storage.read_history().unwrap().items.len(), 3);
+    }
+}
@@ -0,0 +1,403 @@
+//! File-based sync between devices.
+//!
+//! Every device appends its history items, with a copy of the novel for adds and
+//! updates, to its own `<device>.journal` file in a folder that is shared between the
+//! devices by some other program (Syncthing, Nextcloud, ...). Only the owner ever writes
+//! to a journal so the files never conflict. On start the journals of the other devices
+//! are replayed on top of the local data, newest change of each field wins.
+//!
+//! Only changes made after sync was enabled are in the journals, the existing libraries
+//! can be combined once with the JSON merge import.
+
+use crate::database::Database;
+use crate::error::ErisError;
+use crate::file::write_atomic;
+use crate::history::{HistoryAction, NovelHistory, NovelHistoryItem};
+use crate::novel::Novel;
+use crate::profile::profile_dir;
+use crate::SYNC_STATE_FILE;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Extension of the journal files in the sync folder.
+const JOURNAL_EXTENSION: &str = "journal";
+
+/// One change made on some device. Stored as a single line of JSON.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JournalEntry {
+    pub device: String,
+    pub item: NovelHistoryItem,
+    /// The whole novel after it was added or updated.
+    pub novel: Option<Novel>,
+}
+
+/// Journal of this device in the sync folder.
+pub struct Journal {
+    path: PathBuf,
+    device: String,
+}
+
+impl Journal {
+    pub fn new(sync_dir: &Path, device: &str) -> Self {
+        Journal {
+            path: journal_path(sync_dir, device),
+            device: device.to_string(),
+        }
+    }
+
+    /// Append a change made on this device.
+    pub fn append(&self, item: &NovelHistoryItem, novel: Option<Novel>) -> Result<(), anyhow::Error> {
+        let entry = JournalEntry {
+            device: self.device.clone(),
+            item: item.clone(),
+            novel,
+        };
+        let mut line = serde_json::to_string(&entry).context(ErisError::SerializeToFile)?;
+        line.push('\n');
+
+        let mut f = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context(ErisError::WriteToDisk)?;
+        // One write so a half synced file can only ever miss whole lines
+        f.write_all(line.as_bytes()).context(ErisError::WriteToDisk)?;
+
+        Ok(())
+    }
+}
+
+fn journal_path(sync_dir: &Path, device: &str) -> PathBuf {
+    sync_dir.join(format!("{}.{}", device, JOURNAL_EXTENSION))
+}
+
+/// Read the journals of every device except `device`. Lines that cannot be parsed,
+/// for example one that is still being synced, are skipped.
+pub fn read_other_journals(sync_dir: &Path, device: &str) -> Vec<JournalEntry> {
+    let own_journal = journal_path(sync_dir, device);
+    let entries = match fs::read_dir(sync_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Cannot read sync folder {:?}. {}", sync_dir, e);
+            return vec![];
+        }
+    };
+
+    let mut journal = vec![];
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path == own_journal || path.extension().map_or(true, |ext| ext != JOURNAL_EXTENSION) {
+            continue;
+        }
+
+        let f = match File::open(&path) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("Cannot open journal {:?}. {}", path, e);
+                continue;
+            }
+        };
+        for line in BufReader::new(f).lines().filter_map(|l| l.ok()) {
+            match serde_json::from_str::<JournalEntry>(&line) {
+                Ok(entry) => journal.push(entry),
+                Err(e) => debug!("Skipping journal line in {:?}. {}", path, e),
+            }
+        }
+    }
+
+    journal
+}
+
+/// How far the journal of each device has been replayed.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct SyncState {
+    /// Device name -> time of the newest replayed entry.
+    pub last_seen: HashMap<String, i64>,
+}
+
+impl SyncState {
+    pub fn open() -> Self {
+        let path = profile_dir(SYNC_STATE_FILE);
+        if !path.exists() {
+            return SyncState::default();
+        }
+
+        match fs::read_to_string(&path).map(|data| serde_json::from_str(&data)) {
+            Ok(Ok(state)) => state,
+            _ => {
+                // Worst case everything is replayed again, which changes nothing
+                warn!("Cannot read sync state, replaying all journals.");
+                SyncState::default()
+            }
+        }
+    }
+
+    pub fn write_to_file(&self) -> Result<(), anyhow::Error> {
+        write_atomic(&profile_dir(SYNC_STATE_FILE), |writer| {
+            serde_json::to_writer(writer, self).context(ErisError::SerializeToFile)
+        })
+    }
+}
+
+/// Field that can be changed on two devices at the same time.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SyncField {
+    ContentRead,
+    ListStatus,
+}
+
+impl ToString for SyncField {
+    fn to_string(&self) -> String {
+        match self {
+            SyncField::ContentRead => fl!("sync-field-content-read"),
+            SyncField::ListStatus => fl!("sync-field-list-status"),
+        }
+    }
+}
+
+/// A field that was changed both locally and on another device since the last sync.
+#[derive(Debug, Clone)]
+pub struct SyncConflict {
+    pub novel_title: String,
+    pub field: SyncField,
+    pub device: String,
+    /// The local value was newer and was kept.
+    pub local_kept: bool,
+}
+
+/// What replaying the journals changed.
+#[derive(Debug, Default)]
+pub struct ReplayResult {
+    /// Replayed history items that are not in the local history yet.
+    pub history_added: Vec<NovelHistoryItem>,
+    pub conflicts: Vec<SyncConflict>,
+    /// Whether any novel was added, changed or removed.
+    pub db_changed: bool,
+}
+
+/// Apply the `entries` that are newer than what has been replayed before to `db`.
+///
+/// Each field has its own last writer: local changes are taken from `history`.
+/// A conflict is reported when both sides changed `content_read` or `list_status`
+/// to a different value after the last replay.
+pub fn replay(
+    db: &mut Database,
+    history: &NovelHistory,
+    mut entries: Vec<JournalEntry>,
+    state: &mut SyncState,
+) -> ReplayResult {
+    let mut result = ReplayResult::default();
+    let previous = state.last_seen.clone();
+
+    entries.retain(|e| e.item.time > previous.get(&e.device).copied().unwrap_or(0));
+    entries.sort_by_key(|e| e.item.time);
+
+    // Time of the latest local change of each novel field
+    let mut changed_at: HashMap<(String, HistoryAction), i64> = HashMap::new();
+    for item in &history.items {
+        let time = changed_at
+            .entry((item.novel_id.clone(), item.action.clone()))
+            .or_insert(0);
+        *time = (*time).max(item.time);
+    }
+    let mut seen: HashSet<(String, HistoryAction, i64)> = history
+        .items
+        .iter()
+        .map(|i| (i.novel_id.clone(), i.action.clone(), i.time))
+        .collect();
+
+    let novels = db.novels.get_or_insert_with(Vec::new);
+    for entry in entries {
+        let item = &entry.item;
+        let last_sync = previous.get(&entry.device).copied().unwrap_or(0);
+        let key = (item.novel_id.clone(), item.action.clone());
+        let local_time = changed_at.get(&key).copied().unwrap_or(0);
+        let remote_wins = item.time >= local_time;
+        let index = novels.iter().position(|n| n.id == item.novel_id);
+
+        match (&item.action, index) {
+            (HistoryAction::ContentRead, Some(index)) => {
+                if let Some(content) = &item.content {
+                    let novel = &mut novels[index];
+                    let differs = &novel.settings.content_read != content;
+                    if differs && local_time > last_sync {
+                        result
+                            .conflicts
+                            .push(conflict(novel, SyncField::ContentRead, &entry, !remote_wins));
+                    }
+                    if differs && remote_wins {
+                        novel.settings.content_read = content.clone();
+                        novel.settings.last_read = novel.settings.last_read.max(item.time / 1000);
+                        result.db_changed = true;
+                    }
+                }
+            }
+            (HistoryAction::NovelListChange, Some(index)) => {
+                if let Some(list_status) = item.list_status {
+                    let novel = &mut novels[index];
+                    let differs = novel.settings.list_status != list_status;
+                    if differs && local_time > last_sync {
+                        result
+                            .conflicts
+                            .push(conflict(novel, SyncField::ListStatus, &entry, !remote_wins));
+                    }
+                    if differs && remote_wins {
+                        novel.settings.list_status = list_status;
+                        result.db_changed = true;
+                    }
+                }
+            }
+            (HistoryAction::NovelAdd, None) => {
+                if let Some(novel) = &entry.novel {
+                    novels.push(novel.clone());
+                    result.db_changed = true;
+                }
+            }
+            (HistoryAction::NovelUpdate, Some(index)) => {
+                if let (Some(updated), true) = (&entry.novel, remote_wins) {
+                    // Read count and list have their own entries, keep them as they are
+                    let mut updated = updated.clone();
+                    updated.settings.content_read = novels[index].settings.content_read.clone();
+                    updated.settings.list_status = novels[index].settings.list_status;
+                    if novels[index] != updated {
+                        novels[index] = updated;
+                        result.db_changed = true;
+                    }
+                }
+            }
+            (HistoryAction::NovelDelete, Some(index)) => {
+                // Any newer local change to the novel keeps it
+                let changed_later = changed_at
+                    .iter()
+                    .any(|((id, _), time)| id == &item.novel_id && *time > item.time);
+                if !changed_later {
+                    novels.remove(index);
+                    result.db_changed = true;
+                }
+            }
+            // Novel does not exist (anymore) or was already added
+            _ => {}
+        }
+
+        if remote_wins {
+            changed_at.insert(key, item.time);
+        }
+        if seen.insert((item.novel_id.clone(), item.action.clone(), item.time)) {
+            result.history_added.push(item.clone());
+        }
+        let last_seen = state.last_seen.entry(entry.device.clone()).or_insert(0);
+        *last_seen = (*last_seen).max(item.time);
+    }
+
+    result
+}
+
+fn conflict(novel: &Novel, field: SyncField, entry: &JournalEntry, local_kept: bool) -> SyncConflict {
+    SyncConflict {
+        novel_title: novel.title.clone(),
+        field,
+        device: entry.device.clone(),
+        local_kept,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel::{test_novel, ListStatus, NovelContentAmount};
+
+    fn read_item(novel_id: &str, chapters: f32, time: i64) -> NovelHistoryItem {
+        NovelHistoryItem {
+            novel_id: novel_id.to_string(),
+            novel_name: novel_id.to_string(),
+            action: HistoryAction::ContentRead,
+            content: Some(NovelContentAmount::new(0, chapters, 0)),
+            list_status: None,
+            named_chapter: None,
+            chapter_label: None,
+            part: 0,
+            time,
+        }
+    }
+
+    fn entry(device: &str, item: NovelHistoryItem, novel: Option<Novel>) -> JournalEntry {
+        JournalEntry {
+            device: device.to_string(),
+            item,
+            novel,
+        }
+    }
+
+    #[test]
+    fn test_replay_applies_newer_changes_once() {
+        let mut db = Database::new(Some(vec![test_novel("a", "a")]));
+        let mut add = read_item("b", 0.0, 5);
+        add.action = HistoryAction::NovelAdd;
+        let entries = vec![
+            entry("laptop", read_item("a", 3.0, 10), None),
+            entry("laptop", add, Some(test_novel("b", "b"))),
+        ];
+        let mut state = SyncState::default();
+
+        let result = replay(&mut db, &NovelHistory::default(), entries.clone(), &mut state);
+        assert!(result.db_changed);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.history_added.len(), 2);
+        assert_eq!(state.last_seen["laptop"], 10);
+        let novels = db.novels.as_ref().unwrap();
+        assert_eq!(novels.len(), 2);
+        assert_eq!(novels[0].settings.content_read.chapters, 3.0);
+
+        // Already replayed entries are skipped
+        let result = replay(&mut db, &NovelHistory::default(), entries, &mut state);
+        assert!(!result.db_changed);
+        assert!(result.history_added.is_empty());
+    }
+
+    #[test]
+    fn test_replay_conflict_newest_wins() {
+        let mut local = test_novel("a", "a");
+        local.settings.content_read.chapters = 7.0;
+        let mut db = Database::new(Some(vec![local]));
+        let history = NovelHistory {
+            items: vec![read_item("a", 7.0, 20)],
+        };
+        let mut state = SyncState::default();
+
+        // Older remote change loses
+        let result = replay(
+            &mut db,
+            &history,
+            vec![entry("laptop", read_item("a", 3.0, 10), None)],
+            &mut state,
+        );
+        assert_eq!(result.conflicts.len(), 1);
+        assert!(result.conflicts[0].local_kept);
+        assert_eq!(db.novels.as_ref().unwrap()[0].settings.content_read.chapters, 7.0);
+
+        // Newer remote change wins
+        let mut moved = read_item("a", 0.0, 30);
+        moved.action = HistoryAction::NovelListChange;
+        moved.content = None;
+        moved.list_status = Some(ListStatus::Dropped);
+        let result = replay(
+            &mut db,
+            &history,
+            vec![
+                entry("laptop", read_item("a", 9.0, 25), None),
+                entry("laptop", moved, None),
+            ],
+            &mut state,
+        );
+        // Both changed the read count since the last replay, but the remote one is newer
+        assert_eq!(result.conflicts.len(), 1);
+        assert!(!result.conflicts[0].local_kept);
+        let novel = &db.novels.as_ref().unwrap()[0];
+        assert_eq!(novel.settings.content_read.chapters, 9.0);
+        assert_eq!(novel.settings.list_status, ListStatus::Dropped);
+    }
+}
@@ -0,0 +1,210 @@
+//! Title patterns for novel recognition, written by the user for each site or reader app.
+//!
+//! The rules file is TOML with a `[[rule]]` table for each pattern. A pattern is a regex
+//! with named captures for the parts of the title it knows: `novel`, `volume`, `chapter`,
+//! `part`, `side`, `chapter_title` and `source`. The first rule that matches is used
+//! before the generic heuristics, which still guess whatever the rule does not capture.
+
+use crate::file::write_atomic;
+use crate::recognition::NovelRecognitionData;
+use anyhow::Context;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Written to the rules file when there is none yet.
+pub const TEMPLATE: &str = r#"# Title rules for novel recognition
+#
+# Every rule is a regex for the window titles of one site or reader app. The first
+# rule that matches is used, and the parts it does not capture are guessed as usual.
+# Named captures: novel, volume, chapter, part, side, chapter_title and source.
+# Titles are matched after `|` and dashes are turned into `-`, `(?i)` ignores case.
+#
+# [[rule]]
+# name = "Royal Road"
+# pattern = '^(?P<chapter_title>(?i:chapter) (?P<chapter>\d+)[^-]*) - (?P<novel>.+?) - (?P<source>Royal Road)'
+"#;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TitleRule {
+    /// Site or app the rule is for.
+    pub name: String,
+    pub pattern: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RulesFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<TitleRule>,
+}
+
+/// Rules with their patterns compiled, in the order they are tried.
+#[derive(Debug, Clone, Default)]
+pub struct TitleRules {
+    rules: Vec<(TitleRule, Regex)>,
+}
+
+/// What the rule that matched a window title captured.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TitleCaptures {
+    /// Name of the rule.
+    pub rule: String,
+    pub novel: Option<String>,
+    pub volume: Option<i32>,
+    pub chapter: Option<f32>,
+    pub part: Option<i32>,
+    pub side_story: Option<i32>,
+    pub chapter_title: Option<String>,
+    pub source: Option<String>,
+}
+
+impl TitleRules {
+    pub fn parse(text: &str) -> Result<Self, anyhow::Error> {
+        let file: RulesFile = toml::from_str(text).context("Invalid title rules")?;
+        let rules = file
+            .rules
+            .into_iter()
+            .map(|rule| {
+                let regex = Regex::new(&rule.pattern).with_context(|| format!("Invalid pattern in `{}`", rule.name))?;
+                Ok((rule, regex))
+            })
+            .collect::<Result<_, anyhow::Error>>()?;
+
+        Ok(TitleRules { rules })
+    }
+
+    /// Read the rules from `path`, no rules if the file does not exist.
+    pub fn open(path: &Path) -> Result<Self, anyhow::Error> {
+        if !path.exists() {
+            return Ok(TitleRules::default());
+        }
+
+        Self::parse(&read_rules(path)?)
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &TitleRule> {
+        self.rules.iter().map(|(rule, _)| rule)
+    }
+
+    /// Captures of the first rule that matches `window_title`.
+    pub fn captures(&self, window_title: &str) -> Option<TitleCaptures> {
+        self.rules.iter().find_map(|(rule, regex)| {
+            let caps = regex.captures(window_title)?;
+            let text = |name: &str| {
+                caps.name(name)
+                    .map(|m| m.as_str().trim().to_string())
+                    .filter(|text| !text.is_empty())
+            };
+            let number = |name: &str| text(name).and_then(|text| text.parse::<i32>().ok());
+
+            Some(TitleCaptures {
+                rule: rule.name.clone(),
+                novel: text("novel"),
+                volume: number("volume"),
+                chapter: text("chapter").and_then(|text| text.parse().ok()),
+                part: number("part"),
+                side_story: number("side"),
+                chapter_title: text("chapter_title"),
+                source: text("source"),
+            })
+        })
+    }
+}
+
+impl TitleCaptures {
+    /// Replace what the generic heuristics guessed with what was captured.
+    pub fn apply(&self, data: &mut NovelRecognitionData) {
+        if let Some(volume) = self.volume {
+            data.volume = volume;
+        }
+        if let Some(chapter) = self.chapter {
+            // The label the heuristics found may be for another number
+            data.chapter = chapter;
+            data.part = self.part.unwrap_or(0);
+            data.chapter_label = None;
+        } else if let Some(part) = self.part {
+            data.part = part;
+        }
+        if let Some(side_story) = self.side_story {
+            data.side_story = side_story;
+        }
+        if let Some(chapter_title) = &self.chapter_title {
+            data.chapter_title = Some(chapter_title.clone());
+        }
+    }
+}
+
+/// Text of the rules file at `path`, or the `TEMPLATE` if there is no file yet.
+pub fn read_rules(path: &Path) -> Result<String, anyhow::Error> {
+    if !path.exists() {
+        return Ok(TEMPLATE.to_string());
+    }
+
+    fs::read_to_string(path).with_context(|| format!("Cannot read the title rules {}", path.display()))
+}
+
+/// Check the rules in `text` and save them to `path`.
+pub fn write_rules(path: &Path, text: &str) -> Result<TitleRules, anyhow::Error> {
+    let rules = TitleRules::parse(text)?;
+    write_atomic(path, |writer| Ok(writer.write_all(text.as_bytes())?))?;
+
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captures() {
+        let rules = TitleRules::parse(
+            r#"
+[[rule]]
+name = "Reader"
+pattern = '^(?P<novel>.+?) - Vol\. (?P<volume>\d+) Ch\. (?P<chapter>\d+)(?: Part (?P<part>\d+))? - My Reader$'
+
+[[rule]]
+name = "Royal Road"
+pattern = '^(?P<chapter_title>[^-]+) - (?P<novel>.+?) - (?P<source>Royal Road)'
+"#,
+        )
+        .unwrap();
+        assert_eq!(rules.rules().count(), 2);
+
+        assert_eq!(
+            rules.captures("The Novel - Vol. 2 Ch. 14 Part 3 - My Reader"),
+            Some(TitleCaptures {
+                rule: "Reader".to_string(),
+                novel: Some("The Novel".to_string()),
+                volume: Some(2),
+                chapter: Some(14.0),
+                part: Some(3),
+                ..TitleCaptures::default()
+            })
+        );
+
+        let captures = rules
+            .captures("Prologue - The Wandering Inn - Royal Road - Mozilla Firefox")
+            .unwrap();
+        assert_eq!(captures.rule, "Royal Road");
+        assert_eq!(captures.chapter_title.as_deref(), Some("Prologue"));
+        assert_eq!(captures.source.as_deref(), Some("Royal Road"));
+        assert_eq!(captures.chapter, None);
+
+        let mut data = NovelRecognitionData::new(1, 5.0, 0, None, "Source".to_string(), true);
+        captures.apply(&mut data);
+        assert_eq!((data.volume, data.chapter), (1, 5.0));
+        assert_eq!(data.chapter_title.as_deref(), Some("Prologue"));
+
+        assert!(rules.captures("Inbox - Mail").is_none());
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(TitleRules::parse(TEMPLATE).unwrap().rules().next().is_none());
+        assert!(TitleRules::parse("[[rule]]\nname = \"Broken\"\npattern = '(?P<novel>'").is_err());
+        assert!(TitleRules::parse("[[rule]]\nname = \"No pattern\"").is_err());
+    }
+}
@@ -0,0 +1,476 @@
+//! Where novel recognition gets the window titles from.
+//!
+//! Listing windows depends on the platform and, on Wayland, on the compositor, so
+//! every way of doing it is a `TitleSource`. The sources that need a GUI toolkit or
+//! a platform library are implemented by the application, the rest are here.
+
+use anyhow::Context;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Titles reported by a browser extension are forgotten after this long without an update.
+pub const FEED_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub trait TitleSource: Send {
+    /// Titles of the windows that are open right now.
+    fn titles(&mut self) -> Result<Vec<String>, anyhow::Error>;
+
+    /// Which of the windows from the last `titles` has the focus, unknown if the source cannot tell.
+    fn focus(&self) -> Focus {
+        Focus::Unknown
+    }
+}
+
+/// Window with the keyboard focus, as far as the title source can tell.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Focus {
+    /// The source cannot tell, so no window counts as focused.
+    #[default]
+    Unknown,
+    /// Title of the focused window.
+    Window(String),
+    /// A window that was not listed, or none, has the focus.
+    Elsewhere,
+}
+
+impl Focus {
+    /// Whether the window with `title` is known to have the focus.
+    pub fn is_focused(&self, title: &str) -> bool {
+        match self {
+            Focus::Window(focused) => focused == title,
+            Focus::Unknown | Focus::Elsewhere => false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[repr(i32)]
+pub enum TitleSourceKind {
+    /// Pick a source that works with the current desktop.
+    #[default]
+    Auto = 0,
+    /// Window list of Windows, macOS or an X11 server.
+    Native,
+    /// Tree of the sway or i3 window manager over its IPC socket.
+    Sway,
+    /// KWin or GNOME Shell over D-Bus.
+    DBus,
+    /// Tab titles sent by a browser extension to the local API.
+    Browser,
+    /// Titles read from a file, for testing.
+    Replay,
+}
+
+impl ToString for TitleSourceKind {
+    fn to_string(&self) -> String {
+        match self {
+            TitleSourceKind::Auto => fl!("title-source-auto"),
+            TitleSourceKind::Native => fl!("title-source-native"),
+            TitleSourceKind::Sway => fl!("title-source-sway"),
+            TitleSourceKind::DBus => fl!("title-source-dbus"),
+            TitleSourceKind::Browser => fl!("title-source-browser"),
+            TitleSourceKind::Replay => fl!("title-source-replay"),
+        }
+    }
+}
+
+impl TitleSourceKind {
+    pub fn to_i32(&self) -> i32 {
+        *self as i32
+    }
+
+    pub fn vec() -> Vec<String> {
+        (0..6).map(|i| TitleSourceKind::from_i32(i).to_string()).collect()
+    }
+
+    pub fn from_i32(value: i32) -> TitleSourceKind {
+        match value {
+            1 => TitleSourceKind::Native,
+            2 => TitleSourceKind::Sway,
+            3 => TitleSourceKind::DBus,
+            4 => TitleSourceKind::Browser,
+            5 => TitleSourceKind::Replay,
+            _ => TitleSourceKind::Auto,
+        }
+    }
+
+    /// What `Auto` means on this desktop, based on the environment variables of the session.
+    pub fn detect() -> TitleSourceKind {
+        let env = |name| std::env::var(name).unwrap_or_default();
+
+        if !env("SWAYSOCK").is_empty() || !env("I3SOCK").is_empty() {
+            return TitleSourceKind::Sway;
+        }
+
+        let desktop = env("XDG_CURRENT_DESKTOP").to_lowercase();
+        if !env("WAYLAND_DISPLAY").is_empty() && (desktop.contains("kde") || desktop.contains("gnome")) {
+            return TitleSourceKind::DBus;
+        }
+
+        TitleSourceKind::Native
+    }
+}
+
+/// Who reported titles to the feed, each keeps its own report so one does not
+/// replace the titles of the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FeedSender {
+    Browser,
+    DBus,
+}
+
+#[derive(Debug)]
+struct Reported {
+    time: Instant,
+    titles: Vec<String>,
+    /// Title of the active tab or window, if the sender knows it has the focus.
+    focused: Option<String>,
+}
+
+/// Titles last reported by a browser extension or over D-Bus, shared with the local API.
+#[derive(Debug, Clone, Default)]
+pub struct TitleFeed {
+    reports: Arc<RwLock<BTreeMap<FeedSender, Reported>>>,
+}
+
+impl TitleFeed {
+    pub fn report(&self, sender: FeedSender, titles: Vec<String>, focused: Option<String>) {
+        self.reports.write().insert(
+            sender,
+            Reported {
+                time: Instant::now(),
+                titles,
+                focused,
+            },
+        );
+    }
+
+    /// Titles of every sender that has reported some lately.
+    pub fn titles(&self) -> Vec<String> {
+        let mut titles: Vec<String> = vec![];
+        for reported in self.reports.read().values() {
+            if reported.time.elapsed() >= FEED_TIMEOUT {
+                continue;
+            }
+            for title in &reported.titles {
+                if !titles.contains(title) {
+                    titles.push(title.clone());
+                }
+            }
+        }
+
+        titles
+    }
+
+    /// The focused title of the latest report that has one, unknown if none did.
+    pub fn focus(&self) -> Focus {
+        self.reports
+            .read()
+            .values()
+            .filter(|reported| reported.time.elapsed() < FEED_TIMEOUT)
+            .filter_map(|reported| reported.focused.as_ref().map(|focused| (reported.time, focused)))
+            .max_by_key(|(time, _)| *time)
+            .map(|(_, focused)| Focus::Window(focused.clone()))
+            .unwrap_or(Focus::Unknown)
+    }
+}
+
+pub struct BrowserSource {
+    feed: TitleFeed,
+}
+
+impl BrowserSource {
+    pub fn new(feed: TitleFeed) -> Self {
+        BrowserSource { feed }
+    }
+}
+
+impl TitleSource for BrowserSource {
+    fn titles(&mut self) -> Result<Vec<String>, anyhow::Error> {
+        Ok(self.feed.titles())
+    }
+
+    fn focus(&self) -> Focus {
+        self.feed.focus()
+    }
+}
+
+/// Titles of another source with the ones reported to the local API or over D-Bus
+/// added, so the reports go through the usual recognition runs.
+pub struct FeedSource {
+    source: Box<dyn TitleSource>,
+    feed: TitleFeed,
+}
+
+impl FeedSource {
+    pub fn new(source: Box<dyn TitleSource>, feed: TitleFeed) -> Self {
+        FeedSource { source, feed }
+    }
+}
+
+impl TitleSource for FeedSource {
+    fn titles(&mut self) -> Result<Vec<String>, anyhow::Error> {
+        let reported = self.feed.titles();
+        let mut titles = match self.source.titles() {
+            Ok(titles) => titles,
+            Err(e) if reported.is_empty() => return Err(e),
+            Err(_) => vec![],
+        };
+        for title in reported {
+            if !titles.contains(&title) {
+                titles.push(title);
+            }
+        }
+
+        Ok(titles)
+    }
+
+    /// A reported focused title wins, it is newer than what the window list knows.
+    fn focus(&self) -> Focus {
+        match self.feed.focus() {
+            Focus::Window(title) => Focus::Window(title),
+            _ => self.source.focus(),
+        }
+    }
+}
+
+/// Plays back the titles of a replay file, one line each time titles are asked for.
+///
+/// Every line is a JSON array of the window titles that were open at the time.
+/// Once every line has been played no titles are returned.
+pub struct ReplaySource {
+    lines: Vec<Vec<String>>,
+    next: usize,
+}
+
+impl ReplaySource {
+    pub fn open(path: &Path) -> Result<Self, anyhow::Error> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Cannot read the replay file {}", path.display()))?;
+
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, anyhow::Error> {
+        let lines = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| serde_json::from_str(line).with_context(|| format!("Invalid replay line {}", i + 1)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(ReplaySource { lines, next: 0 })
+    }
+}
+
+impl TitleSource for ReplaySource {
+    fn titles(&mut self) -> Result<Vec<String>, anyhow::Error> {
+        let titles = self.lines.get(self.next).cloned().unwrap_or_default();
+        self.next += 1;
+
+        Ok(titles)
+    }
+}
+
+/// Window titles from the tree of sway or i3, found with `SWAYSOCK` or `I3SOCK`.
+#[cfg(unix)]
+pub struct SwaySource {
+    socket: std::path::PathBuf,
+    focus: Focus,
+}
+
+#[cfg(unix)]
+impl SwaySource {
+    const MAGIC: &'static [u8] = b"i3-ipc";
+    const GET_TREE: u32 = 4;
+
+    pub fn new() -> Result<Self, anyhow::Error> {
+        let socket = ["SWAYSOCK", "I3SOCK"]
+            .iter()
+            .filter_map(std::env::var_os)
+            .find(|path| !path.is_empty())
+            .context("Neither SWAYSOCK nor I3SOCK is set")?;
+
+        Ok(SwaySource {
+            socket: socket.into(),
+            focus: Focus::Unknown,
+        })
+    }
+
+    fn get_tree(&self) -> Result<serde_json::Value, anyhow::Error> {
+        use std::io::{Read, Write};
+        use std::os::unix::net::UnixStream;
+
+        let mut stream = UnixStream::connect(&self.socket)
+            .with_context(|| format!("Cannot connect to {}", self.socket.display()))?;
+        stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+
+        let mut message = Self::MAGIC.to_vec();
+        message.extend(0u32.to_ne_bytes());
+        message.extend(Self::GET_TREE.to_ne_bytes());
+        stream.write_all(&message)?;
+
+        let mut header = [0; 14];
+        stream.read_exact(&mut header)?;
+        if &header[..6] != Self::MAGIC {
+            anyhow::bail!("Not an i3 IPC reply");
+        }
+        let length = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]) as usize;
+        let mut payload = vec![0; length];
+        stream.read_exact(&mut payload)?;
+
+        Ok(serde_json::from_slice(&payload)?)
+    }
+}
+
+#[cfg(unix)]
+impl TitleSource for SwaySource {
+    fn titles(&mut self) -> Result<Vec<String>, anyhow::Error> {
+        let mut titles = vec![];
+        let mut focus = Focus::Elsewhere;
+        tree_window_titles(&self.get_tree()?, &mut titles, &mut focus);
+        self.focus = focus;
+
+        Ok(titles)
+    }
+
+    fn focus(&self) -> Focus {
+        self.focus.clone()
+    }
+}
+
+/// Titles of the windows in a sway or i3 tree. Containers have names too,
+/// but only windows have a `pid` (sway) or a `window` id (i3).
+#[cfg_attr(not(unix), allow(dead_code))]
+fn tree_window_titles(node: &serde_json::Value, titles: &mut Vec<String>, focus: &mut Focus) {
+    let is_window = !node["pid"].is_null() || !node["window"].is_null();
+    if is_window {
+        if let Some(name) = node["name"].as_str() {
+            titles.push(name.to_string());
+            if node["focused"].as_bool() == Some(true) {
+                *focus = Focus::Window(name.to_string());
+            }
+        }
+    }
+
+    for key in ["nodes", "floating_nodes"] {
+        for child in node[key].as_array().into_iter().flatten() {
+            tree_window_titles(child, titles, focus);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_replay_source() {
+        let mut source =
+            ReplaySource::parse("[\"Chapter 1 - Novel\", \"Terminal\"]\n\n[]\n[\"Chapter 2 - Novel\"]\n").unwrap();
+
+        assert_eq!(source.titles().unwrap(), vec!["Chapter 1 - Novel", "Terminal"]);
+        assert!(source.titles().unwrap().is_empty());
+        assert_eq!(source.titles().unwrap(), vec!["Chapter 2 - Novel"]);
+        assert!(source.titles().unwrap().is_empty());
+
+        assert!(ReplaySource::parse("[\"fine\"]\nnot json\n").is_err());
+    }
+
+    #[test]
+    fn test_tree_window_titles() {
+        let tree = json!({
+            "name": "root",
+            "nodes": [{
+                "name": "1",
+                "nodes": [
+                    { "name": "Chapter 3 - Novel - Firefox", "pid": 100, "focused": true, "nodes": [] },
+                    { "name": "vim", "window": 4194307, "pid": null, "nodes": [] }
+                ],
+                "floating_nodes": [{ "name": "Reader", "pid": 200 }]
+            }]
+        });
+        let mut titles = vec![];
+        let mut focus = Focus::Elsewhere;
+        tree_window_titles(&tree, &mut titles, &mut focus);
+
+        assert_eq!(titles, vec!["Chapter 3 - Novel - Firefox", "vim", "Reader"]);
+        assert_eq!(focus, Focus::Window("Chapter 3 - Novel - Firefox".to_string()));
+    }
+
+    #[test]
+    fn test_feed_forgets_old_titles() {
+        let feed = TitleFeed::default();
+        assert!(feed.titles().is_empty());
+
+        feed.report(FeedSender::Browser, vec!["Chapter 4 - Novel".to_string()], None);
+        assert_eq!(
+            BrowserSource::new(feed.clone()).titles().unwrap(),
+            vec!["Chapter 4 - Novel"]
+        );
+        assert_eq!(feed.focus(), Focus::Unknown);
+
+        feed.report(
+            FeedSender::Browser,
+            vec!["Chapter 4 - Novel".to_string()],
+            Some("Chapter 4 - Novel".to_string()),
+        );
+        assert!(feed.focus().is_focused("Chapter 4 - Novel"));
+
+        feed.reports.write().get_mut(&FeedSender::Browser).unwrap().time -= FEED_TIMEOUT;
+        assert!(feed.titles().is_empty());
+        assert_eq!(feed.focus(), Focus::Unknown);
+    }
+
+    #[test]
+    fn test_feed_keeps_senders_apart() {
+        let feed = TitleFeed::default();
+        feed.report(
+            FeedSender::Browser,
+            vec!["Chapter 4 - Novel".to_string(), "News".to_string()],
+            Some("Chapter 4 - Novel".to_string()),
+        );
+        feed.report(FeedSender::DBus, vec!["Chapter 9 - Other".to_string()], None);
+
+        assert_eq!(feed.titles(), vec!["Chapter 4 - Novel", "News", "Chapter 9 - Other"]);
+        assert!(feed.focus().is_focused("Chapter 4 - Novel"));
+
+        feed.report(
+            FeedSender::DBus,
+            vec!["Chapter 9 - Other".to_string()],
+            Some("Chapter 9 - Other".to_string()),
+        );
+        assert!(feed.focus().is_focused("Chapter 9 - Other"));
+
+        feed.report(FeedSender::Browser, vec![], None);
+        assert_eq!(feed.titles(), vec!["Chapter 9 - Other"]);
+    }
+
+    #[test]
+    fn test_feed_source_adds_reported_titles() {
+        let feed = TitleFeed::default();
+        let replay = ReplaySource::parse("[\"Chapter 1 - Novel\", \"Terminal\"]\n[\"Terminal\"]\n").unwrap();
+        let mut source = FeedSource::new(Box::new(replay), feed.clone());
+
+        feed.report(
+            FeedSender::Browser,
+            vec!["Chapter 1 - Novel".to_string(), "Chapter 5 - Other".to_string()],
+            Some("Chapter 5 - Other".to_string()),
+        );
+        assert_eq!(
+            source.titles().unwrap(),
+            vec!["Chapter 1 - Novel", "Terminal", "Chapter 5 - Other"]
+        );
+        assert!(source.focus().is_focused("Chapter 5 - Other"));
+
+        feed.report(FeedSender::Browser, vec![], None);
+        assert_eq!(source.titles().unwrap(), vec!["Terminal"]);
+        assert_eq!(source.focus(), Focus::Unknown);
+    }
+}
@@ -0,0 +1,362 @@
+//! Recording the window titles novel recognition sees and replaying them.
+//!
+//! A trace file has one JSON object per line with the time and the window titles
+//! that looked like a novel being read, none when nothing was being read, and the
+//! titles that were skipped because of an ignore keyword. Lines of older traces
+//! with a single `title` are read too. Replaying a trace against a library shows
+//! what every step of the recognition decided, without changing the library.
+
+use crate::history::NovelHistory;
+use crate::ignore::IgnoredTitle;
+use crate::markers::TitleMarkers;
+use crate::novel::Novel;
+use crate::progress::{self, ProgressDecision, ProgressRules};
+use crate::recognition::{clean_window_title_string, recognize, NovelMatch, NovelRecognitionData};
+use crate::title_rules::TitleRules;
+use anyhow::Context;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredTraceLine")]
+pub struct TraceLine {
+    /// Unix timestamp in seconds.
+    pub time: i64,
+    pub titles: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignored: Vec<IgnoredTitle>,
+}
+
+/// `TraceLine` as it is in the file, older traces have one `title` instead of `titles`.
+#[derive(Deserialize)]
+struct StoredTraceLine {
+    time: i64,
+    #[serde(default)]
+    titles: Vec<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    ignored: Vec<IgnoredTitle>,
+}
+
+impl From<StoredTraceLine> for TraceLine {
+    fn from(line: StoredTraceLine) -> Self {
+        let mut titles = line.titles;
+        if titles.is_empty() {
+            titles.extend(line.title);
+        }
+
+        TraceLine {
+            time: line.time,
+            titles,
+            ignored: line.ignored,
+        }
+    }
+}
+
+/// Appends the window titles to a trace file, skipping repeats of the previous line.
+#[derive(Debug)]
+pub struct TraceWriter {
+    path: PathBuf,
+    previous: Option<(Vec<String>, Vec<IgnoredTitle>)>,
+}
+
+impl TraceWriter {
+    pub fn new(path: PathBuf) -> Self {
+        TraceWriter { path, previous: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&mut self, titles: &[String], ignored: &[IgnoredTitle]) -> Result<(), anyhow::Error> {
+        if self
+            .previous
+            .as_ref()
+            .map(|(titles, ignored)| (titles.as_slice(), ignored.as_slice()))
+            == Some((titles, ignored))
+        {
+            return Ok(());
+        }
+
+        let line = TraceLine {
+            time: Local::now().timestamp(),
+            titles: titles.to_vec(),
+            ignored: ignored.to_vec(),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Cannot open the trace file {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&line)?)?;
+        self.previous = Some((titles.to_vec(), ignored.to_vec()));
+
+        Ok(())
+    }
+}
+
+pub fn read_trace(path: &Path) -> Result<Vec<TraceLine>, anyhow::Error> {
+    let content = fs::read_to_string(path).with_context(|| format!("Cannot read the trace file {}", path.display()))?;
+
+    parse_trace(&content)
+}
+
+pub fn parse_trace(content: &str) -> Result<Vec<TraceLine>, anyhow::Error> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).with_context(|| format!("Invalid trace line {}", i + 1)))
+        .collect()
+}
+
+/// What the recognition decided for one line of a trace.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayStep {
+    pub line: TraceLine,
+    /// One for every title of the line, in the same order.
+    pub titles: Vec<TitleReplay>,
+}
+
+/// What the recognition decided for one title of a trace line.
+#[derive(Debug, Clone, Serialize)]
+pub struct TitleReplay {
+    pub title: String,
+    /// Parts of the title, empty if it was not a reading title.
+    pub strings: Vec<String>,
+    /// Name of the title rule that matched.
+    pub rule: Option<String>,
+    pub data: Option<NovelRecognitionData>,
+    /// Name of the novel guessed from the title when the parts did not match a novel.
+    pub novel_title: Option<String>,
+    /// Best match for the novel, also when it was not confident enough to be used.
+    pub matched: Option<NovelMatch>,
+    /// Id and title of the recognized novel.
+    pub novel: Option<(String, String)>,
+    pub decision: Option<ProgressDecision>,
+}
+
+/// Run every title of a trace through the recognition and the progress rules.
+///
+/// The changes are made to `novels` and `history` so the later titles see them,
+/// like they would when reading. The delay before a chapter is counted as read is
+/// not simulated, every recognized chapter is decided on right away. Matches below
+/// the `auto_confidence` are not confirmed.
+pub fn replay(
+    lines: &[TraceLine],
+    title_rules: &TitleRules,
+    markers: &[TitleMarkers],
+    auto_confidence: f32,
+    novels: &mut [Novel],
+    history: &mut NovelHistory,
+    rules: ProgressRules,
+) -> Vec<ReplayStep> {
+    lines
+        .iter()
+        .map(|line| ReplayStep {
+            line: line.clone(),
+            titles: line
+                .titles
+                .iter()
+                .map(|title| replay_title(title, title_rules, markers, auto_confidence, novels, history, rules))
+                .collect(),
+        })
+        .collect()
+}
+
+fn replay_title(
+    title: &str,
+    title_rules: &TitleRules,
+    markers: &[TitleMarkers],
+    auto_confidence: f32,
+    novels: &mut [Novel],
+    history: &mut NovelHistory,
+    rules: ProgressRules,
+) -> TitleReplay {
+    let mut step = TitleReplay {
+        title: title.to_string(),
+        strings: vec![],
+        rule: None,
+        data: None,
+        novel_title: None,
+        matched: None,
+        novel: None,
+        decision: None,
+    };
+
+    let recognition = clean_window_title_string(Some(title.to_string()))
+        .and_then(|title| recognize(novels, title_rules, markers, auto_confidence, &title));
+    if let Some(recognition) = recognition {
+        if let Some(novel) = &recognition.novel {
+            let decision = progress::decide(novel, &recognition.data, history, rules, false);
+            if let ProgressDecision::Changed(change) = &decision {
+                if let Some(novel) = novels.iter_mut().find(|n| n.id == novel.id) {
+                    history.items.extend(change.apply(novel));
+                }
+            }
+
+            step.novel = Some((novel.id.clone(), novel.title.clone()));
+            step.decision = Some(decision);
+        }
+
+        step.strings = recognition.strings;
+        step.rule = recognition.rule;
+        step.data = Some(recognition.data);
+        step.novel_title = Some(recognition.novel_title).filter(|title| title != "?");
+        step.matched = recognition.matched;
+    }
+
+    step
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel::{test_novel, ListStatus, NovelContentAmount};
+    use crate::recognition::{MatchReason, AUTO_CONFIDENCE};
+
+    fn novel(id: &str, title: &str) -> Novel {
+        let mut novel = test_novel(id, title);
+        novel.content = NovelContentAmount::new(0, 100.0, 0);
+        novel
+    }
+
+    /// Replay `trace` against `novels` and return the steps and the novels afterwards.
+    fn replay_trace(trace: &str, mut novels: Vec<Novel>) -> (Vec<ReplayStep>, Vec<Novel>) {
+        let lines = parse_trace(trace).unwrap();
+        let steps = replay(
+            &lines,
+            &TitleRules::default(),
+            &TitleMarkers::defaults(),
+            AUTO_CONFIDENCE,
+            &mut novels,
+            &mut NovelHistory::default(),
+            ProgressRules::default(),
+        );
+
+        (steps, novels)
+    }
+
+    #[test]
+    fn test_record_and_read_trace() {
+        let path = std::env::temp_dir().join(format!("eris-trace-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut writer = TraceWriter::new(path.clone());
+        let titles = vec![
+            "Chapter 1 - Novel - Royal Road".to_string(),
+            "Chapter 5 - Other Novel - Royal Road".to_string(),
+        ];
+        let ignored = vec![IgnoredTitle {
+            title: "Chapter 3 - Manga - Firefox".to_string(),
+            keyword: "Manga".to_string(),
+        }];
+        writer.record(&titles, &[]).unwrap();
+        writer.record(&titles, &[]).unwrap();
+        writer.record(&[], &[]).unwrap();
+        writer.record(&titles, &[]).unwrap();
+        writer.record(&titles, &ignored).unwrap();
+
+        let lines = read_trace(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(lines[3].ignored, ignored);
+        let recorded: Vec<_> = lines.into_iter().map(|line| line.titles).collect();
+        assert_eq!(recorded, vec![titles.clone(), vec![], titles.clone(), titles]);
+
+        // Lines of older traces have a single title
+        let lines =
+            parse_trace("{\"time\": 1, \"title\": \"Chapter 1 - Novel\"}\n{\"time\": 2, \"title\": null}").unwrap();
+        assert_eq!(lines[0].titles, vec!["Chapter 1 - Novel"]);
+        assert!(lines[1].titles.is_empty());
+
+        assert!(parse_trace("{\"time\": 1, \"titles\": []}\nnonsense").is_err());
+    }
+
+    #[test]
+    fn test_replay() {
+        let trace = r#"
+{"time": 1, "title": "Chapter 3 - The Wandering Inn - Mozilla Firefox"}
+{"time": 2, "title": "Chapter 2 - The Wandering Inn - Mozilla Firefox"}
+{"time": 3, "title": null}
+{"time": 4, "title": "Inbox - Mail"}
+{"time": 5, "title": "Unknown Novel - Chapter 7 - Mozilla Firefox"}
+{"time": 6, "title": "第十二章 风起 - 诡秘之主 - Mozilla Firefox"}
+{"time": 7, "title": "Chapter 9 - Wandering Inn - Mozilla Firefox"}
+{"time": 8, "titles": ["Chapter 4 - The Wandering Inn - Mozilla Firefox", "Chapter 6 - Mother of Learning - Mozilla Firefox"]}
+"#;
+        let (steps, novels) = replay_trace(
+            trace,
+            vec![novel("inn", "The Wandering Inn"), novel("mol", "Mother of Learning")],
+        );
+        let step = |i: usize| &steps[i].titles[0];
+
+        assert_eq!(steps.len(), 8);
+        assert_eq!(
+            step(0).novel,
+            Some(("inn".to_string(), "The Wandering Inn".to_string()))
+        );
+        assert_eq!(step(0).data.as_ref().map(|data| data.chapter), Some(3.0));
+        assert!(matches!(
+            &step(0).decision,
+            Some(ProgressDecision::Changed(change)) if change.move_to == Some(ListStatus::Reading)
+        ));
+        // Earlier chapter after a later one does not go backwards
+        assert_eq!(step(1).decision, Some(ProgressDecision::Unchanged));
+        assert!(steps[2].titles.is_empty());
+        assert!(step(3).novel.is_none());
+        assert_eq!(step(4).novel_title.as_deref(), Some("Unknown Novel"));
+        assert!(step(4).novel.is_none());
+        assert_eq!(step(5).data.as_ref().map(|data| data.chapter), Some(12.0));
+        // A similar title is only a match to confirm
+        let matched = step(6).matched.as_ref().unwrap();
+        assert_eq!(
+            (matched.novel_id.as_str(), matched.text.as_str(), matched.reason),
+            ("inn", "Wandering Inn", MatchReason::Similar)
+        );
+        assert!(matched.confidence < AUTO_CONFIDENCE);
+        assert!(step(6).novel.is_none());
+        assert_eq!(step(0).matched.as_ref().map(|matched| matched.confidence), Some(1.0));
+        // Every open novel of a line is replayed
+        let novel_ids: Vec<_> = steps[7]
+            .titles
+            .iter()
+            .filter_map(|title| title.novel.as_ref().map(|(id, _)| id.as_str()))
+            .collect();
+        assert_eq!(novel_ids, vec!["inn", "mol"]);
+
+        assert_eq!(novels[0].settings.content_read, NovelContentAmount::new(0, 4.0, 0));
+        assert_eq!(novels[0].settings.list_status, ListStatus::Reading);
+        assert_eq!(novels[1].settings.content_read, NovelContentAmount::new(0, 6.0, 0));
+    }
+
+    #[test]
+    fn test_replay_with_title_rules() {
+        let rules = TitleRules::parse(
+            "[[rule]]\nname = \"Reader\"\npattern = '^(?P<novel>.+?) \\[(?P<chapter>\\d+)/\\d+\\] - My Reader$'",
+        )
+        .unwrap();
+        let lines = parse_trace(r#"{"time": 1, "title": "The Wandering Inn [12/40] - My Reader"}"#).unwrap();
+        let mut novels = vec![novel("inn", "The Wandering Inn")];
+        let steps = replay(
+            &lines,
+            &rules,
+            &[],
+            AUTO_CONFIDENCE,
+            &mut novels,
+            &mut NovelHistory::default(),
+            ProgressRules::default(),
+        );
+
+        assert_eq!(steps[0].titles[0].rule.as_deref(), Some("Reader"));
+        assert_eq!(
+            steps[0].titles[0].novel.as_ref().map(|(id, _)| id.as_str()),
+            Some("inn")
+        );
+        assert_eq!(novels[0].settings.content_read, NovelContentAmount::new(0, 12.0, 0));
+    }
+}
@@ -3,12 +3,9 @@ plan-to-read = Plan to read
 on-hold = On hold
 completed = Completed
 dropped = Dropped
-abandoned = Abandoned
-ongoing = Ongoing
-hiatus = Hiatus
-original-completed = Original completed
 other = Other
 yes = Yes
+read-only = read-only
 
 current = Current
 previous = Previous
@@ -58,10 +55,6 @@ action-increase-chapter-count = Increase chapter read count
 action-decrease-chapter-count = Decrease chapter read count
 action-open-context-menu = Open context menu
 
-type-web-novel = Web Novel
-type-light-novel = Light Novel
-type-other = Other
-
 about-text = Keeps track of your webnovel reading progress.
 add-novel-file = Add novel from file
 add-novel = Add novel
@@ -83,6 +76,7 @@ update-button = Update
 edit-button = Edit
 delete-button = Delete
 open-dir-button = Open directory
+move-dir-button = Move...
 continue-reading-button = Continue reading
 add-button = Add
 reading-type-button = Set as Completed
@@ -91,10 +85,6 @@ load-history-button = Load complete history
 #
 # History
 #
-added-novel = Added novel
-deleted-novel = Deleted novel
-updated-novel = Updated novel
-moved-novel = Moved to:
 load-label = This can take a while.
 
 
@@ -132,15 +122,44 @@ settings-reg-advanced-title = Advanced
 settings-reg-enable-feature = Enable this feature
 settings-reg-delay = Delay (seconds)
 settings-reg-delay-info = Update list after # seconds
+settings-reg-idle-threshold = Idle after (seconds)
+settings-reg-idle-threshold-info = Pause the delay without input for # seconds, 0 never pauses
+settings-reg-focus-only = Count only the focused window
+settings-reg-focus-only-hint = Where the focus cannot be told nothing is counted, turn this off there to count every open window.
 settings-reg-chapter-read-pref = Chapter read preference
 settings-reg-autocomplete-ongoing = Autocomplete "ongoing" novels
 settings-reg-keywords = Keywords
 settings-reg-ignore-keywords = Ignore keywords
+settings-reg-auto-confidence = Confirm matches below
+settings-reg-auto-confidence-info = Less confident matches wait in Reading Now, 1 is an exact match
+settings-reg-ignore-keywords-hint = Comma separated. `re:` in front makes a regex, ` @ <source>` after only skips the titles that also have the site or app in them, like `Comments @ Royal Road`.
+settings-reg-title-source = Window titles from
+settings-reg-title-rules-title = Title rules
+settings-reg-title-rules-hint = Regex patterns for the window titles of a site or reader app, tried before the usual recognition. Named captures: novel, volume, chapter, part, side, chapter_title and source.
+settings-reg-title-rules-test = Type a window title to test the rules
+settings-reg-title-rules-invalid =
+    Cannot save the title rules.
+    {$error}
+title-rules-none = none, the usual recognition is used
+title-rules-novel-not-found = {$title} (not in the library)
+title-rules-novel-to-confirm = {$title} ({$confidence}% sure, to be confirmed)
+title-rules-not-reading = Not a reading title.
+title-rules-test-result =
+    Rule: {$rule}
+    Novel: {$novel}
+    Volume {$volume}, chapter {$chapter}, side story {$side_story}
+    Chapter title: {$chapter_title}
+    Source: {$source}
 settings-rec-when = When novel is recognized
 settings-rec-when-not = When novel is not recognized
 settings-list-behavior-text = First novel info tab to show
 
 settings-data-dir = Data directory location
+settings-data-storage = Storage
+settings-move-library = Move the library to another folder
+settings-backup-retention = Automatic backups to keep (0 disables them)
+settings-sync-enabled = Sync through a shared folder
+settings-api-enabled = Local API for browser extensions, port
 
 windows-auto-startup = Start automatically with Windows
 windows-start-minimized = Start minimized
@@ -169,8 +188,6 @@ novel-source = Source
 year = Year
 original-publishers = Original publisher
 english-publishers = English publisher
-fully-translated = Translation complete
-not-fully-translated = Incomplete translation
 no-tags = No tags
 no-description = No description
 
@@ -200,6 +217,13 @@ open-file-location = Open file location
 import-text = Import was successfull. Please restart.
 quit = Quit
 
+database-recovery-title = Database could not be read
+database-restored-text = The database file could not be read so the previous copy of it was restored. The most recent changes may be missing.
+database-read-only-text = The database could not be read. Eris was started in read-only mode and no changes to novels are saved.
+database-newer-version-text = The library was saved by a newer version of Eris (schema version {$version}). Eris was started in read-only mode and the files are kept as they are, update Eris to use them.
+database-recovery-tried = Tried:
+database-import-export = Import {$file}
+
 
 #
 # new_dialog.ui
@@ -232,6 +256,13 @@ history = History
 previously-read = Previously read
 not-found = Novel not found.
 not-found-suggestion = Could it be one of these:
+pending-confirmations = Is this the novel being read?
+pending-confirmation = <b>{$novel}</b> for “{$text}” ({$confidence}%, {$reason})
+pending-confirm = Confirm
+pending-dismiss = Dismiss
+match-reason-title = same title
+match-reason-keyword = window title keyword
+match-reason-similar = similar title
 add-to-keywords = (add '{$k}' to novel keywords)
 
 volume = Volume
@@ -240,3 +271,37 @@ chapter = Chapter
 chapters = Chapters
 side-stories = Side stories
 side-story = Side story
+
+menu-restore-backup = Restore from backup...
+menu-profiles = Profiles
+backup-restore-title = Restore from backup
+backup-restore-text = Replace all novels and history with the selected backup. The current data is backed up first.
+backup-restore = Restore
+backup-none = There are no backups yet.
+backup-row = {$time} ({$reason}) - {$novels} novels, {$history} history items
+
+merge-title = Import preview
+merge-apply = Merge
+merge-nothing = Nothing to import, everything in the file is already in the library.
+merge-done-text = Import was merged into the library.
+merge-summary = {$added} new novels, {$updated} updated, {$conflicts} with conflicts and {$unchanged} unchanged. {$history} new history items. Conflicting novels keep their current settings.
+merge-added = + {$title}
+merge-updated = {$title}: read {$read}
+merge-conflict = {$title}: different {$fields}
+
+sync-conflicts-title = Sync conflicts
+sync-conflicts-text = Some novels were changed both on this device and on another one since the last sync.
+sync-conflict = {$title}: {$field}, {$kept}
+sync-kept-local = kept the change from this device
+sync-kept-remote = used the newer change from {$device}
+
+profile-default = Default
+profile-current = {$name} (in use)
+profile-new = New profile...
+profile-new-title = New profile
+profile-new-text = Every profile has its own novels, history, backups and settings. Eris restarts with the new profile.
+profile-create = Create
+profile-switch-error = Could not start Eris with the selected profile.
+
+move-library-title = Select an empty folder for the library
+library-moved-text = The library was moved to {$path}. Eris will now close, start it again to continue.
@@ -0,0 +1,52 @@
+reading = Reading
+plan-to-read = Plan to read
+on-hold = On hold
+completed = Completed
+dropped = Dropped
+
+ongoing = Ongoing
+original-completed = Original completed
+hiatus = Hiatus
+abandoned = Abandoned
+other = Other
+
+type-web-novel = Web Novel
+type-light-novel = Light Novel
+type-other = Other
+fully-translated = Translation complete
+not-fully-translated = Incomplete translation
+
+volume = Volume
+volumes = Volumes
+chapter = Chapter
+chapters = Chapters
+side-story = Side story
+side-stories = Side stories
+
+added-novel = Added novel
+deleted-novel = Deleted novel
+updated-novel = Updated novel
+moved-novel = Moved to:
+
+backup-reason-daily = daily
+backup-reason-quit = on quit
+backup-reason-import = before import
+backup-reason-restore = before restore
+
+merge-field-list = list
+merge-field-score = score
+merge-field-rereading = rereading
+merge-field-notes = notes
+
+sync-field-content-read = read count
+sync-field-list-status = list
+
+storage-file = Files
+storage-sqlite = SQLite database
+
+title-source-auto = Automatic
+title-source-native = Window list (Windows, macOS, X11)
+title-source-sway = Sway / i3
+title-source-dbus = KDE / GNOME (D-Bus)
+title-source-browser = Browser extension
+title-source-replay = Replay file
@@ -2,6 +2,9 @@ use gio::prelude::*;
 use gio::SimpleAction;
 
 use crate::appop::AppOp;
+use eris_core::backup::list_backups;
+use eris_core::export::{export_db_to_json, export_history_to_json};
+use eris_core::profile::profile_name;
 use gtk::prelude::{GtkApplicationExt, GtkWindowExt, WidgetExt};
 
 pub fn new(appop: &AppOp) {
@@ -22,6 +25,9 @@ pub fn new(appop: &AppOp) {
     let select_search_entry = SimpleAction::new("select_search_entry", None);
     let export_db = SimpleAction::new("export_db", None);
     let export_history = SimpleAction::new("export_history", None);
+    let restore_backup = SimpleAction::new("restore_backup", None);
+    let switch_profile = SimpleAction::new("switch_profile", Some(glib::VariantTy::STRING));
+    let new_profile = SimpleAction::new("new_profile", None);
     let update_menu = SimpleAction::new("update_menu", None);
     let switch_active_novel_list = SimpleAction::new("switch_list", None);
 
@@ -39,6 +45,9 @@ pub fn new(appop: &AppOp) {
     app.add_action(&select_search_entry);
     app.add_action(&export_db);
     app.add_action(&export_history);
+    app.add_action(&restore_backup);
+    app.add_action(&switch_profile);
+    app.add_action(&new_profile);
     app.add_action(&update_menu);
     app.add_action(&switch_active_novel_list);
 
@@ -126,12 +135,13 @@ pub fn new(appop: &AppOp) {
 
     export_db.connect_activate(glib::clone!(@strong app_runtime => move |_, _| {
         app_runtime.update_state_with(|state| {
-            match state.export_db_to_json(&state.db.read()) {
+            match export_db_to_json(&state.library.read().db) {
                 Ok(_) => {
                     state.ui.open_post_export_message(&state.app_runtime);
                 },
                 Err(e) => {
                     error!("Could not export db to json. {:?}", e);
+                    state.ui.notification_dialog(&e.to_string());
                 }
             }
         });
@@ -139,17 +149,36 @@ pub fn new(appop: &AppOp) {
 
     export_history.connect_activate(glib::clone!(@strong app_runtime => move |_, _| {
         app_runtime.update_state_with(|state| {
-            match state.export_history_to_json(&state.history.read()) {
+            match export_history_to_json(&state.library.read().history) {
                 Ok(_) => {
                     state.ui.open_post_export_message(&state.app_runtime);
                 },
                 Err(e) => {
                     error!("Could not export history to json. {:?}", e);
+                    state.ui.notification_dialog(&e.to_string());
                 }
             }
         });
     }));
 
+    restore_backup.connect_activate(glib::clone!(@strong app_runtime => move |_, _| {
+        app_runtime.update_state_with(|state| {
+            state.ui.open_restore_backup_dialog(list_backups(), &state.app_runtime);
+        });
+    }));
+
+    switch_profile.connect_activate(glib::clone!(@strong app_runtime => move |_, param| {
+        if let Some(name) = param.and_then(|p| p.get::<String>()) {
+            app_runtime.update_state_with(move |state| state.switch_profile(profile_name(&name).as_deref()));
+        }
+    }));
+
+    new_profile.connect_activate(glib::clone!(@strong app_runtime => move |_, _| {
+        app_runtime.update_state_with(|state| {
+            state.ui.open_new_profile_dialog(&state.app_runtime);
+        });
+    }));
+
     update_menu.connect_activate(glib::clone!(@strong app_runtime => move |_, _| {
         app_runtime.update_state_with(|state| {
             state.open_update_link();
@@ -0,0 +1,120 @@
+//! How long the user has been away, so novel recognition does not count an unattended chapter as read.
+
+use std::time::Duration;
+
+/// Whether there has been no input for `threshold`, or the screen is locked.
+pub fn is_idle(threshold: Duration) -> bool {
+    screen_locked() || idle_time().map_or(false, |idle| idle >= threshold)
+}
+
+/// Time since the last keyboard or mouse input, `None` if the desktop does not tell.
+#[cfg(target_os = "linux")]
+pub fn idle_time() -> Option<Duration> {
+    let mutter = || {
+        dbus_call(
+            "org.gnome.Mutter.IdleMonitor",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "org.gnome.Mutter.IdleMonitor",
+            "GetIdletime",
+            "(t)",
+        )?
+        .get::<(u64,)>()
+        .map(|(milliseconds,)| milliseconds)
+    };
+    // KDE and the other desktops with a screen saver service, the unit is not specified
+    // but KDE, where most users of it are, answers in milliseconds
+    let screen_saver = || {
+        dbus_call(
+            "org.freedesktop.ScreenSaver",
+            "/org/freedesktop/ScreenSaver",
+            "org.freedesktop.ScreenSaver",
+            "GetSessionIdleTime",
+            "(u)",
+        )?
+        .get::<(u32,)>()
+        .map(|(milliseconds,)| milliseconds as u64)
+    };
+
+    mutter().or_else(screen_saver).map(Duration::from_millis)
+}
+
+#[cfg(target_os = "windows")]
+pub fn idle_time() -> Option<Duration> {
+    use winapi::um::sysinfoapi::GetTickCount;
+    use winapi::um::winuser::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // Both are milliseconds since boot that wrap around after 49 days
+    let milliseconds = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+
+    Some(Duration::from_millis(milliseconds as u64))
+}
+
+#[cfg(target_os = "macos")]
+pub fn idle_time() -> Option<Duration> {
+    use std::process::Command;
+
+    let output = Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4", "-r", "-k", "HIDIdleTime"])
+        .output()
+        .ok()?;
+    let nanoseconds = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("\"HIDIdleTime\""))?
+        .rsplit('=')
+        .next()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    Some(Duration::from_nanos(nanoseconds))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub fn idle_time() -> Option<Duration> {
+    None
+}
+
+/// Whether the screen saver or the lock screen is on.
+#[cfg(target_os = "linux")]
+fn screen_locked() -> bool {
+    [
+        ("org.freedesktop.ScreenSaver", "/org/freedesktop/ScreenSaver"),
+        ("org.gnome.ScreenSaver", "/org/gnome/ScreenSaver"),
+    ]
+    .iter()
+    .find_map(|(name, path)| dbus_call(name, path, name, "GetActive", "(b)"))
+    .and_then(|reply| reply.get::<(bool,)>())
+    .map_or(false, |(active,)| active)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn screen_locked() -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn dbus_call(name: &str, path: &str, interface: &str, method: &str, reply_type: &str) -> Option<glib::Variant> {
+    let connection = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>).ok()?;
+
+    connection
+        .call_sync(
+            Some(name),
+            path,
+            interface,
+            method,
+            None,
+            Some(glib::VariantTy::new(reply_type).unwrap()),
+            gio::DBusCallFlags::NONE,
+            1000,
+            None::<&gio::Cancellable>,
+        )
+        .map_err(|e| debug!("No {} from {}. {}", method, name, e))
+        .ok()
+}
@@ -10,17 +10,14 @@ use crate::app::window_state::WindowState;
 use crate::appop::AppOp;
 use crate::ui;
 use crate::utils::Resources;
+use eris_core::backup::BackupReason;
 
-pub mod database;
-pub mod error;
-pub mod history;
+pub mod idle;
 pub mod localize;
-pub mod novel;
 pub mod settings;
+pub mod title_sources;
 pub mod window_state;
 
-pub const NOVEL_UPDATE_COOLDOWN: i64 = 3600;
-
 #[derive(Clone)]
 pub struct AppRuntime(glib::Sender<Box<dyn FnOnce(&mut AppOp)>>);
 
@@ -207,6 +204,7 @@ fn on_shutdown(appop: &AppOp) {
         .write()
         .write_to_file()
         .expect("Cannot save settings to file");
+    appop.backup(BackupReason::Quit);
     // Quit
     appop.quit();
 }
@@ -1,12 +1,14 @@
-use crate::utils::data_dir;
-use crate::DATA_DIR;
+use chrono::Local;
+pub use eris_core::storage::StorageBackend;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
 use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
 pub struct GeneralSettings {
     pub show_sidebar: bool,
-    pub data_dir: PathBuf,
     pub mouse_2_action: NovelListAction,
     pub mouse_3_action: NovelListAction,
     pub mouse_4_action: NovelListAction,
@@ -18,6 +20,20 @@ pub struct GeneralSettings {
     pub start_minimized: bool,
     pub check_update: bool,
     pub window_state_enabled: bool,
+    /// Where novels and history are saved. Requires a restart.
+    pub storage_backend: StorageBackend,
+    /// How many automatic backups to keep. `0` disables them.
+    pub backup_retention: u32,
+    /// Folder shared between devices where the sync journals are kept. `None` disables sync.
+    pub sync_dir: Option<PathBuf>,
+    /// Name of this device in the sync folder, also the name of its journal file.
+    pub sync_device: String,
+    /// Run the local HTTP API for browser extensions and scripts.
+    pub api_enabled: bool,
+    /// Port of the local API, it only listens on `127.0.0.1`.
+    pub api_port: u16,
+    /// Secret that the local API clients send in the `X-Eris-Token` header.
+    pub api_token: String,
 }
 
 impl Default for GeneralSettings {
@@ -30,7 +46,6 @@ impl GeneralSettings {
     pub fn new() -> GeneralSettings {
         GeneralSettings {
             show_sidebar: true,
-            data_dir: data_dir(DATA_DIR),
             mouse_2_action: NovelListAction::ReadNext,
             mouse_3_action: NovelListAction::OpenContextMenu,
             mouse_4_action: NovelListAction::DecreaseChapterCount,
@@ -42,10 +57,34 @@ impl GeneralSettings {
             start_minimized: false,
             check_update: false,
             window_state_enabled: true,
+            storage_backend: StorageBackend::File,
+            backup_retention: 10,
+            sync_dir: None,
+            sync_device: default_device_name(),
+            api_enabled: false,
+            api_port: 17453,
+            api_token: new_api_token(),
         }
     }
 }
 
+/// Host name with a timestamp so two devices with the same host name do not share a journal.
+pub fn default_device_name() -> String {
+    let host = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .unwrap_or_else(|_| "eris".to_string());
+
+    format!("{}-{}", slug::slugify(host), Local::now().timestamp())
+}
+
+/// Random token for the local API, generated once and then kept in the settings file.
+pub fn new_api_token() -> String {
+    // `RandomState` is seeded from the system's random source
+    (0..2)
+        .map(|_| format!("{:016x}", RandomState::new().build_hasher().finish()))
+        .collect()
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 #[repr(i32)]
 pub enum NovelListAction {
@@ -0,0 +1,305 @@
+//! Frozen layouts of the bincode `Settings` file, used only for converting it to TOML.
+
+use crate::app::settings::general::{default_device_name, StorageBackend};
+use crate::app::settings::{
+    ChapterReadPreference, GeneralSettings, ListSettings, NovelListAction, NovelRecognitionSettings, Settings, Sorting,
+};
+use bincode::deserialize_from;
+use eris_core::error::ErisError;
+use eris_core::schema::{Versioned, LEGACY_VERSION};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// `ListSettings` as it was in every bincode schema version.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListSettingsV1 {
+    pub list_sort_order: [Sorting; 6],
+    pub column_width: [HashMap<i32, i32>; 6],
+    pub visible_columns: Vec<bool>,
+    pub open_info_behavior: i32,
+    pub always_open_selected_tab: bool,
+}
+
+/// `NovelRecognitionSettings` as it was in every bincode schema version.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NovelRecognitionSettingsV1 {
+    pub enable: bool,
+    pub delay: i64,
+    pub chapter_read_preference: ChapterReadPreference,
+    pub when_novel_go_to_reading: bool,
+    pub when_not_novel_go_to_reading: bool,
+    pub title_keywords: Vec<String>,
+    pub ignore_keywords: Vec<String>,
+    pub autocomplete_ongoing: bool,
+}
+
+/// `Settings` schema version 1.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsV1 {
+    pub list: ListSettingsV1,
+    pub general: GeneralSettingsV1,
+    pub novel_recognition: NovelRecognitionSettingsV1,
+    pub path: PathBuf,
+}
+
+/// `GeneralSettings` before `storage_backend` was added.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeneralSettingsV1 {
+    pub show_sidebar: bool,
+    pub data_dir: PathBuf,
+    pub mouse_2_action: NovelListAction,
+    pub mouse_3_action: NovelListAction,
+    pub mouse_4_action: NovelListAction,
+    pub mouse_5_action: NovelListAction,
+    pub reader: Option<PathBuf>,
+    pub reader_args: String,
+    pub language: Option<String>,
+    pub open_with_windows: bool,
+    pub start_minimized: bool,
+    pub check_update: bool,
+    pub window_state_enabled: bool,
+}
+
+/// `Settings` schema version 2.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsV2 {
+    pub list: ListSettingsV1,
+    pub general: GeneralSettingsV2,
+    pub novel_recognition: NovelRecognitionSettingsV1,
+    pub path: PathBuf,
+}
+
+/// `GeneralSettings` before `backup_retention` was added.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeneralSettingsV2 {
+    pub show_sidebar: bool,
+    pub data_dir: PathBuf,
+    pub mouse_2_action: NovelListAction,
+    pub mouse_3_action: NovelListAction,
+    pub mouse_4_action: NovelListAction,
+    pub mouse_5_action: NovelListAction,
+    pub reader: Option<PathBuf>,
+    pub reader_args: String,
+    pub language: Option<String>,
+    pub open_with_windows: bool,
+    pub start_minimized: bool,
+    pub check_update: bool,
+    pub window_state_enabled: bool,
+    pub storage_backend: StorageBackend,
+}
+
+impl From<SettingsV1> for SettingsV2 {
+    fn from(old: SettingsV1) -> Self {
+        let general = old.general;
+
+        SettingsV2 {
+            list: old.list,
+            general: GeneralSettingsV2 {
+                show_sidebar: general.show_sidebar,
+                data_dir: general.data_dir,
+                mouse_2_action: general.mouse_2_action,
+                mouse_3_action: general.mouse_3_action,
+                mouse_4_action: general.mouse_4_action,
+                mouse_5_action: general.mouse_5_action,
+                reader: general.reader,
+                reader_args: general.reader_args,
+                language: general.language,
+                open_with_windows: general.open_with_windows,
+                start_minimized: general.start_minimized,
+                check_update: general.check_update,
+                window_state_enabled: general.window_state_enabled,
+                storage_backend: StorageBackend::File,
+            },
+            novel_recognition: old.novel_recognition,
+            path: old.path,
+        }
+    }
+}
+
+impl From<SettingsV2> for SettingsV3 {
+    fn from(old: SettingsV2) -> Self {
+        let general = old.general;
+
+        SettingsV3 {
+            list: old.list,
+            general: GeneralSettingsV3 {
+                show_sidebar: general.show_sidebar,
+                data_dir: general.data_dir,
+                mouse_2_action: general.mouse_2_action,
+                mouse_3_action: general.mouse_3_action,
+                mouse_4_action: general.mouse_4_action,
+                mouse_5_action: general.mouse_5_action,
+                reader: general.reader,
+                reader_args: general.reader_args,
+                language: general.language,
+                open_with_windows: general.open_with_windows,
+                start_minimized: general.start_minimized,
+                check_update: general.check_update,
+                window_state_enabled: general.window_state_enabled,
+                storage_backend: general.storage_backend,
+                backup_retention: GeneralSettings::default().backup_retention,
+            },
+            novel_recognition: old.novel_recognition,
+            path: old.path,
+        }
+    }
+}
+
+/// `Settings` schema version 3.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsV3 {
+    pub list: ListSettingsV1,
+    pub general: GeneralSettingsV3,
+    pub novel_recognition: NovelRecognitionSettingsV1,
+    pub path: PathBuf,
+}
+
+/// `GeneralSettings` before `sync_dir` and `sync_device` were added.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeneralSettingsV3 {
+    pub show_sidebar: bool,
+    pub data_dir: PathBuf,
+    pub mouse_2_action: NovelListAction,
+    pub mouse_3_action: NovelListAction,
+    pub mouse_4_action: NovelListAction,
+    pub mouse_5_action: NovelListAction,
+    pub reader: Option<PathBuf>,
+    pub reader_args: String,
+    pub language: Option<String>,
+    pub open_with_windows: bool,
+    pub start_minimized: bool,
+    pub check_update: bool,
+    pub window_state_enabled: bool,
+    pub storage_backend: StorageBackend,
+    pub backup_retention: u32,
+}
+
+impl From<SettingsV3> for SettingsV4 {
+    fn from(old: SettingsV3) -> Self {
+        let general = old.general;
+
+        SettingsV4 {
+            list: old.list,
+            general: GeneralSettingsV4 {
+                show_sidebar: general.show_sidebar,
+                data_dir: general.data_dir,
+                mouse_2_action: general.mouse_2_action,
+                mouse_3_action: general.mouse_3_action,
+                mouse_4_action: general.mouse_4_action,
+                mouse_5_action: general.mouse_5_action,
+                reader: general.reader,
+                reader_args: general.reader_args,
+                language: general.language,
+                open_with_windows: general.open_with_windows,
+                start_minimized: general.start_minimized,
+                check_update: general.check_update,
+                window_state_enabled: general.window_state_enabled,
+                storage_backend: general.storage_backend,
+                backup_retention: general.backup_retention,
+                sync_dir: None,
+                sync_device: default_device_name(),
+            },
+            novel_recognition: old.novel_recognition,
+            path: old.path,
+        }
+    }
+}
+
+/// `Settings` schema version 4, the last one saved with bincode.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsV4 {
+    pub list: ListSettingsV1,
+    pub general: GeneralSettingsV4,
+    pub novel_recognition: NovelRecognitionSettingsV1,
+    pub path: PathBuf,
+}
+
+/// `GeneralSettings` when settings were saved with bincode.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeneralSettingsV4 {
+    pub show_sidebar: bool,
+    pub data_dir: PathBuf,
+    pub mouse_2_action: NovelListAction,
+    pub mouse_3_action: NovelListAction,
+    pub mouse_4_action: NovelListAction,
+    pub mouse_5_action: NovelListAction,
+    pub reader: Option<PathBuf>,
+    pub reader_args: String,
+    pub language: Option<String>,
+    pub open_with_windows: bool,
+    pub start_minimized: bool,
+    pub check_update: bool,
+    pub window_state_enabled: bool,
+    pub storage_backend: StorageBackend,
+    pub backup_retention: u32,
+    pub sync_dir: Option<PathBuf>,
+    pub sync_device: String,
+}
+
+impl Versioned for SettingsV4 {
+    const VERSION: u32 = 4;
+
+    fn migrate(version: u32, reader: &mut dyn Read) -> Result<Self, anyhow::Error> {
+        match version {
+            // Files from before the schema header have the same layout as version 1
+            LEGACY_VERSION | 1 => {
+                let v2 = SettingsV2::from(deserialize_from::<_, SettingsV1>(reader)?);
+                Ok(SettingsV3::from(v2).into())
+            }
+            2 => Ok(SettingsV3::from(deserialize_from::<_, SettingsV2>(reader)?).into()),
+            3 => Ok(deserialize_from::<_, SettingsV3>(reader)?.into()),
+            _ => Err(ErisError::UnsupportedVersion(version).into()),
+        }
+    }
+}
+
+impl From<SettingsV4> for Settings {
+    fn from(old: SettingsV4) -> Self {
+        let general = old.general;
+        let list = old.list;
+        let novel_recognition = old.novel_recognition;
+
+        Settings {
+            list: ListSettings {
+                list_sort_order: list.list_sort_order,
+                column_width: list.column_width,
+                visible_columns: list.visible_columns,
+                open_info_behavior: list.open_info_behavior,
+                always_open_selected_tab: list.always_open_selected_tab,
+            },
+            general: GeneralSettings {
+                show_sidebar: general.show_sidebar,
+                mouse_2_action: general.mouse_2_action,
+                mouse_3_action: general.mouse_3_action,
+                mouse_4_action: general.mouse_4_action,
+                mouse_5_action: general.mouse_5_action,
+                reader: general.reader,
+                reader_args: general.reader_args,
+                language: general.language,
+                open_with_windows: general.open_with_windows,
+                start_minimized: general.start_minimized,
+                check_update: general.check_update,
+                window_state_enabled: general.window_state_enabled,
+                storage_backend: general.storage_backend,
+                backup_retention: general.backup_retention,
+                sync_dir: general.sync_dir,
+                sync_device: general.sync_device,
+                ..GeneralSettings::default()
+            },
+            novel_recognition: NovelRecognitionSettings {
+                enable: novel_recognition.enable,
+                delay: novel_recognition.delay,
+                chapter_read_preference: novel_recognition.chapter_read_preference,
+                when_novel_go_to_reading: novel_recognition.when_novel_go_to_reading,
+                when_not_novel_go_to_reading: novel_recognition.when_not_novel_go_to_reading,
+                title_keywords: novel_recognition.title_keywords,
+                ignore_keywords: novel_recognition.ignore_keywords,
+                autocomplete_ongoing: novel_recognition.autocomplete_ongoing,
+                ..NovelRecognitionSettings::default()
+            },
+            ..Settings::default()
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use crate::ui::novel_list::Column;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
 pub struct ListSettings {
     /// Sorting order for each list.
     pub list_sort_order: [Sorting; 6],
     /// `HashMap` with `<column id, column width>` values for each list.
+    #[serde(with = "column_width_keys")]
     pub column_width: [HashMap<i32, i32>; 6],
     /// Vector of booleans that decide if the corresponding `Column` is visible or not.
     pub visible_columns: Vec<bool>,
@@ -55,6 +57,7 @@ impl Default for ListSettings {
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Copy, Clone)]
+#[serde(default)]
 pub struct Sorting {
     pub column_id: u32,
     pub is_sort_indicator: bool,
@@ -79,3 +82,32 @@ impl Sorting {
         }
     }
 }
+
+/// TOML keys have to be strings, so the column ids are saved as such.
+mod column_width_keys {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(column_width: &[HashMap<i32, i32>; 6], serializer: S) -> Result<S::Ok, S::Error> {
+        let column_width: Vec<BTreeMap<String, i32>> = column_width
+            .iter()
+            .map(|widths| widths.iter().map(|(id, width)| (id.to_string(), *width)).collect())
+            .collect();
+
+        column_width.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[HashMap<i32, i32>; 6], D::Error> {
+        let saved = Vec::<HashMap<String, i32>>::deserialize(deserializer)?;
+
+        // Ids that are not numbers and lists past the sixth one are ignored
+        let mut column_width: [HashMap<i32, i32>; 6] = Default::default();
+        for (widths, saved) in column_width.iter_mut().zip(saved) {
+            *widths = saved
+                .into_iter()
+                .filter_map(|(id, width)| Some((id.parse().ok()?, width)))
+                .collect();
+        }
+
+        Ok(column_width)
+    }
+}
@@ -1,23 +1,163 @@
 pub mod general;
+mod legacy;
 pub mod list;
 pub mod novel_recognition;
 
-pub use general::{GeneralSettings, NovelListAction};
+pub use general::{GeneralSettings, NovelListAction, StorageBackend};
 pub use list::{ListSettings, Sorting};
 pub use novel_recognition::{ChapterReadPreference, NovelRecognitionSettings};
 
-use crate::app::error::ErisError;
-use crate::{data_dir, CONFIG_NAME};
+use crate::app::settings::legacy::SettingsV4;
+use crate::{BINCODE_CONFIG_NAME, CONFIG_NAME};
 use anyhow::Context;
-use bincode::{deserialize_from, serialize_into};
+use eris_core::error::ErisError;
+use eris_core::file::{set_aside, write_atomic};
+use eris_core::profile::profile_dir;
+use eris_core::schema::{deserialize_versioned, newer_version};
 use serde::{Deserialize, Serialize};
+use std::fs;
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, Write};
 use std::path::PathBuf;
 
+/// Written at the top of the settings file.
+const HEADER: &str = "\
+# Eris settings
+#
+# Unknown keys are ignored and missing keys use their default values,
+# so removing a line resets that setting.
+
+";
+
+/// Comments written above the keys in the settings file, by `table.key`.
+const COMMENTS: &[(&str, &str)] = &[
+    ("general.show_sidebar", "Show the sidebar with the views."),
+    (
+        "general.mouse_2_action",
+        "What mouse buttons 2-5 do in the novel list: Nothing, OpenNovelInfo, EditNovelInfo,\n\
+         ReadNext, IncreaseChapterCount, DecreaseChapterCount or OpenContextMenu.",
+    ),
+    (
+        "general.reader",
+        "Program used to open novels that were added from a file.",
+    ),
+    (
+        "general.reader_args",
+        "Arguments for the reader, `%f` is the file and `%p` the page.",
+    ),
+    (
+        "general.language",
+        "Language code like \"en\", the system language is used when not set.",
+    ),
+    ("general.open_with_windows", "Start Eris when Windows starts."),
+    ("general.start_minimized", "Start Eris minimized."),
+    ("general.check_update", "Check for a new version on startup."),
+    ("general.window_state_enabled", "Remember the window size and position."),
+    (
+        "general.storage_backend",
+        "Where novels and history are saved: File or Sqlite. Changing it in the settings\n\
+         copies the library over, Sqlite also copies the files again if they were saved later.",
+    ),
+    (
+        "general.backup_retention",
+        "How many automatic backups to keep, 0 disables them.",
+    ),
+    (
+        "general.sync_dir",
+        "Folder shared between devices for syncing, sync is disabled when not set.",
+    ),
+    ("general.sync_device", "Name of this device in the sync folder."),
+    (
+        "general.api_enabled",
+        "Run the local HTTP API for browser extensions and scripts.",
+    ),
+    (
+        "general.api_port",
+        "Port of the local API, it only listens on 127.0.0.1.",
+    ),
+    (
+        "general.api_token",
+        "Secret that API clients send in the X-Eris-Token header. Keep it private.",
+    ),
+    (
+        "list.visible_columns",
+        "Visible novel list columns, in the column order.",
+    ),
+    ("list.open_info_behavior", "Tab that the novel dialog opens with."),
+    (
+        "list.always_open_selected_tab",
+        "Always open the tab above instead of the one that was open last.",
+    ),
+    ("novel_recognition.enable", "Recognize novels from window titles."),
+    (
+        "novel_recognition.delay",
+        "Seconds a recognized chapter has to be open in the focused window before it is marked as read.",
+    ),
+    (
+        "novel_recognition.idle_threshold",
+        "Seconds without keyboard or mouse input that pause the delay above, 0 to never pause.",
+    ),
+    (
+        "novel_recognition.focus_only",
+        "Count only the time the window has the focus. Where the focus cannot be told nothing\n\
+         is counted, turn this off there to count every open window.",
+    ),
+    (
+        "novel_recognition.chapter_read_preference",
+        "Mark the Current or the Previous chapter as read.",
+    ),
+    (
+        "novel_recognition.when_novel_go_to_reading",
+        "Show the Reading Now view when a novel is recognized.",
+    ),
+    (
+        "novel_recognition.when_not_novel_go_to_reading",
+        "Show the Reading Now view when a novel is not recognized.",
+    ),
+    (
+        "novel_recognition.title_keywords",
+        "Window titles with any of these are checked for novels.",
+    ),
+    (
+        "novel_recognition.ignore_keywords",
+        "Window titles with any of these are skipped, the other windows are still checked.\n\
+         `re:` in front makes a regex, ` @ <source>` after only skips the titles that also have\n\
+         the site or app in them, like `Comments @ Royal Road`.",
+    ),
+    (
+        "novel_recognition.auto_confidence",
+        "Confidence from 0 to 1 that a title matches a novel before its chapters are counted\n\
+         without asking. Less confident matches wait in Reading Now for a confirmation.",
+    ),
+    (
+        "novel_recognition.autocomplete_ongoing",
+        "Allow completing novels that are still ongoing when the last chapter is read.",
+    ),
+    (
+        "novel_recognition.title_source",
+        "Where window titles come from: Auto, Native (Windows, macOS, X11), Sway (sway or i3),\n\
+         DBus (KDE or GNOME with the Window Calls extension), Browser (POST /titles of the\n\
+         local API) or Replay (the replay file below).",
+    ),
+    (
+        "novel_recognition.replay_file",
+        "File with a JSON array of window titles on each line, played back one line at a time.",
+    ),
+    (
+        "novel_recognition.trace_file",
+        "Record every window title novel recognition sees to this file. Replay it with `eris replay-trace`.",
+    ),
+    (
+        "novel_recognition.title_markers",
+        "Volume, chapter and side story markers of other languages, one table for each language.\n\
+         Markers are regexes for the lowercase title where `<num>` is the number in ASCII,\n\
+         full-width or CJK numerals, or Roman numerals for volumes.",
+    ),
+];
+
 /// Application settings
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-#[serde(rename = "settings")]
+#[serde(rename = "settings", default)]
 pub struct Settings {
     /// Settings specific to novel lists.
     pub list: ListSettings,
@@ -26,12 +166,13 @@ pub struct Settings {
     /// Novel recognition settings.
     pub novel_recognition: NovelRecognitionSettings,
     /// Filepath to the settings file.
+    #[serde(skip)]
     path: PathBuf,
 }
 
 impl Default for Settings {
     fn default() -> Self {
-        let path = data_dir(CONFIG_NAME);
+        let path = profile_dir(CONFIG_NAME);
 
         Settings {
             list: ListSettings::default(),
@@ -44,31 +185,174 @@ impl Default for Settings {
 
 impl Settings {
     pub fn open() -> Result<Self, ErisError> {
-        let path = &data_dir(CONFIG_NAME);
+        let path = &profile_dir(CONFIG_NAME);
         if path.exists() {
-            let f = File::open(path).context(ErisError::ReadFromDisk)?;
-            let reader = BufReader::new(f);
+            let text = fs::read_to_string(path).context(ErisError::ReadFromDisk)?;
 
-            if let Ok(settings) = deserialize_from(reader).context(ErisError::Unknown) {
-                return Ok(settings);
+            match toml::from_str::<Settings>(&text) {
+                Ok(mut settings) => {
+                    settings.use_current_data_dir();
+                    return Ok(settings);
+                }
+                Err(e) => {
+                    // Keep the broken file so the hand edits are not lost for good
+                    error!("Cannot read settings file, using defaults. {}", e);
+                    set_aside(path, "invalid");
+                }
             }
         }
 
-        // If file doesn't exist then set then create it
-        let settings = Settings::default();
+        let settings = match Settings::open_bincode() {
+            Some(settings) => settings,
+            // If file doesn't exist then set then create it
+            None => Settings::default(),
+        };
 
         settings.write_to_file().context(ErisError::WriteToDisk)?;
         Ok(settings)
     }
 
+    /// Read the settings file from before they were saved as TOML. The file is
+    /// renamed afterwards so it is converted only once.
+    fn open_bincode() -> Option<Self> {
+        let path = &profile_dir(BINCODE_CONFIG_NAME);
+        if !path.exists() {
+            return None;
+        }
+
+        let settings = File::open(path)
+            .context(ErisError::ReadFromDisk)
+            .and_then(|f| deserialize_versioned::<_, SettingsV4>(BufReader::new(f)));
+        match settings {
+            Ok(settings) => {
+                info!("Converting {:?} to TOML", path);
+                set_aside(path, "converted");
+
+                let mut settings = Settings::from(settings);
+                settings.use_current_data_dir();
+                Some(settings)
+            }
+            Err(e) => {
+                error!("Cannot read settings file, using defaults. {:#}", e);
+                // A newer Eris can still read it, so it stays where that version looks for it
+                if newer_version(&e).is_none() {
+                    // Keep the unreadable file so the user's config is not lost for good
+                    set_aside(path, "unreadable");
+                }
+                None
+            }
+        }
+    }
+
+    /// Point the settings file to the data directory in use.
+    ///
+    /// The library can be moved, so the path may be out of date.
+    pub fn use_current_data_dir(&mut self) {
+        self.path = profile_dir(CONFIG_NAME);
+    }
+
     pub fn write_to_file(&self) -> Result<(), ErisError> {
         debug!("Saving settings to file.");
 
-        let f = File::create(&self.path).unwrap();
-        let writer = BufWriter::new(f);
-
-        serialize_into(writer, self).context(ErisError::WriteToDisk)?;
+        let text = self.to_toml()?;
+        write_atomic(&self.path, |writer| {
+            writer.write_all(text.as_bytes()).context(ErisError::WriteToDisk)
+        })?;
 
         Ok(())
     }
+
+    /// Settings as TOML with a comment above the keys that have one.
+    fn to_toml(&self) -> Result<String, ErisError> {
+        // Going through `toml::Value` writes the plain values of a table before
+        // its subtables, which serializing the struct directly cannot do
+        let value = toml::Value::try_from(self).context(ErisError::SerializeToFile)?;
+        let text = toml::to_string_pretty(&value).context(ErisError::SerializeToFile)?;
+
+        Ok(with_comments(&text))
+    }
+}
+
+/// Add `HEADER` and the `COMMENTS` to the TOML `text`.
+fn with_comments(text: &str) -> String {
+    let mut commented = String::from(HEADER);
+    let mut table = "";
+
+    for line in text.lines() {
+        if line.starts_with('[') {
+            let header = line.trim_matches(|c| c == '[' || c == ']');
+            // Arrays of tables get their comment above the first one
+            if line.starts_with("[[") && header != table {
+                if let Some((_, comment)) = COMMENTS.iter().find(|(k, _)| *k == header) {
+                    push_comment(&mut commented, comment);
+                }
+            }
+            table = header;
+        } else if let Some((key, _)) = line.split_once(" = ") {
+            let comment = COMMENTS
+                .iter()
+                .find(|(k, _)| k.split_once('.') == Some((table, key)))
+                .map(|(_, comment)| comment);
+            if let Some(comment) = comment {
+                push_comment(&mut commented, comment);
+            }
+        }
+
+        commented.push_str(line);
+        commented.push('\n');
+    }
+
+    commented
+}
+
+fn push_comment(text: &mut String, comment: &str) {
+    for comment_line in comment.lines() {
+        text.push_str("# ");
+        text.push_str(comment_line.trim());
+        text.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_round_trip() {
+        let mut settings = Settings::default();
+        settings.list.column_width[2].insert(4, 120);
+        settings.general.reader = Some(PathBuf::from("/usr/bin/reader"));
+
+        let text = settings.to_toml().unwrap();
+        assert!(text.contains("# the site or app in them, like `Comments @ Royal Road`.\nignore_keywords"));
+        assert_eq!(text.matches("# Volume, chapter and side story markers").count(), 1);
+
+        let mut read: Settings = toml::from_str(&text).unwrap();
+        read.path = settings.path.clone();
+        assert_eq!(read, settings);
+    }
+
+    #[test]
+    fn test_missing_and_unknown_keys() {
+        let text = r#"
+            removed_setting = 1
+
+            [novel_recognition]
+            delay = 30
+            title_keywords = ["Kapitel"]
+            unknown = "value"
+        "#;
+        let settings: Settings = toml::from_str(text).unwrap();
+
+        assert_eq!(settings.novel_recognition.delay, 30);
+        assert_eq!(settings.novel_recognition.title_keywords, vec!["Kapitel"]);
+        assert_eq!(
+            settings.novel_recognition.ignore_keywords,
+            NovelRecognitionSettings::default().ignore_keywords
+        );
+        assert_eq!(
+            settings.general.backup_retention,
+            GeneralSettings::default().backup_retention
+        );
+    }
 }
@@ -1,4 +1,9 @@
+use eris_core::markers::TitleMarkers;
+use eris_core::progress::ProgressRules;
+use eris_core::recognition::AUTO_CONFIDENCE;
+use eris_core::titles::TitleSourceKind;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::str::FromStr;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -51,12 +56,19 @@ impl ChapterReadPreference {
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
 pub struct NovelRecognitionSettings {
     /// Enable novel recognition system.
     pub enable: bool,
     /// Delay in seconds. How long the recognized novel has to be
     /// "active" before the chapter is recorded.
     pub delay: i64,
+    /// Seconds without input after which the user counts as idle
+    /// and the delay is paused. 0 turns this off.
+    pub idle_threshold: i64,
+    /// Only the focused window counts towards the delay. Where the focus
+    /// cannot be told nothing counts, so without it every open window does.
+    pub focus_only: bool,
     /// Should the current chapter be saved as read,
     /// or perhaps the previous one.
     pub chapter_read_preference: ChapterReadPreference,
@@ -68,8 +80,19 @@ pub struct NovelRecognitionSettings {
     pub title_keywords: Vec<String>,
     /// Ignore window titles with these words.
     pub ignore_keywords: Vec<String>,
+    /// Confidence from 0 to 1 a novel match needs to count chapters without
+    /// asking, the less confident matches wait for a confirmation.
+    pub auto_confidence: f32,
     /// Allow autocompleting novel with status "ongoing".
     pub autocomplete_ongoing: bool,
+    /// Where the window titles come from.
+    pub title_source: TitleSourceKind,
+    /// Titles to play back with `TitleSourceKind::Replay`.
+    pub replay_file: Option<PathBuf>,
+    /// Record the recognized window titles to this file, for `eris replay-trace`.
+    pub trace_file: Option<PathBuf>,
+    /// Volume, chapter and side story markers of other languages.
+    pub title_markers: Vec<TitleMarkers>,
 }
 
 impl Default for NovelRecognitionSettings {
@@ -89,12 +112,27 @@ impl NovelRecognitionSettings {
         NovelRecognitionSettings {
             enable,
             delay: 120,
+            idle_threshold: 300,
+            focus_only: true,
             chapter_read_preference,
             when_novel_go_to_reading: true,
             when_not_novel_go_to_reading: true,
             title_keywords,
             ignore_keywords,
+            auto_confidence: AUTO_CONFIDENCE,
             autocomplete_ongoing,
+            title_source: TitleSourceKind::Auto,
+            replay_file: None,
+            trace_file: None,
+            title_markers: TitleMarkers::defaults(),
+        }
+    }
+
+    /// Rules for counting chapters as read.
+    pub fn progress_rules(&self) -> ProgressRules {
+        ProgressRules {
+            read_previous: self.chapter_read_preference == ChapterReadPreference::Previous,
+            autocomplete_ongoing: self.autocomplete_ongoing,
         }
     }
 }
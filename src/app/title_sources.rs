@@ -0,0 +1,514 @@
+//! Window title sources that need the platform libraries of the application.
+
+use crate::app::settings::NovelRecognitionSettings;
+use anyhow::{anyhow, Context};
+use eris_core::titles::{BrowserSource, FeedSource, Focus, ReplaySource, TitleFeed, TitleSource, TitleSourceKind};
+use window_titles::{Connection, ConnectionTrait};
+
+/// Open the title source selected in `settings`.
+pub fn open_title_source(
+    settings: &NovelRecognitionSettings,
+    feed: &TitleFeed,
+) -> Result<Box<dyn TitleSource>, anyhow::Error> {
+    let kind = match settings.title_source {
+        TitleSourceKind::Auto => TitleSourceKind::detect(),
+        kind => kind,
+    };
+    debug!("Window titles from {:?}", kind);
+
+    let source: Box<dyn TitleSource> = match kind {
+        TitleSourceKind::Auto | TitleSourceKind::Native => Box::new(NativeSource::default()),
+        #[cfg(unix)]
+        TitleSourceKind::Sway => Box::new(eris_core::titles::SwaySource::new()?),
+        #[cfg(target_os = "linux")]
+        TitleSourceKind::DBus => Box::new(DBusSource::new()),
+        TitleSourceKind::Browser => return Ok(Box::new(BrowserSource::new(feed.clone()))),
+        TitleSourceKind::Replay => {
+            let path = settings.replay_file.as_ref().context("No replay file is set")?;
+            return Ok(Box::new(ReplaySource::open(path)?));
+        }
+        #[allow(unreachable_patterns)]
+        kind => return Err(anyhow!("{} is not available on this platform", kind.to_string())),
+    };
+
+    // Titles reported to the local API or over D-Bus are recognized along with the windows
+    Ok(Box::new(FeedSource::new(source, feed.clone())))
+}
+
+/// Window list of Windows, macOS or X11.
+#[derive(Default)]
+pub struct NativeSource {
+    focus: Focus,
+}
+
+impl TitleSource for NativeSource {
+    fn titles(&mut self) -> Result<Vec<String>, anyhow::Error> {
+        // A new connection every time so a restarted X server is picked up
+        let connection = Connection::new().map_err(|e| anyhow!("Cannot list windows. {:?}", e))?;
+
+        let titles = connection
+            .window_titles()
+            .map_err(|e| anyhow!("Cannot list windows. {:?}", e))?;
+        #[cfg(target_os = "windows")]
+        {
+            self.focus = foreground_title().map_or(Focus::Elsewhere, Focus::Window);
+        }
+        #[cfg(unix)]
+        {
+            self.focus = active_window_focus();
+        }
+
+        Ok(titles)
+    }
+
+    fn focus(&self) -> Focus {
+        self.focus.clone()
+    }
+}
+
+/// Title of the foreground window.
+#[cfg(target_os = "windows")]
+fn foreground_title() -> Option<String> {
+    use winapi::um::winuser::{GetForegroundWindow, GetWindowTextLengthW, GetWindowTextW};
+
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.is_null() {
+            return None;
+        }
+        let mut text = vec![0u16; GetWindowTextLengthW(window) as usize + 1];
+        let length = GetWindowTextW(window, text.as_mut_ptr(), text.len() as i32);
+        if length <= 0 {
+            return None;
+        }
+
+        Some(String::from_utf16_lossy(&text[..length as usize]))
+    }
+}
+
+/// Focus from the `_NET_ACTIVE_WINDOW` property of the X11 root window, read with `xprop`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn active_window_focus() -> Focus {
+    use std::process::Command;
+
+    let xprop = |args: &[&str]| -> Option<String> {
+        let output = Command::new("xprop").arg("-notype").args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        xprop_value(&String::from_utf8_lossy(&output.stdout))
+    };
+
+    match xprop(&["-root", "_NET_ACTIVE_WINDOW"]) {
+        None => Focus::Unknown,
+        // Nothing is active, e.g. after clicking the desktop
+        Some(window) if window == "0x0" => Focus::Elsewhere,
+        Some(window) => xprop(&["-id", &window, "_NET_WM_NAME"])
+            .or_else(|| xprop(&["-id", &window, "WM_NAME"]))
+            .map_or(Focus::Unknown, Focus::Window),
+    }
+}
+
+/// Window id or string in the first line of `xprop -notype` output, e.g.
+/// `_NET_ACTIVE_WINDOW: window id # 0x3a00007` or `_NET_WM_NAME = "Chapter 4"`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn xprop_value(output: &str) -> Option<String> {
+    let line = output.lines().next()?;
+    if let Some((_, window)) = line.split_once("window id # ") {
+        return window.split(',').next().map(|window| window.trim().to_string());
+    }
+    let quoted = line.split_once(" = ")?.1.trim();
+    let quoted = quoted.strip_prefix('"')?.strip_suffix('"')?;
+
+    // Quotes and backslashes in the string are escaped with a backslash
+    let mut value = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next()),
+            c => value.push(c),
+        }
+    }
+
+    Some(value)
+}
+
+/// Title of the front window of `NSWorkspace.frontmostApplication`. The window names
+/// need the same screen recording permission as the window list.
+#[cfg(target_os = "macos")]
+const FRONT_WINDOW_SCRIPT: &str = r#"
+ObjC.import('AppKit');
+ObjC.import('CoreGraphics');
+const app = $.NSWorkspace.sharedWorkspace.frontmostApplication;
+const windows = ObjC.deepUnwrap(ObjC.castRefToObject($.CGWindowListCopyWindowInfo(
+    $.kCGWindowListOptionOnScreenOnly | $.kCGWindowListExcludeDesktopElements, 0)));
+const front = windows.find(w => w.kCGWindowOwnerPID === app.processIdentifier && w.kCGWindowLayer === 0);
+front && front.kCGWindowName ? front.kCGWindowName : '';
+"#;
+
+/// Focus from the frontmost application, through JavaScript for Automation.
+#[cfg(target_os = "macos")]
+fn active_window_focus() -> Focus {
+    use std::process::Command;
+
+    match Command::new("osascript")
+        .args(["-l", "JavaScript", "-e", FRONT_WINDOW_SCRIPT])
+        .output()
+    {
+        Ok(output) if output.status.success() => match String::from_utf8_lossy(&output.stdout).trim_end_matches('\n') {
+            "" => Focus::Elsewhere,
+            title => Focus::Window(title.to_string()),
+        },
+        _ => Focus::Unknown,
+    }
+}
+
+/// Window captions from KWin or, with the Window Calls extension, GNOME Shell.
+#[cfg(target_os = "linux")]
+pub struct DBusSource {
+    focus: Focus,
+    kwin_focus: Option<KWinFocus>,
+}
+
+#[cfg(target_os = "linux")]
+impl DBusSource {
+    const TIMEOUT_MS: i32 = 1000;
+
+    /// Has to be called on the main thread, where the focus reports of KWin are handled.
+    pub fn new() -> DBusSource {
+        let kwin_focus = KWinFocus::register()
+            .map_err(|e| warn!("The focused window of KWin is not available. {:#}", e))
+            .ok();
+
+        DBusSource {
+            focus: Focus::Unknown,
+            kwin_focus,
+        }
+    }
+
+    fn kwin_focus(&self, connection: &gio::DBusConnection) -> Focus {
+        self.kwin_focus
+            .as_ref()
+            .map_or(Focus::Unknown, |kwin_focus| kwin_focus.focus(connection))
+    }
+
+    /// The windows runner of KRunner lists every window for the `window` keyword.
+    fn kwin_titles(connection: &gio::DBusConnection) -> Result<Vec<String>, anyhow::Error> {
+        use glib::ToVariant;
+        use std::collections::HashMap;
+
+        let reply = connection.call_sync(
+            Some("org.kde.KWin"),
+            "/WindowsRunner",
+            "org.kde.krunner1",
+            "Match",
+            Some(&("window",).to_variant()),
+            Some(glib::VariantTy::new("(a(sssida{sv}))").unwrap()),
+            gio::DBusCallFlags::NONE,
+            Self::TIMEOUT_MS,
+            None::<&gio::Cancellable>,
+        )?;
+        let (matches,) = reply
+            .get::<(Vec<(String, String, String, i32, f64, HashMap<String, glib::Variant>)>,)>()
+            .context("Unexpected reply from KWin")?;
+
+        Ok(matches.into_iter().map(|(_, text, ..)| text).collect())
+    }
+
+    fn gnome_titles(connection: &gio::DBusConnection) -> Result<(Vec<String>, Focus), anyhow::Error> {
+        use glib::ToVariant;
+
+        let call = |method: &str, parameters: Option<glib::Variant>| -> Result<String, anyhow::Error> {
+            let reply = connection.call_sync(
+                Some("org.gnome.Shell"),
+                "/org/gnome/Shell/Extensions/Windows",
+                "org.gnome.Shell.Extensions.Windows",
+                method,
+                parameters.as_ref(),
+                Some(glib::VariantTy::new("(s)").unwrap()),
+                gio::DBusCallFlags::NONE,
+                Self::TIMEOUT_MS,
+                None::<&gio::Cancellable>,
+            )?;
+
+            Ok(reply.get::<(String,)>().context("Unexpected reply from GNOME Shell")?.0)
+        };
+
+        let windows: Vec<serde_json::Value> = serde_json::from_str(&call("List", None)?)?;
+        let mut titles = vec![];
+        let mut focus = Focus::Unknown;
+        for window in windows {
+            // Older versions of the extension only have the title behind `GetTitle`
+            let title = match (window["title"].as_str(), window["id"].as_u64()) {
+                (Some(title), _) => title.to_string(),
+                (None, Some(id)) => call("GetTitle", Some((id as u32,).to_variant()))?,
+                _ => continue,
+            };
+            // Older versions of the extension do not tell the focus either
+            match window["focus"].as_bool() {
+                Some(true) => focus = Focus::Window(title.clone()),
+                Some(false) if focus == Focus::Unknown => focus = Focus::Elsewhere,
+                _ => {}
+            }
+            titles.push(title);
+        }
+
+        Ok((titles, focus))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl TitleSource for DBusSource {
+    fn titles(&mut self) -> Result<Vec<String>, anyhow::Error> {
+        let connection = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>)?;
+
+        let (titles, focus) = Self::kwin_titles(&connection)
+            .map(|titles| (titles, self.kwin_focus(&connection)))
+            .or_else(|kwin_error| {
+                Self::gnome_titles(&connection).map_err(|gnome_error| {
+                    anyhow!(
+                        "Neither KWin ({}) nor the Window Calls extension of GNOME Shell ({}) answered",
+                        kwin_error,
+                        gnome_error
+                    )
+                })
+            })?;
+        self.focus = focus;
+
+        Ok(titles)
+    }
+
+    fn focus(&self) -> Focus {
+        self.focus.clone()
+    }
+}
+
+/// The active window of KWin. KWin has no D-Bus method for it, so a KWin script
+/// calls back with its caption. The focus is the one reported for the run before,
+/// so it lags by a run.
+#[cfg(target_os = "linux")]
+struct KWinFocus {
+    connection: gio::DBusConnection,
+    registration: Option<gio::RegistrationId>,
+    plugin_name: String,
+    /// Directory created for the script when there is no `$XDG_RUNTIME_DIR`.
+    script_dir: Option<std::path::PathBuf>,
+    script: std::path::PathBuf,
+    caption: std::sync::Arc<parking_lot::Mutex<Option<String>>>,
+}
+
+#[cfg(target_os = "linux")]
+impl KWinFocus {
+    const INTERFACE: &'static str = const_format::formatcp!("{}.KWinFocus", eris_core::APPLICATION_ID);
+
+    /// Write the script and export the object it calls.
+    fn register() -> Result<KWinFocus, anyhow::Error> {
+        use glib::ToVariant;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // A restarted recognition registers again before the old one is dropped
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+
+        let connection = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>)?;
+        let bus_name = connection.unique_name().context("No unique name on the session bus")?;
+        let object_path = format!("/{}/KWinFocus{}", eris_core::APPLICATION_ID.replace('.', "/"), count);
+        let plugin_name = format!("eris-focus-{}-{}", std::process::id(), count);
+
+        let xml = format!(
+            r#"<node><interface name="{}"><method name="Report"><arg type="s" name="caption" direction="in"/></method></interface></node>"#,
+            Self::INTERFACE
+        );
+        let interface = gio::DBusNodeInfo::for_xml(&xml)?
+            .lookup_interface(Self::INTERFACE)
+            .context("Invalid D-Bus interface description")?;
+
+        // Plasma 6 has `activeWindow`, Plasma 5 `activeClient`
+        let (script_dir, script) = Self::write_script(
+            &plugin_name,
+            &format!(
+                "const window = workspace.activeWindow || workspace.activeClient;\n\
+                 callDBus(\"{}\", \"{}\", \"{}\", \"Report\", window ? window.caption : \"\");\n",
+                bus_name,
+                object_path,
+                Self::INTERFACE
+            ),
+        )?;
+
+        let caption = std::sync::Arc::new(parking_lot::Mutex::new(None));
+        let mut kwin_focus = KWinFocus {
+            connection: connection.clone(),
+            registration: None,
+            plugin_name,
+            script_dir,
+            script,
+            caption: caption.clone(),
+        };
+        kwin_focus.registration = Some(connection.register_object(
+            &object_path,
+            &interface,
+            move |_, _, _, _, _, parameters, invocation| {
+                *caption.lock() = parameters.get::<(String,)>().map(|(caption,)| caption);
+                invocation.return_value(None);
+            },
+            |_, _, _, _, _| ().to_variant(),
+            |_, _, _, _, _, _| false,
+        )?);
+
+        Ok(kwin_focus)
+    }
+
+    /// Write the script where only this user can read or replace it, in `$XDG_RUNTIME_DIR`
+    /// or else a new 0700 directory of its own. Refuses to use a file or directory that
+    /// is already there. Returns the created directory, if any, and the script.
+    fn write_script(
+        plugin_name: &str,
+        contents: &str,
+    ) -> Result<(Option<std::path::PathBuf>, std::path::PathBuf), anyhow::Error> {
+        use std::io::Write;
+        use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+
+        let (script_dir, dir) = match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+            Some(dir) => (None, std::path::PathBuf::from(dir)),
+            None => {
+                let dir = std::env::temp_dir().join(plugin_name);
+                std::fs::DirBuilder::new()
+                    .mode(0o700)
+                    .create(&dir)
+                    .with_context(|| format!("Cannot create {}", dir.display()))?;
+                (Some(dir.clone()), dir)
+            }
+        };
+
+        let script = dir.join(format!("{}.js", plugin_name));
+        let written = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&script)
+            .with_context(|| format!("Cannot create {}", script.display()))
+            .and_then(|mut file| {
+                let written = file
+                    .write_all(contents.as_bytes())
+                    .with_context(|| format!("Cannot write {}", script.display()));
+                if written.is_err() {
+                    let _ = std::fs::remove_file(&script);
+                }
+                written
+            });
+        if let Err(e) = written {
+            if let Some(script_dir) = &script_dir {
+                let _ = std::fs::remove_dir(script_dir);
+            }
+            return Err(e);
+        }
+
+        Ok((script_dir, script))
+    }
+
+    /// The last reported focus. Runs the script again for the next one.
+    fn focus(&self, connection: &gio::DBusConnection) -> Focus {
+        let focus = match self.caption.lock().clone() {
+            None => Focus::Unknown,
+            Some(caption) if caption.is_empty() => Focus::Elsewhere,
+            Some(caption) => Focus::Window(caption),
+        };
+        if let Err(e) = self.run_script(connection) {
+            debug!("Cannot run the focus script in KWin. {:#}", e);
+        }
+
+        focus
+    }
+
+    fn run_script(&self, connection: &gio::DBusConnection) -> Result<(), anyhow::Error> {
+        use glib::ToVariant;
+
+        let call = |path: &str, interface: &str, method: &str, parameters: Option<glib::Variant>, reply: &str| {
+            connection.call_sync(
+                Some("org.kde.KWin"),
+                path,
+                interface,
+                method,
+                parameters.as_ref(),
+                Some(glib::VariantTy::new(reply).unwrap()),
+                gio::DBusCallFlags::NONE,
+                DBusSource::TIMEOUT_MS,
+                None::<&gio::Cancellable>,
+            )
+        };
+
+        // The script of the run before may still be loaded
+        self.unload_script(connection);
+        let script = self.script.to_string_lossy().to_string();
+        let (id,) = call(
+            "/Scripting",
+            "org.kde.kwin.Scripting",
+            "loadScript",
+            Some((script, self.plugin_name.clone()).to_variant()),
+            "(i)",
+        )?
+        .get::<(i32,)>()
+        .context("Unexpected reply from KWin")?;
+
+        // Plasma 5 has the script object at `/<id>`
+        call(
+            &format!("/Scripting/Script{}", id),
+            "org.kde.kwin.Script",
+            "run",
+            None,
+            "()",
+        )
+        .or_else(|_| call(&format!("/{}", id), "org.kde.kwin.Script", "run", None, "()"))?;
+
+        Ok(())
+    }
+
+    fn unload_script(&self, connection: &gio::DBusConnection) {
+        use glib::ToVariant;
+
+        let _ = connection.call_sync(
+            Some("org.kde.KWin"),
+            "/Scripting",
+            "org.kde.kwin.Scripting",
+            "unloadScript",
+            Some(&(self.plugin_name.clone(),).to_variant()),
+            Some(glib::VariantTy::new("(b)").unwrap()),
+            gio::DBusCallFlags::NONE,
+            DBusSource::TIMEOUT_MS,
+            None::<&gio::Cancellable>,
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for KWinFocus {
+    fn drop(&mut self) {
+        self.unload_script(&self.connection);
+        if let Some(registration) = self.registration.take() {
+            let _ = self.connection.unregister_object(registration);
+        }
+        let _ = std::fs::remove_file(&self.script);
+        if let Some(script_dir) = &self.script_dir {
+            let _ = std::fs::remove_dir(script_dir);
+        }
+    }
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xprop_value() {
+        assert_eq!(
+            xprop_value("_NET_ACTIVE_WINDOW: window id # 0x3a00007\n"),
+            Some("0x3a00007".to_string())
+        );
+        assert_eq!(
+            xprop_value(r#"_NET_WM_NAME = "Chapter 4 \"The \\ Gate\" - Novel""#),
+            Some(r#"Chapter 4 "The \ Gate" - Novel"#.to_string())
+        );
+        assert_eq!(xprop_value("_NET_WM_NAME:  not found."), None);
+    }
+}
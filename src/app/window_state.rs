@@ -1,12 +1,14 @@
 use gtk::prelude::*;
 
-use crate::app::error::ErisError;
-use crate::{data_dir, STATE_CONFIG_NAME};
+use crate::STATE_CONFIG_NAME;
 use anyhow::Context;
 use bincode::{deserialize_from, serialize_into};
+use eris_core::error::ErisError;
+use eris_core::file::write_atomic;
+use eris_core::paths::data_dir;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::BufReader;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct WindowState {
@@ -77,10 +79,9 @@ impl WindowState {
 
     pub fn write_to_file(&self) -> Result<(), ErisError> {
         let path = data_dir(STATE_CONFIG_NAME);
-        let f = File::create(&path).unwrap();
-        let writer = BufWriter::new(f);
-
-        serialize_into(writer, self).context(ErisError::WriteToDisk)?;
+        write_atomic(&path, |writer| {
+            serialize_into(writer, self).context(ErisError::SerializeToFile)
+        })?;
 
         Ok(())
     }
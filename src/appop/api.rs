@@ -0,0 +1,632 @@
+//! Opt-in HTTP/JSON API on localhost for browser extensions and scripts.
+//!
+//! Window titles lose the address of the page, so a browser extension can report
+//! the exact url and chapter instead. The server threads only read the requests,
+//! everything that touches the library is done by `AppOp` on the main loop which
+//! sends the response back.
+//!
+//! Every request besides `GET /` needs the `X-Eris-Token` header with the token
+//! from the settings, so other web pages cannot change the library.
+
+use crate::app::settings::GeneralSettings;
+use crate::app::AppRuntime;
+use crate::appop::AppOp;
+use crate::VERSION;
+use eris_core::novel::{ChapterRead, ListStatus, Novel, NovelSettings};
+use eris_core::parsers::{fetch_novel, novel_url};
+use eris_core::titles::FeedSender;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use url::Url;
+
+/// Header with the API token.
+pub const API_TOKEN_HEADER: &str = "x-eris-token";
+/// Request bodies are small JSON objects, anything larger is refused.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+/// Limit for the request line and the headers together.
+const MAX_HEAD_SIZE: u64 = 16 * 1024;
+/// Connections answered at the same time, more are closed right away.
+const MAX_CONNECTIONS: usize = 16;
+
+#[derive(Clone)]
+pub struct ApiServer {
+    running: Arc<AtomicBool>,
+    handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    port: u16,
+}
+
+impl ApiServer {
+    /// Start listening on `127.0.0.1` if the API is enabled in `settings`.
+    pub fn new(app_runtime: AppRuntime, settings: &GeneralSettings) -> Option<ApiServer> {
+        if !settings.api_enabled {
+            return None;
+        }
+
+        let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, settings.api_port)) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Cannot start the local API on port {}. {}", settings.api_port, e);
+                return None;
+            }
+        };
+
+        let (tx, rx) = glib::MainContext::channel::<(ApiRequest, mpsc::Sender<ApiResponse>)>(glib::PRIORITY_DEFAULT);
+        rx.attach(
+            None,
+            glib::clone!(@strong app_runtime => @default-return glib::Continue(false), move |message: (ApiRequest, mpsc::Sender<ApiResponse>)| {
+                let (request, reply) = message;
+                app_runtime.update_state_with(move |state| {
+                    let _ = reply.send(state.api_request(request));
+                });
+
+                glib::Continue(true)
+            }),
+        );
+
+        let running = Arc::new(AtomicBool::new(true));
+        let connections = Arc::new(AtomicUsize::new(0));
+        let token = settings.api_token.clone();
+        let thread_running = running.clone();
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if !thread_running.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                // Each request gets its own thread, so downloading a novel or waiting for
+                // the main loop never keeps the listener from seeing that it has to stop
+                match stream {
+                    Ok(stream) => {
+                        if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                            connections.fetch_sub(1, Ordering::SeqCst);
+                            warn!("Too many local API connections, closing a new one.");
+                            continue;
+                        }
+                        let slot = ConnectionSlot(connections.clone());
+                        let token = token.clone();
+                        let tx = tx.clone();
+                        thread::spawn(move || {
+                            let _slot = slot;
+                            handle_connection(stream, &token, &tx)
+                        });
+                    }
+                    Err(e) => warn!("Local API connection failed. {}", e),
+                }
+            }
+        });
+        info!("Local API listening on 127.0.0.1:{}", settings.api_port);
+
+        Some(ApiServer {
+            running,
+            handle: Arc::new(Mutex::new(Some(handle))),
+            port: settings.api_port,
+        })
+    }
+
+    /// Stop the server and wait until the port is free again.
+    ///
+    /// Only the listener thread is joined. Requests that are still being answered
+    /// finish on their own threads, which may wait for the main loop calling this.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        // Wake up the thread waiting for a connection so it sees that it has to stop
+        let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port));
+
+        if let Some(handle) = self.handle.lock().take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Counts a connection as open until it is answered.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// What the main loop is asked to do.
+#[derive(Debug, PartialEq)]
+pub enum ApiRequest {
+    /// Find the novel that the url belongs to.
+    Lookup {
+        url: String,
+    },
+    /// Novels in the reading list.
+    ReadingList,
+    /// A chapter of the novel in `url` is being read.
+    Reading {
+        url: String,
+        volume: i32,
+        chapter: f32,
+        side_story: i32,
+    },
+    /// Download the novel in `url` and add it. Handled on the request thread,
+    /// which sends `AddNovel` once the novel is downloaded.
+    Add {
+        url: String,
+        status: ListStatus,
+    },
+    AddNovel(Novel),
+    /// Titles of the open browser tabs and the active one, for novel recognition.
+    Titles {
+        titles: Vec<String>,
+        focused: Option<String>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiResponse {
+    pub fn ok(body: Value) -> Self {
+        ApiResponse { status: 200, body }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        ApiResponse {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            502 => "Bad Gateway",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    /// Header names in lowercase.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    fn query(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Deserialize)]
+struct ReadingBody {
+    url: String,
+    #[serde(default)]
+    volume: i32,
+    chapter: f32,
+    #[serde(default)]
+    side_story: i32,
+}
+
+#[derive(Deserialize)]
+struct TitlesBody {
+    titles: Vec<String>,
+    /// Active tab of the focused browser window.
+    #[serde(default)]
+    focused: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AddBody {
+    url: String,
+    #[serde(default)]
+    status: ListStatus,
+}
+
+fn handle_connection(mut stream: TcpStream, token: &str, tx: &glib::Sender<(ApiRequest, mpsc::Sender<ApiResponse>)>) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+
+    let response = match read_request(&mut BufReader::new(&stream)) {
+        Ok(request) => respond(&request, token, |api_request| dispatch(tx, api_request)),
+        Err(response) => response,
+    };
+
+    if let Err(e) = write_response(&mut stream, &response) {
+        debug!("Cannot answer a local API request. {}", e);
+    }
+}
+
+/// Send `request` to the main loop and wait for the response.
+fn dispatch(tx: &glib::Sender<(ApiRequest, mpsc::Sender<ApiResponse>)>, request: ApiRequest) -> ApiResponse {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    if tx.send((request, reply_tx)).is_err() {
+        return ApiResponse::error(503, "Eris is closing");
+    }
+
+    reply_rx
+        .recv_timeout(Duration::from_secs(10))
+        .unwrap_or_else(|_| ApiResponse::error(503, "Eris did not answer"))
+}
+
+/// Read the request line, headers and body of one HTTP/1.1 request.
+pub fn read_request(reader: &mut impl BufRead) -> Result<HttpRequest, ApiResponse> {
+    let bad_request = || ApiResponse::error(400, "Malformed request");
+
+    // A line without an end cannot fill the memory
+    let mut head = reader.by_ref().take(MAX_HEAD_SIZE);
+    let mut line = String::new();
+    head.read_line(&mut line).map_err(|_| bad_request())?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return Err(bad_request()),
+    };
+
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        if head.read_line(&mut line).map_err(|_| bad_request())? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        let (name, value) = line.split_once(':').ok_or_else(bad_request)?;
+        headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+    }
+    if head.limit() == 0 {
+        return Err(ApiResponse::error(431, "Request headers are too large"));
+    }
+
+    let mut request = HttpRequest {
+        method,
+        headers,
+        ..Default::default()
+    };
+
+    let target = Url::parse(&format!("http://localhost{}", target)).map_err(|_| bad_request())?;
+    request.path = target.path().to_string();
+    request.query = target.query_pairs().into_owned().collect();
+
+    let length: usize = match request.header("content-length") {
+        Some(length) => length.parse().map_err(|_| bad_request())?,
+        None => 0,
+    };
+    if length > MAX_BODY_SIZE {
+        return Err(ApiResponse::error(413, "Request body is too large"));
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body).map_err(|_| bad_request())?;
+
+    Ok(request)
+}
+
+/// Answer `request`, passing what needs the library to `dispatch`.
+pub fn respond(request: &HttpRequest, token: &str, dispatch: impl Fn(ApiRequest) -> ApiResponse) -> ApiResponse {
+    if request.method == "OPTIONS" {
+        // CORS preflight of a browser extension
+        return ApiResponse {
+            status: 204,
+            body: Value::Null,
+        };
+    }
+    if request.method == "GET" && request.path == "/" {
+        return ApiResponse::ok(json!({ "name": "eris", "version": VERSION }));
+    }
+    if token.is_empty() || request.header(API_TOKEN_HEADER) != Some(token) {
+        return ApiResponse::error(401, "Missing or wrong X-Eris-Token header");
+    }
+
+    match route(request) {
+        Ok(ApiRequest::Add { url, status }) => {
+            let url = novel_url(&url);
+            if dispatch(ApiRequest::Lookup { url: url.clone() }).status == 200 {
+                return ApiResponse::error(409, "The novel is already in the library");
+            }
+
+            match fetch_novel(&url) {
+                Ok(mut novel) => {
+                    novel.settings = NovelSettings {
+                        list_status: status,
+                        ..NovelSettings::default()
+                    };
+                    dispatch(ApiRequest::AddNovel(novel))
+                }
+                Err(e) => ApiResponse::error(502, &e.to_string()),
+            }
+        }
+        Ok(api_request) => dispatch(api_request),
+        Err(response) => response,
+    }
+}
+
+fn route(request: &HttpRequest) -> Result<ApiRequest, ApiResponse> {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/novel") => {
+            let url = request
+                .query("url")
+                .ok_or_else(|| ApiResponse::error(400, "Missing the url parameter"))?;
+
+            Ok(ApiRequest::Lookup { url: url.to_string() })
+        }
+        ("GET", "/reading-list") => Ok(ApiRequest::ReadingList),
+        ("POST", "/reading") => {
+            let body: ReadingBody = parse_body(request)?;
+
+            Ok(ApiRequest::Reading {
+                url: body.url,
+                volume: body.volume,
+                chapter: body.chapter,
+                side_story: body.side_story,
+            })
+        }
+        ("POST", "/novels") => {
+            let body: AddBody = parse_body(request)?;
+
+            Ok(ApiRequest::Add {
+                url: body.url,
+                status: body.status,
+            })
+        }
+        ("POST", "/titles") => {
+            let body: TitlesBody = parse_body(request)?;
+
+            Ok(ApiRequest::Titles {
+                titles: body.titles,
+                focused: body.focused,
+            })
+        }
+        (_, "/novel" | "/reading-list" | "/reading" | "/novels" | "/titles") => {
+            Err(ApiResponse::error(405, "Method not allowed"))
+        }
+        _ => Err(ApiResponse::error(404, "Unknown endpoint")),
+    }
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(request: &'a HttpRequest) -> Result<T, ApiResponse> {
+    serde_json::from_slice(&request.body).map_err(|e| ApiResponse::error(400, &e.to_string()))
+}
+
+fn write_response(stream: &mut impl Write, response: &ApiResponse) -> std::io::Result<()> {
+    let body = if response.body.is_null() {
+        String::new()
+    } else {
+        response.body.to_string()
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type, X-Eris-Token\r\n\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        body.len(),
+        body
+    )?;
+
+    stream.flush()
+}
+
+impl AppOp {
+    /// Do what the local API was asked to.
+    pub fn api_request(&mut self, request: ApiRequest) -> ApiResponse {
+        let not_found = || ApiResponse::error(404, "No novel with that url in the library");
+
+        match request {
+            ApiRequest::Lookup { url } => match self.get_by_slug(url) {
+                Some(novel) => ApiResponse::ok(json!(novel)),
+                None => not_found(),
+            },
+            ApiRequest::ReadingList => {
+                let novels: Vec<Novel> = self
+                    .library
+                    .read()
+                    .novels()
+                    .iter()
+                    .filter(|n| n.settings.list_status == ListStatus::Reading)
+                    .cloned()
+                    .collect();
+
+                ApiResponse::ok(json!(novels))
+            }
+            ApiRequest::Reading {
+                url,
+                volume,
+                chapter,
+                side_story,
+            } => {
+                let novel = match self.get_by_slug(url) {
+                    Some(novel) => novel,
+                    None => return not_found(),
+                };
+                let novel_id = novel.id.clone();
+
+                // Same as a chapter found from a window title, read counts do not go backwards
+                self.chapter_read(ChapterRead {
+                    volume,
+                    chapter,
+                    side: side_story,
+                    exact_num: false,
+                    novel,
+                });
+
+                match self.get_by_id(novel_id) {
+                    Some(novel) => ApiResponse::ok(json!(novel)),
+                    None => not_found(),
+                }
+            }
+            ApiRequest::AddNovel(novel) => {
+                if self.library.read().db.read_only {
+                    return ApiResponse::error(503, "The library is read-only");
+                }
+                if let Some(slug) = &novel.slug {
+                    if self.get_by_slug(slug.clone()).is_some() {
+                        return ApiResponse::error(409, "The novel is already in the library");
+                    }
+                }
+                if self.get_by_id(novel.id.clone()).is_some() {
+                    return ApiResponse::error(409, "The novel is already in the library");
+                }
+
+                self.add_novel_to_db(novel.clone());
+
+                ApiResponse {
+                    status: 201,
+                    body: json!(novel),
+                }
+            }
+            ApiRequest::Titles { titles, focused } => {
+                self.title_feed.report(FeedSender::Browser, titles, focused);
+
+                ApiResponse {
+                    status: 204,
+                    body: Value::Null,
+                }
+            }
+            ApiRequest::Add { .. } => ApiResponse::error(400, "Novels are added by the request thread"),
+        }
+    }
+
+    /// Start or stop the local API to match `settings`.
+    pub fn restart_api_server(&mut self, settings: &GeneralSettings) {
+        if let Some(api_server) = self.api_server.take() {
+            api_server.stop();
+        }
+
+        self.api_server = ApiServer::new(self.app_runtime.clone(), settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn request(text: &str) -> HttpRequest {
+        read_request(&mut Cursor::new(text.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn test_read_and_route_requests() {
+        let lookup = request("GET /novel?url=https%3A%2F%2Fexample.com%2Fn%2F1 HTTP/1.1\r\nX-Eris-Token: abc\r\n\r\n");
+        assert_eq!(lookup.header(API_TOKEN_HEADER), Some("abc"));
+        assert_eq!(
+            route(&lookup).unwrap(),
+            ApiRequest::Lookup {
+                url: "https://example.com/n/1".to_string()
+            }
+        );
+
+        let body = r#"{"url": "https://example.com/n/1/chapter-5", "chapter": 5}"#;
+        let reading = request(&format!(
+            "POST /reading HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        assert_eq!(
+            route(&reading).unwrap(),
+            ApiRequest::Reading {
+                url: "https://example.com/n/1/chapter-5".to_string(),
+                volume: 0,
+                chapter: 5.0,
+                side_story: 0,
+            }
+        );
+
+        let body = r#"{"titles": ["Chapter 5 - Novel - Royal Road", "Inbox"], "focused": "Inbox"}"#;
+        let titles = request(&format!(
+            "POST /titles HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        assert_eq!(
+            route(&titles).unwrap(),
+            ApiRequest::Titles {
+                titles: vec!["Chapter 5 - Novel - Royal Road".to_string(), "Inbox".to_string()],
+                focused: Some("Inbox".to_string()),
+            }
+        );
+
+        assert_eq!(
+            route(&request("GET /reading HTTP/1.1\r\n\r\n")).unwrap_err().status,
+            405
+        );
+        assert_eq!(
+            route(&request("GET /nothing HTTP/1.1\r\n\r\n")).unwrap_err().status,
+            404
+        );
+        assert_eq!(
+            route(&request("POST /novels HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}"))
+                .unwrap_err()
+                .status,
+            400
+        );
+        assert!(read_request(&mut Cursor::new(b"nonsense".as_ref())).is_err());
+    }
+
+    #[test]
+    fn test_token_is_required() {
+        let dispatch = |_| ApiResponse::ok(json!([]));
+
+        let response = respond(&request("GET /reading-list HTTP/1.1\r\n\r\n"), "abc", dispatch);
+        assert_eq!(response.status, 401);
+        let response = respond(
+            &request("GET /reading-list HTTP/1.1\r\nX-Eris-Token: abc\r\n\r\n"),
+            "abc",
+            dispatch,
+        );
+        assert_eq!(response.status, 200);
+        let response = respond(
+            &request("GET /reading-list HTTP/1.1\r\nX-Eris-Token: \r\n\r\n"),
+            "",
+            dispatch,
+        );
+        assert_eq!(response.status, 401);
+
+        assert_eq!(
+            respond(&request("OPTIONS /reading HTTP/1.1\r\n\r\n"), "abc", dispatch).status,
+            204
+        );
+        assert_eq!(respond(&request("GET / HTTP/1.1\r\n\r\n"), "abc", dispatch).status, 200);
+    }
+
+    #[test]
+    fn test_headers_are_limited() {
+        let text = format!(
+            "GET / HTTP/1.1\r\nX-Long: {}\r\n\r\n",
+            "a".repeat(MAX_HEAD_SIZE as usize)
+        );
+        let response = read_request(&mut Cursor::new(text.as_bytes())).unwrap_err();
+        assert_eq!(response.status, 431);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::appop::AppOp;
+use eris_core::backup::{has_backup_today, Backup, BackupReason};
+use eris_core::database::set_aside_database_file;
+use std::path::PathBuf;
+
+impl AppOp {
+    /// Back up the novels and history currently in memory.
+    ///
+    /// Does nothing if backups are disabled or the database is read-only,
+    /// backing up an empty read-only database would only push good backups out.
+    pub fn backup(&self, reason: BackupReason) {
+        let retention = self.settings.read().general.backup_retention;
+        let library = self.library.read();
+        if retention == 0 || library.db.read_only {
+            return;
+        }
+
+        if let Err(e) = Backup::create(&library.db, &library.history, reason, retention) {
+            error!("Could not create a backup. {:#}", e);
+        }
+    }
+
+    /// Back up on the first start of the day.
+    pub fn daily_backup(&self) {
+        if !has_backup_today() {
+            self.backup(BackupReason::Daily);
+        }
+    }
+
+    /// Replace the novels and history with the contents of the backup in `path`.
+    ///
+    /// The current data is backed up first so the restore can be undone.
+    pub fn restore_backup(&mut self, path: PathBuf) -> Result<(), anyhow::Error> {
+        let mut backup = Backup::open(&path)?;
+
+        self.backup(BackupReason::Restore);
+
+        // A restored database can be written to even if the current one could not be read,
+        // but keep the unreadable file out of the way so it does not end up as the `.bak`
+        let mut library = self.library.write();
+        if library.db.read_only {
+            set_aside_database_file();
+        }
+        backup.db.read_only = false;
+        library.storage().write_database(&mut backup.db)?;
+        library.storage().write_history(&backup.history)?;
+
+        library.db = backup.db;
+        library.history = backup.history;
+        drop(library);
+        self.database_recovery = None;
+
+        info!("Restored backup {:?}", path);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+//! D-Bus interface for launchers, panels and scripts on Linux.
+//!
+//! The object is exported next to the `org.gtk.Application` one that GTK already
+//! provides, so it is found under the bus name of the application, e.g.
+//! `com.github.temeez.eris`, and the path `/com/github/temeez/eris`.
+
+use crate::app::AppRuntime;
+use crate::appop::AppOp;
+use const_format::formatcp;
+use eris_core::history::NovelHistoryItem;
+use eris_core::novel::{ChapterRead, NovelContentAmount};
+use eris_core::recognition::{clean_window_title_string, recognize};
+use eris_core::titles::FeedSender;
+use eris_core::APPLICATION_ID;
+use gio::prelude::*;
+use glib::{ToVariant, Variant};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+pub const DBUS_INTERFACE: &str = formatcp!("{APPLICATION_ID}.Library");
+const DBUS_ERROR: &str = formatcp!("{APPLICATION_ID}.Error.Failed");
+
+const INTERFACE_XML: &str = formatcp!(
+    r#"
+<node>
+  <interface name="{DBUS_INTERFACE}">
+    <method name="ReportReading">
+      <arg type="s" name="url_or_title" direction="in"/>
+      <arg type="b" name="focused" direction="in"/>
+      <arg type="b" name="recognized" direction="out"/>
+    </method>
+    <method name="GetCurrentlyReading">
+      <arg type="s" name="id" direction="out"/>
+      <arg type="s" name="title" direction="out"/>
+      <arg type="s" name="progress" direction="out"/>
+    </method>
+    <method name="MarkChapterRead">
+      <arg type="s" name="id" direction="in"/>
+      <arg type="s" name="progress" direction="in"/>
+      <arg type="s" name="content_read" direction="out"/>
+    </method>
+    <signal name="HistoryChanged">
+      <arg type="s" name="id"/>
+      <arg type="s" name="title"/>
+      <arg type="s" name="action"/>
+      <arg type="s" name="details"/>
+    </signal>
+  </interface>
+</node>
+"#
+);
+
+pub struct DBusService {
+    connection: gio::DBusConnection,
+    object_path: String,
+}
+
+impl DBusService {
+    /// Export the interface on the session bus connection of `gtk_app`.
+    pub fn register(gtk_app: &gtk::Application, app_runtime: AppRuntime) -> Option<DBusService> {
+        let (connection, object_path) = match (gtk_app.dbus_connection(), gtk_app.dbus_object_path()) {
+            (Some(connection), Some(object_path)) => (connection, object_path.to_string()),
+            _ => {
+                warn!("No D-Bus connection, the D-Bus interface is not available.");
+                return None;
+            }
+        };
+
+        let interface = match gio::DBusNodeInfo::for_xml(INTERFACE_XML)
+            .ok()
+            .and_then(|node| node.lookup_interface(DBUS_INTERFACE))
+        {
+            Some(interface) => interface,
+            None => {
+                error!("Invalid D-Bus interface description.");
+                return None;
+            }
+        };
+
+        let result = connection.register_object(
+            &object_path,
+            &interface,
+            move |_, _, _, _, method, parameters, invocation| {
+                let method = method.to_string();
+                app_runtime.update_state_with(move |state| match state.dbus_method_call(&method, &parameters) {
+                    Ok(value) => invocation.return_value(Some(&value)),
+                    Err(message) => invocation.return_dbus_error(DBUS_ERROR, &message),
+                });
+            },
+            |_, _, _, _, _| ().to_variant(),
+            |_, _, _, _, _, _| false,
+        );
+        if let Err(e) = result {
+            error!("Cannot register the D-Bus interface. {}", e);
+            return None;
+        }
+        info!("D-Bus interface {} registered at {}", DBUS_INTERFACE, object_path);
+
+        Some(DBusService {
+            connection,
+            object_path,
+        })
+    }
+
+    /// Let the listeners know about a new history item.
+    pub fn emit_history_changed(&self, item: &NovelHistoryItem) {
+        let parameters = (
+            item.novel_id.clone(),
+            item.novel_name.clone(),
+            format!("{:?}", item.action),
+            item.detail_string(),
+        )
+            .to_variant();
+
+        if let Err(e) = self.connection.emit_signal(
+            None,
+            &self.object_path,
+            DBUS_INTERFACE,
+            "HistoryChanged",
+            Some(&parameters),
+        ) {
+            warn!("Cannot emit the HistoryChanged D-Bus signal. {}", e);
+        }
+    }
+}
+
+impl AppOp {
+    /// Handle a call to the D-Bus interface.
+    fn dbus_method_call(&mut self, method: &str, parameters: &Variant) -> Result<Variant, String> {
+        match method {
+            "ReportReading" => {
+                let (url_or_title, focused) = parameters
+                    .get::<(String, bool)>()
+                    .ok_or_else(|| "Expected a string and a boolean".to_string())?;
+
+                Ok((self.report_reading(url_or_title, focused),).to_variant())
+            }
+            "GetCurrentlyReading" => {
+                let novel = self.currently_reading.novel.read().clone();
+                let novel = novel.and_then(|novel| self.get_by_id(novel.id));
+
+                Ok(match novel {
+                    Some(novel) => (novel.id, novel.title, novel.settings.content_read.to_string(false)).to_variant(),
+                    None => (String::new(), String::new(), String::new()).to_variant(),
+                })
+            }
+            "MarkChapterRead" => {
+                let (novel_id, progress) = parameters
+                    .get::<(String, String)>()
+                    .ok_or_else(|| "Expected two strings".to_string())?;
+                let novel = self
+                    .get_by_id(novel_id.clone())
+                    .ok_or_else(|| format!("No novel with the id {}", novel_id))?;
+                let amount = NovelContentAmount::parse(&progress)
+                    .ok_or_else(|| format!("`{}` is not a read amount, use something like `v2c34ss1`", progress))?;
+
+                self.chapter_read(ChapterRead {
+                    volume: amount.volumes,
+                    chapter: amount.chapters,
+                    side: amount.side_stories,
+                    exact_num: true,
+                    novel,
+                });
+
+                let novel = self
+                    .get_by_id(novel_id)
+                    .ok_or_else(|| "The novel was removed".to_string())?;
+                Ok((novel.settings.content_read.to_string(false),).to_variant())
+            }
+            _ => Err(format!("Unknown method {}", method)),
+        }
+    }
+
+    /// Handle a chapter url, or pass a window title to novel recognition like the
+    /// titles of a browser extension, as the focused window only if `focused` is set.
+    /// Returns `true` if it belongs to a novel.
+    fn report_reading(&mut self, url_or_title: String, focused: bool) -> bool {
+        if !url_or_title.starts_with("http") {
+            let recognized = self.recognizes(&url_or_title);
+            let focused_title = if focused { Some(url_or_title.clone()) } else { None };
+            self.title_feed
+                .report(FeedSender::DBus, vec![url_or_title], focused_title);
+
+            return recognized;
+        }
+
+        let novel = match self.get_by_slug(url_or_title.clone()) {
+            Some(novel) => novel,
+            None => return false,
+        };
+        if let Some(chapter) = chapter_from_url(&url_or_title) {
+            self.chapter_read(ChapterRead {
+                volume: novel.settings.content_read.volumes,
+                chapter,
+                side: novel.settings.content_read.side_stories,
+                exact_num: false,
+                novel,
+            });
+        }
+
+        true
+    }
+
+    /// Whether novel recognition would find a novel in the library from `title`.
+    fn recognizes(&self, title: &str) -> bool {
+        let title = match clean_window_title_string(Some(title.to_string())) {
+            Some(title) => title,
+            None => return false,
+        };
+        let library = self.library.read();
+        let settings = self.settings.read();
+
+        recognize(
+            library.novels(),
+            &self.title_rules,
+            &settings.novel_recognition.title_markers,
+            settings.novel_recognition.auto_confidence,
+            &title,
+        )
+        .and_then(|recognition| recognition.novel)
+        .is_some()
+    }
+}
+
+/// Chapter number from an url ending like `/chapter-12` or `/ch_12.5/`.
+fn chapter_from_url(url: &str) -> Option<f32> {
+    lazy_static! {
+        static ref RE_CHAPTER: Regex = Regex::new(r"(?i)/(?:chapter|ch|c)[-_]?(\d+(?:\.\d+)?)[^/]*/?$").unwrap();
+    }
+
+    RE_CHAPTER
+        .captures(url)
+        .and_then(|caps| caps.get(1))
+        .and_then(|chapter| chapter.as_str().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chapter_from_url() {
+        assert_eq!(chapter_from_url("https://example.com/novel/chapter-12"), Some(12.0));
+        assert_eq!(chapter_from_url("https://example.com/novel/ch_12.5/"), Some(12.5));
+        assert_eq!(chapter_from_url("https://example.com/novel/c3-the-end"), Some(3.0));
+        assert_eq!(chapter_from_url("https://example.com/chapter-1/novel"), None);
+        assert_eq!(chapter_from_url("https://example.com/novel/"), None);
+    }
+}
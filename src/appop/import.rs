@@ -0,0 +1,79 @@
+use crate::appop::AppOp;
+use eris_core::backup::BackupReason;
+use eris_core::database::set_aside_database_file;
+use eris_core::error::ErisError;
+use eris_core::export::{read_export, ImportData};
+use eris_core::merge::MergePreview;
+use gtk::prelude::GtkWindowExt;
+use std::path::{Path, PathBuf};
+
+impl AppOp {
+    /// Read and validate a JSON export and show a preview of merging it
+    /// into the current novels or history.
+    pub fn import_json(&mut self, file_path: String) -> Result<(), ErisError> {
+        let data = read_export(Path::new(&file_path))?;
+
+        self.backup(BackupReason::Import);
+
+        let preview = match data {
+            ImportData::Database(db) => {
+                debug!("Importing db");
+                let current = self.library.read().novels().to_vec();
+                MergePreview::novels(&current, db.novels.as_deref().unwrap_or_default())
+            }
+            ImportData::History(history) => {
+                debug!("Importing history");
+                MergePreview::history(&self.library.read().history, &history)
+            }
+        };
+        self.ui.open_merge_preview_dialog(preview, &self.app_runtime);
+
+        Ok(())
+    }
+
+    /// Apply the previewed import to the novels and history, save them and update the lists.
+    pub fn apply_merge(&mut self, preview: MergePreview) {
+        let result = self.library.write().apply_merge(&preview);
+        match result {
+            Ok(events) => self.show_library_events(events),
+            Err(e) => {
+                error!("Cannot write merged db. {:?}", e);
+                self.ui.notification_dialog(&e.to_string());
+                return;
+            }
+        }
+
+        self.ui.open_post_merge_message();
+    }
+
+    /// Replace the unreadable database with the given JSON export and
+    /// populate the novel lists with its contents.
+    pub fn recover_db_from_json(&mut self, file_path: PathBuf) {
+        let mut db = match read_export(&file_path) {
+            Ok(ImportData::Database(db)) => db,
+            Ok(ImportData::History(_)) => {
+                self.ui.notification_dialog(&ErisError::NotDatabaseExport.to_string());
+                return;
+            }
+            Err(e) => {
+                error!("Could not recover db from {:?}. {:?}", file_path, e);
+                self.ui.notification_dialog(&e.to_string());
+                return;
+            }
+        };
+
+        // Keep the broken database file around, but out of the way
+        set_aside_database_file();
+
+        if let Err(e) = self.library.read().storage().write_database(&mut db) {
+            error!("Cannot write to db file. {:?}", e);
+        }
+        if let Some(novels) = &db.novels {
+            self.ui.lists.populate_columns(novels);
+            self.ui.filter.populate_columns(novels);
+        }
+        self.library.write().db = db;
+        self.database_recovery = None;
+        self.ui.main_window.set_title("Eris");
+    }
+}
@@ -0,0 +1,54 @@
+use crate::appop::AppOp;
+use eris_core::library::LibraryEvent;
+
+impl AppOp {
+    /// Show the result of a change to the library.
+    ///
+    /// A change that could not be made, e.g. because the library is read-only, is
+    /// only logged. Returns `true` if the change was made.
+    pub fn library_changed(&mut self, result: Result<Vec<LibraryEvent>, anyhow::Error>) -> bool {
+        match result {
+            Ok(events) => {
+                self.show_library_events(events);
+                true
+            }
+            Err(e) => {
+                error!("Cannot change the library. {:#}", e);
+                false
+            }
+        }
+    }
+
+    /// Update the lists, the reading now view and the history list after a change.
+    pub fn show_library_events(&mut self, events: Vec<LibraryEvent>) {
+        for event in events {
+            match event {
+                LibraryEvent::NovelAdded(novel) => {
+                    self.ui.filter.list_insert(&novel);
+                    self.ui.lists.list_insert(&novel);
+                }
+                LibraryEvent::NovelUpdated(novel) | LibraryEvent::ChapterRead(novel) => {
+                    self.ui.lists.list_update(&novel);
+                    self.ui.filter.list_update(&novel);
+                    self.update_reading_now_novel_info(&novel);
+                }
+                LibraryEvent::NovelMoved { novel, from } => {
+                    // The row is still in the list the novel was moved from
+                    let mut moved_from = novel.clone();
+                    moved_from.settings.list_status = from;
+                    self.ui.lists.active_list = from;
+                    let old_iter = self.ui.lists.find_iter(&moved_from);
+
+                    self.ui.lists.list_move(&novel, old_iter);
+                    self.ui.filter.list_update(&novel);
+                    self.update_reading_now_novel_info(&novel);
+                }
+                LibraryEvent::NovelRemoved(novel) => {
+                    self.ui.filter.list_remove(&novel);
+                    self.ui.lists.list_remove(&novel, None);
+                }
+                LibraryEvent::HistoryAdded(item) => self.history_added(&item),
+            }
+        }
+    }
+}
@@ -1,7 +1,6 @@
-use crate::app::history::NovelHistoryItem;
-use crate::app::novel::{ChapterRead, Novel};
 use crate::app::settings::Sorting;
 use crate::appop::AppOp;
+use eris_core::novel::{ChapterRead, Novel};
 
 impl AppOp {
     /// Create the `chapter_read` message.
@@ -35,30 +34,6 @@ impl AppOp {
             .expect("Cannot send ChapterRead message");
     }
 
-    pub fn history_message(&self) -> glib::Sender<NovelHistoryItem> {
-        let (tx, rx) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
-
-        rx.attach(None, glib::clone!(@strong self.app_runtime as app_runtime => @default-return glib::Continue(false), move |data: NovelHistoryItem| {
-            app_runtime.update_state_with(move |state| {
-                state.new_history_item(data);
-            });
-
-            glib::Continue(true)
-        }));
-
-        tx
-    }
-
-    /// Used for sending `NovelHistoryItem` message every time a new history
-    /// entry needs to be added.
-    pub fn history_send(&self, history_item: NovelHistoryItem) {
-        self.history_sender
-            .as_ref()
-            .unwrap()
-            .send(history_item)
-            .expect("Cannot send ChapterRead message");
-    }
-
     pub fn list_sort_message(&self) -> glib::Sender<SortingMessage> {
         let (tx, rx) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
 
@@ -4,63 +4,57 @@ use std::sync::Arc;
 use gtk::prelude::*;
 use parking_lot::RwLock;
 
-use crate::app::database::{read_database, Database};
-use crate::app::history::{NovelHistory, NovelHistoryItem};
-use crate::app::novel::{ChapterRead, Novel, NovelFile, ReadAmount};
 use crate::app::settings::Settings;
 use crate::app::window_state::WindowState;
 use crate::app::AppRuntime;
+use crate::appop::api::ApiServer;
+#[cfg(target_os = "linux")]
+use crate::appop::dbus::DBusService;
 use crate::appop::messages::SortingMessage;
 use crate::appop::novel_recognition::NovelRecognition;
-use crate::appop::parsers::novel_title_to_slug;
+use crate::appop::sync::sync_journal;
 use crate::{ui, UPDATE_LINK};
-use chrono::Local;
 use epub::doc::EpubDoc;
+use eris_core::confirmations::ConfirmationQueue;
+use eris_core::database::DatabaseRecovery;
+use eris_core::dwell::DwellTimers;
+use eris_core::library::Library;
+use eris_core::novel::{ChapterRead, Novel, NovelFile, ReadAmount};
+use eris_core::parsers::novel_title_to_slug;
+use eris_core::profile::profile_dir;
+use eris_core::storage;
+use eris_core::title_rules::TitleRules;
+use eris_core::titles::TitleFeed;
+use eris_core::trace::TraceWriter;
+use eris_core::TITLE_RULES_FILE;
 use gtk::{ButtonsType, DialogFlags, MessageType};
 use select::document::Document;
 use select::predicate::Name;
 use std::path::{Path, PathBuf};
 
+pub mod api;
+mod backup;
+#[cfg(target_os = "linux")]
+pub mod dbus;
 pub mod history;
+mod import;
+mod library;
 pub mod messages;
 mod novel;
 pub mod novel_recognition;
-pub mod parsers;
+mod profile;
 pub mod settings;
+mod sync;
 mod update;
 
 #[derive(Debug)]
 pub struct CurrentlyReading {
     pub title: Arc<RwLock<Option<String>>>,
     pub novel: Arc<RwLock<Option<Novel>>>,
-    pub timestamp: Arc<RwLock<Option<i64>>>,
-    pub timestamp_used: bool,
-}
-
-impl CurrentlyReading {
-    pub fn timestamp_spend(&self, delay: i64) -> bool {
-        if let Some(timestamp) = *self.timestamp.read() {
-            if Local::now().timestamp() >= timestamp + delay {
-                return true;
-            }
-        }
-
-        false
-    }
-
-    pub fn timestamp_exists(&self) -> bool {
-        self.timestamp.read().is_some()
-    }
-
-    pub fn timestamp_set(&mut self) {
-        self.timestamp.write().replace(Local::now().timestamp());
-        self.timestamp_used = false;
-    }
-
-    pub fn timestamp_take(&mut self) {
-        let _ = self.timestamp.write().take();
-        self.timestamp_used = true;
-    }
+    /// How long each open novel has been read.
+    pub timers: DwellTimers,
+    /// Novel matches that wait for the user to confirm them.
+    pub confirmations: ConfirmationQueue,
 }
 
 pub struct AppOp {
@@ -68,13 +62,25 @@ pub struct AppOp {
     pub app_runtime: AppRuntime,
     pub ui: ui::UI,
     pub settings: Arc<RwLock<Settings>>,
-    pub db: Arc<RwLock<Database>>,
-    pub history: Arc<RwLock<NovelHistory>>,
+    /// Novels and history, every change to them goes through it.
+    pub library: Arc<RwLock<Library>>,
+    /// Set if the database file could not be read on startup.
+    pub database_recovery: Option<DatabaseRecovery>,
     pub currently_reading: CurrentlyReading,
     pub novel_recognition: Option<NovelRecognition>,
+    /// Local HTTP API, running if enabled in the settings.
+    pub api_server: Option<ApiServer>,
+    /// Tab titles sent by a browser extension, read by novel recognition.
+    pub title_feed: TitleFeed,
+    /// Title patterns tried before the generic novel recognition.
+    pub title_rules: TitleRules,
+    /// Records the recognized window titles when a trace file is set.
+    pub recognition_trace: Option<TraceWriter>,
+    /// D-Bus interface for desktop integration.
+    #[cfg(target_os = "linux")]
+    pub dbus_service: Option<DBusService>,
 
     pub chapter_read_sender: Option<glib::Sender<ChapterRead>>,
-    pub history_sender: Option<glib::Sender<NovelHistoryItem>>,
     pub previous_chapter_read: Option<ChapterRead>,
     pub list_populated: bool,
     pub list_sort_sender: Option<glib::Sender<SortingMessage>>,
@@ -88,20 +94,23 @@ impl AppOp {
         debug!("appop::new");
 
         let settings_data = Settings::open().expect("Failed to open settings file.");
+        let storage = storage::open(&settings_data.general.storage_backend);
         let settings = Arc::new(RwLock::new(settings_data));
         let currently_reading = CurrentlyReading {
             title: Arc::new(RwLock::new(None)),
             novel: Arc::new(RwLock::new(None)),
-            timestamp: Arc::new(RwLock::new(None)),
-            timestamp_used: false,
+            timers: DwellTimers::default(),
+            confirmations: ConfirmationQueue::default(),
         };
 
-        let history = match NovelHistory::open() {
-            Ok(history) => Arc::new(RwLock::new(history)),
+        let (mut library, database_recovery) = match Library::open(storage) {
+            Ok((library, database_recovery)) => (library, database_recovery),
             Err(e) => {
                 panic!("{:?}", e);
             }
         };
+        library.set_journal(sync_journal(&settings.read().general));
+        let library = Arc::new(RwLock::new(library));
 
         let targets = vec![gtk::TargetEntry::new("text/uri-list", gtk::TargetFlags::OTHER_APP, 0)];
 
@@ -241,12 +250,11 @@ impl AppOp {
                             }
                         }
                         "json" => {
-                            app_runtime_clone.update_state_with(move |state| match state.import_json(file_name) {
-                                Ok(_) => {
-                                    state.ui.open_post_import_message(&state.app_runtime);
-                                }
-                                Err(e) => {
+                            // Import shows a preview of the changes before anything is merged
+                            app_runtime_clone.update_state_with(move |state| {
+                                if let Err(e) = state.import_json(file_name) {
                                     error!("{:?}", e);
+                                    state.ui.notification_dialog(&e.to_string());
                                 }
                             });
                         }
@@ -255,19 +263,27 @@ impl AppOp {
                 }
             });
 
-        let db = Arc::new(RwLock::new(read_database()));
+        let title_rules = TitleRules::open(&profile_dir(TITLE_RULES_FILE)).unwrap_or_else(|e| {
+            error!("Cannot read the title rules. {:#}", e);
+            TitleRules::default()
+        });
 
         AppOp {
             window_state: Arc::new(RwLock::new(None)),
             app_runtime,
             ui,
             settings,
-            db,
-            history,
+            library,
+            database_recovery,
             currently_reading,
             novel_recognition: None,
+            api_server: None,
+            title_feed: TitleFeed::default(),
+            title_rules,
+            recognition_trace: None,
+            #[cfg(target_os = "linux")]
+            dbus_service: None,
             chapter_read_sender: None,
-            history_sender: None,
             previous_chapter_read: None,
             list_populated: false,
             list_sort_sender: None,
@@ -277,7 +293,10 @@ impl AppOp {
     }
 
     pub fn init(&mut self) {
-        if let Some(novels) = self.db.read().novels.clone() {
+        // Apply changes from other devices before anything is shown
+        let sync_conflicts = self.sync_replay();
+
+        if let Some(novels) = self.library.read().db.novels.clone() {
             self.ui
                 .lists
                 .add_columns(self.app_runtime.clone(), &self.settings.read());
@@ -290,19 +309,42 @@ impl AppOp {
         }
 
         self.ui.history.add_columns(&self.ui.builder);
-        // self.ui.history.populate_columns(&self.history.read().items);
+        // self.ui.history.populate_columns(&self.library.read().history.items);
 
         debug!("appop::init");
 
         let app_runtime = self.app_runtime.clone();
-        self.novel_recognition = NovelRecognition::new(app_runtime, self.settings.read().novel_recognition.clone());
+        self.novel_recognition = NovelRecognition::new(
+            app_runtime,
+            self.settings.read().novel_recognition.clone(),
+            &self.title_feed,
+            self.title_rules.clone(),
+        );
+        self.api_server = ApiServer::new(self.app_runtime.clone(), &self.settings.read().general);
+        #[cfg(target_os = "linux")]
+        {
+            self.dbus_service = DBusService::register(&self.ui.gtk_app, self.app_runtime.clone());
+        }
         self.chapter_read_sender = Some(self.chapter_read_message());
-        self.history_sender = Some(self.history_message());
         self.list_sort_sender = Some(self.list_sort_message());
 
         self.currently_reading();
 
         self.list_populated = true;
+
+        // Let the user know if the database had to be recovered
+        if let Some(recovery) = &self.database_recovery {
+            if self.library.read().db.read_only {
+                self.ui.main_window.set_title(&format!("Eris ({})", fl!("read-only")));
+            }
+            self.ui.open_database_recovery_message(recovery, &self.app_runtime);
+        }
+
+        if !sync_conflicts.is_empty() {
+            self.ui.open_sync_conflicts_message(&sync_conflicts);
+        }
+
+        self.daily_backup();
     }
 
     pub fn quit(&self) {
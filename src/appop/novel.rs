@@ -1,32 +1,27 @@
-use crate::app::novel::{ChapterRead, Novel, NovelContentAmount, NovelFile, NovelSettings, NovelStatus, NovelType};
-use crate::app::settings::ChapterReadPreference;
-use crate::app::NOVEL_UPDATE_COOLDOWN;
 use crate::appop::AppOp;
-use crate::ui::novel_list::{ListStatus, ID_COLUMN};
+use eris_core::novel::{
+    ChapterRead, ListStatus, Novel, NovelContentAmount, NovelFile, NovelSettings, NovelStatus, NovelType,
+};
+use eris_core::NOVEL_UPDATE_COOLDOWN;
 
-use crate::app::error::ErisError;
-use crate::app::history::NovelHistoryItem;
 use crate::appop::messages::SortingMessage;
-use crate::appop::novel_recognition::NovelRecognitionData;
-use crate::appop::parsers::NovelParser;
 use crate::ui::new_dialog::guess_keyword;
-use crate::utils::gtk::BuilderExtManualCustom;
-use crate::utils::split_checker;
-use crate::{data_dir, DATA_IMAGE_DIR};
 use anyhow::Context;
 use chrono::Local;
-use gtk::prelude::{NotebookExt, StackExt, TreeModelExt, TreeViewExt, WidgetExt};
-use ngrammatic::{CorpusBuilder, Pad};
-use select::document::Document;
+use eris_core::error::ErisError;
+use eris_core::parsers::{fetch_novel, novel_url};
+use eris_core::paths::data_dir;
+use eris_core::profile::profile_path;
+use eris_core::progress::{self, ProgressDecision};
+use eris_core::recognition::{novel_by_title, NovelRecognitionData};
+use eris_core::DATA_IMAGE_DIR;
+use gtk::prelude::{NotebookExt, StackExt, WidgetExt};
 use std::fs::File;
 use std::io::{BufWriter, Write};
-use std::mem;
 use std::path::PathBuf;
 use std::process::Command;
 use std::str::FromStr;
 use std::thread;
-use std::time::Duration;
-use ureq::{Agent, Error};
 use url::Url;
 
 impl AppOp {
@@ -68,7 +63,7 @@ impl AppOp {
         let data = NovelRecognitionData::new(volume_num, chapter_num, side_story_num, None, String::new(), false);
 
         // Updated novel instance with correct chapter number
-        novel = self.reading_novel(&mut novel, &data, manual_change);
+        novel = self.reading_novel(&novel, &data, manual_change);
         self.ui.update_currently_reading(&novel);
     }
 
@@ -98,23 +93,19 @@ impl AppOp {
                 return;
             }
 
-            // All good so go for it
-            if let Some(mut novel) = self.update_novel(old_novel) {
-                novel.last_scrape = Local::now().timestamp();
-                novel.settings = old_novel.settings.clone();
-
+            // All good so go for it, the personal settings of the old novel are kept
+            if let Some(novel) = self.update_novel(old_novel) {
                 debug!("update novel, response ok -> novel: {:?}", novel);
-                novel = self.update_novel_in_db(novel);
-                self.ui.novel_dialog.update(&self.ui.builder, &novel);
-                self.ui.lists.list_update(&novel);
-                self.ui.filter.list_update(&novel);
-                self.ui.lists.active_novel = Some(novel.clone());
-
-                self.history_send(NovelHistoryItem::new_history_update_novel(&novel));
+                let result = self.library.write().update(novel);
+                if !self.library_changed(result) {
+                    return;
+                }
 
+                if let Some(novel) = self.get_by_id(old_novel.id.clone()) {
+                    self.ui.novel_dialog.update(&self.ui.builder, &novel);
+                    self.ui.lists.active_novel = Some(novel);
+                }
                 self.ui.novel_dialog.update_stack.set_visible_child_name("page0");
-
-                self.update_reading_now_novel_info(&novel);
             }
         }
     }
@@ -151,7 +142,12 @@ impl AppOp {
         // `self.novel_file_data` should never be `None` here.
         let image = if let Some(cover_data) = &self.novel_file_data.as_ref().unwrap().cover_data {
             let cover_ext = self.novel_file_data.clone().unwrap().cover_ext.unwrap();
-            let cover_file_path = format!("{}/{}.{}", DATA_IMAGE_DIR, &novel_file.novel_string_id, cover_ext);
+            let cover_file_path = format!(
+                "{}/{}.{}",
+                profile_path(DATA_IMAGE_DIR),
+                &novel_file.novel_string_id,
+                cover_ext
+            );
             let path = data_dir(&cover_file_path);
             // Create the cover file if it doesn't exist
             if !path.exists() {
@@ -265,100 +261,51 @@ impl AppOp {
             return;
         }
 
-        let mut url = format!("{}{}", url_start, url_end);
-
-        // Add slash and the end of the url if using novelupdates.com
-        if url.contains("novelupdates.com") && !url.ends_with('/') {
-            url += "/";
-        }
+        let url = novel_url(&format!("{}{}", url_start, url_end));
 
         // If a novel with the same slug already exists then do nothing
         if self.get_by_slug(url.clone()).is_some() {
             return;
         }
 
-        let agent: Agent = ureq::AgentBuilder::new()
-            .timeout_read(Duration::from_secs(2))
-            .timeout_write(Duration::from_secs(2))
-            .build();
-
-        // Get the data from the webpage.
-        match agent.get(url.as_str()).call() {
-            Ok(response) => {
-                let document = Document::from(response.into_string().expect("Cannot String").as_str());
-
-                let novel_parser = NovelParser::from_url(&url);
-                if !novel_parser.is_supported() {
-                    // Do nothing more if novel parser is not supported
-                    warn!("Novel was not added because the Source URL {} it not supported.", url);
-                    return;
-                }
-
+        match fetch_novel(&url) {
+            Ok(mut novel) => {
                 let reading_url = if let Ok(url) = Url::from_str(&reading_url_str) {
                     Some(url.to_string())
                 } else {
                     None
                 };
 
-                if let Some(mut novel) = novel_parser.parse(document, &url) {
-                    // Add the novel settings
-                    novel.settings = NovelSettings {
-                        list_status,
-                        content_read,
-                        notes: None,
-                        score,
-                        rereading: false,
-                        reading_url,
-                        window_titles: keywords,
-                        file: None,
-                        last_read: Local::now().timestamp(),
-                    };
+                // Add the novel settings
+                novel.settings = NovelSettings {
+                    list_status,
+                    content_read,
+                    notes: None,
+                    score,
+                    rereading: false,
+                    reading_url,
+                    window_titles: keywords,
+                    file: None,
+                    last_read: Local::now().timestamp(),
+                };
 
-                    // Add novel to db and UI
-                    self.add_novel_to_db(novel);
-                } else {
-                    self.ui.notification_dialog("Could not add novel.");
-                }
+                // Add novel to db and UI
+                self.add_novel_to_db(novel);
             }
-            Err(Error::Status(code, _response)) => {
-                self.ui.notification_dialog(&format!(
-                    "Could not add novel!\n\nUrl {} returned {}",
-                    url.as_str(),
-                    code
-                ));
-                error!("Could not add novel. Url {} returned {}", url.as_str(), code);
-                /* the server returned an unexpected status
-                code (such as 400, 500 etc) */
+            Err(e) => {
+                error!("Could not add novel from {}. {}", url, e);
+                self.ui.notification_dialog(&format!("Could not add novel!\n\n{}", e));
             }
-            Err(_) => { /* some kind of io/transport error */ }
         }
     }
 
     /// Handles moving a `Novel` to another list
     pub fn move_novel(&mut self, novel_id: String, to_list: ListStatus) {
-        if let Some(mut novel) = self.get_by_id(novel_id.clone()) {
-            // Create a copy of the novel settings and update the list status
-            let mut new_novel_settings = novel.settings.clone();
-            new_novel_settings.list_status = to_list;
-            // Add the new novel settings to the novel
-            novel.settings = new_novel_settings;
-            // Save to db
-            novel = self.update_novel_in_db(novel.clone());
-            // Update the lists
-            let iter = self.ui.lists.active_iter.take();
-            self.ui.lists.list_move(&novel, iter);
-            // Add a history entry
-            self.history_send(NovelHistoryItem::new_history_novel_list_change(&novel));
-            // Update reading now view if currently reading novel is the one moved
-            if let Some(anovel) = self.currently_reading.novel.read().as_ref() {
-                if anovel.id == novel_id {
-                    self.ui.update_reading_now(&Some(novel));
-                }
-            }
-        } else {
-            // Practically impossible
-            error!("Tried to move a novel but it could not be found anymore?!");
-        }
+        // The row is looked up again when the move is shown
+        let _ = self.ui.lists.active_iter.take();
+
+        let result = self.library.write().move_to(&novel_id, to_list);
+        self.library_changed(result);
     }
 
     /// NOVEL DIALOG
@@ -374,43 +321,15 @@ impl AppOp {
 
         let url = old_novel.slug.as_ref().unwrap();
 
-        let agent: Agent = ureq::AgentBuilder::new()
-            .timeout_read(Duration::from_secs(2))
-            .timeout_write(Duration::from_secs(2))
-            .build();
-
-        // Get the data from the webpage.
-        match agent.get(url.as_str()).call() {
-            Ok(response) => {
-                debug!("update_novel, response ok");
-                let document = Document::from(response.into_string().expect("Cannot String").as_str());
-
-                let novel_parser = NovelParser::from_url(url);
-                if !novel_parser.is_supported() {
-                    // Do nothing more if novel parser is not supported
-                    warn!(
-                        "Novel {} was not updated because the Source URL {} it not supported.",
-                        old_novel.title, url
-                    );
-                    return None;
-                }
-
-                return novel_parser.parse(document, url);
-            }
-            Err(Error::Status(code, _response)) => {
-                self.ui.notification_dialog(&format!(
-                    "Could not update novel!\n\nUrl {} returned {}",
-                    url.as_str(),
-                    code
-                ));
-                error!("Could not update novel! URL: {} returned {}", url.as_str(), code);
-                /* the server returned an unexpected status
-                code (such as 400, 500 etc) */
+        match fetch_novel(url) {
+            Ok(novel) => Some(novel),
+            Err(e) => {
+                error!("Could not update novel {}. {}", old_novel.title, e);
+                self.ui
+                    .notification_dialog(&format!("Could not update novel!\n\n{}", e));
+                None
             }
-            Err(_) => { /* some kind of io/transport error */ }
         }
-
-        None
     }
 
     /// NOVEL DIALOG
@@ -423,24 +342,9 @@ impl AppOp {
         }
 
         if let Some(novel) = self.ui.lists.active_novel.clone() {
-            // Remove novel from the lists
-            self.ui.filter.list_remove(&novel);
-            self.ui.lists.list_remove(&novel, None);
-
-            // Remove novel from the db
-            let db = self.db.read().clone();
-            if let Some(mut novels) = db.novels {
-                let index = novels.iter().position(|n| n.id == novel.id).unwrap();
-
-                novels.remove(index);
-
-                self.db.write().novels = Some(novels);
-                self.save_db_to_file();
-                // Novel was deleted so add an entry into the history
-                self.history_send(NovelHistoryItem::new_history_delete_novel(&novel));
-                // let item = self.history.read().clone().new_history_delete_novel(&novel);
-                // self.ui.history.list_insert(item);
-            }
+            // Removes the novel from the db and the lists and adds an entry into the history
+            let result = self.library.write().remove(&novel.id);
+            self.library_changed(result);
         }
     }
 
@@ -451,12 +355,9 @@ impl AppOp {
         debug!("appop::edit_novel_settings | {:?}", novel_settings);
 
         if let Some(mut novel) = self.ui.lists.active_novel.clone() {
-            // Check if the novel was moved to another list
-            let old_iter = if novel.settings.list_status != novel_settings.list_status {
-                self.ui.lists.find_iter(&novel)
-            } else {
-                None
-            };
+            // Moving the novel to another list is saved on its own, with a history entry
+            let list_status = novel_settings.list_status;
+            novel_settings.list_status = novel.settings.list_status;
 
             // Remove any empty elements from the `window_titles` keyword vector
             if let Some(ref mut keywords) = novel_settings.window_titles {
@@ -470,17 +371,17 @@ impl AppOp {
             let last_read = novel.settings.last_read;
             novel.settings = novel_settings;
             novel.settings.last_read = last_read;
-            novel = self.update_novel_in_db(novel.clone());
-
-            if old_iter.is_some() {
-                self.history_send(NovelHistoryItem::new_history_novel_list_change(&novel));
 
-                self.ui.lists.list_move(&novel, old_iter);
+            let result = self.library.write().edit(novel.clone());
+            if !self.library_changed(result) {
+                return;
             }
+            let result = self.library.write().move_to(&novel.id, list_status);
+            self.library_changed(result);
 
-            self.ui.lists.list_update(&novel);
-            self.ui.filter.list_update(&novel);
-            self.ui.update_reading_now(&Some(novel));
+            if let Some(novel) = self.get_by_id(novel.id) {
+                self.ui.update_reading_now(&Some(novel));
+            }
         }
     }
 
@@ -489,16 +390,12 @@ impl AppOp {
         debug!("appop::edit_active_novel");
 
         let novel = self.ui.lists.active_novel.clone().unwrap();
-        let mut updated_novel = self.ui.novel_dialog.update_novel_from_edit(&self.ui.builder, &novel);
+        let updated_novel = self.ui.novel_dialog.update_novel_from_edit(&self.ui.builder, &novel);
 
-        updated_novel = self.update_novel_in_db(updated_novel.clone());
+        // Also updates the lists and the reading now view
+        let updated_novel = self.update_novel_in_db(updated_novel);
 
         self.ui.novel_dialog.update(&self.ui.builder, &updated_novel);
-        self.ui.lists.list_update(&updated_novel);
-        self.ui.filter.list_update(&novel);
-
-        self.update_reading_now_novel_info(&updated_novel);
-
         self.ui.lists.active_novel = Some(updated_novel);
     }
 
@@ -514,73 +411,20 @@ impl AppOp {
         }
     }
 
-    /// Update the given `Novel` read count in db.
-    /// Update the UI to reflect the change.
-    /// Insert the change into the history list.
-    pub fn edit_novel_chapters_read_count(
-        &mut self,
-        mut novel: Novel,
-        old_iter: Option<gtk::TreeIter>,
-        chapter_title: Option<String>,
-        manual_change: bool,
-    ) -> Novel {
-        debug!("appop::edit_novel_chapters_read_count");
-
-        let move_novel = old_iter.is_some();
-        // Only change the last read value if the chapter read count change
-        // is from the novel recognition
-        if !manual_change {
-            novel.settings.last_read = Local::now().timestamp();
-        }
-
-        let novel = self.update_novel_in_db(novel);
-        self.ui.lists.list_update(&novel);
-        self.ui.filter.list_update(&novel);
-
-        if move_novel {
-            self.history_send(NovelHistoryItem::new_history_novel_list_change(&novel));
-
-            self.ui.lists.list_move(&novel, old_iter);
-        }
-
-        // Add history item if any of the content read values are over zero
-        if novel.settings.content_read.chapters > 0.0
-            || novel.settings.content_read.side_stories > 0
-            || novel.settings.content_read.volumes > 0
-        {
-            self.history_send(NovelHistoryItem::new_history_chapter_read(&novel, chapter_title));
-        }
-        novel
-    }
-
     /// Add novel to the db and write the change to file.
     /// Afterwards insert it into the novel list (UI).
     pub fn add_novel_to_db(&mut self, novel: Novel) {
         debug!("appop::add_to_novel_list");
 
-        // Save to db
-        self.db.write().push_novel(novel.clone());
-        match self.db.write().write_to_file() {
-            Ok(_) => {}
-            Err(e) => {
-                // This should practically never happen since if
-                // deserializing fails during app startup then
-                // serializing will not happen (since app is no longer up).
-                error!("Could not add novel to db file. {}", e);
-                return;
-            }
+        // Fails if the novel is already there, the db is read-only or the disk is not writable
+        let result = self.library.write().add(novel.clone());
+        if !self.library_changed(result) {
+            return;
         }
 
-        // Add history entry
-        self.history_send(NovelHistoryItem::new_history_add_novel(&novel));
-
         // Switch to the list the novel is added to
         self.ui.list_notebook.set_page(novel.settings.list_status.to_i32());
 
-        // Update the UI lists
-        self.ui.filter.list_insert(&novel);
-        self.ui.lists.list_insert(&novel);
-
         // Update currently reading things only if the novel added is "relevant"
         if self.currently_reading.title.read().as_ref().is_some()
             && self
@@ -596,44 +440,20 @@ impl AppOp {
 
             // Reset the currently reading title so the reading now view gets updated
             let _ = self.currently_reading.title.write().take();
-
-            // Reset currently reading delay thingy
-            self.currently_reading.timestamp_take();
         }
     }
 
-    /// Write db to file in another thread.
-    pub fn save_db_to_file(&mut self) {
-        debug!("appop::save_db_to_file");
-
-        let db = self.db.clone();
-        thread::spawn(move || {
-            debug!("Saving db to file in a new thread!");
-            match db.write().write_to_file() {
-                Ok(_) => {}
-                Err(e) => {
-                    // This should practically never happen since if
-                    // deserializing fails during app startup then
-                    // serializing will not happen (since app is no longer up).
-                    error!("Could not add novel to db file. {}", e);
-                }
-            }
-        });
-    }
-
     /// Write everything to files in another thread.
     pub fn save_to_file(&mut self) {
         debug!("appop::save_to_file");
 
-        self.save_db_to_file();
-
-        let history = self.history.clone();
+        let library = self.library.clone();
         thread::spawn(move || {
-            debug!("Saving history to file in a new thread!");
-            match history.write().write_to_file() {
-                Ok(_) => {}
-                Err(e) => error!("Could not save history to file in another thread. {:?}", e),
-            };
+            debug!("Saving db and history to file in a new thread!");
+            let result = library.write().write_all();
+            if let Err(e) = result {
+                error!("Could not save db and history to file in another thread. {:#}", e);
+            }
         });
 
         let settings = self.settings.clone();
@@ -661,90 +481,15 @@ impl AppOp {
 
     /// Try to find `Novel` by `Novel.id` from the db.
     pub fn get_by_id(&self, novel_id: String) -> Option<Novel> {
-        if let Some(novels) = &self.db.read().novels {
-            for novel in novels {
-                if novel.id == novel_id {
-                    return Some(novel.clone());
-                }
-            }
-        }
-
-        None
+        self.library.read().get(&novel_id).cloned()
     }
 
     /// Try to find `Novel` by `Novel.slug` from the db.
     pub fn get_by_slug(&self, novel_slug: String) -> Option<Novel> {
-        if let Some(novels) = &self.db.read().novels {
-            for novel in novels {
-                if let Some(slug) = &novel.slug {
-                    if slug == &novel_slug || novel_slug.contains(slug) {
-                        return Some(novel.clone());
-                    }
-                }
-            }
-        }
-
-        None
-    }
-
-    /// Try to find `Novel` by `Novel.title` or `Novel.settings.window_titles` from the db.
-    pub fn find_novel_by_window_title(&self, window_title: &str) -> Option<Novel> {
-        if let Some(novels) = &self.db.read().novels {
-            let mut corpus = CorpusBuilder::new().arity(2).pad_full(Pad::Auto).finish();
-
-            // First check if the window title is identical to any novel in the db
-            let exact_match = self.get_by_title(window_title);
-            if exact_match.is_some() {
-                return exact_match;
-            }
-
-            // Then check for keywords in novel settings
-            let keyword_match = self.get_by_novel_keywords(window_title);
-            if keyword_match.is_some() {
-                return keyword_match;
-            }
-
-            // Lasty do a high percentage match fuzzy search
-            for novel in novels {
-                corpus.add_text(&novel.title.to_lowercase());
-            }
-            if let Some(top_result) = corpus.search(window_title, 0.25).first() {
-                if top_result.similarity > 0.9 {
-                    return self.get_by_title(&top_result.text);
-                } else {
-                    debug!(
-                        "{} (did you mean {} [{:.0}% match]?)",
-                        window_title.to_lowercase(),
-                        top_result.text,
-                        top_result.similarity * 100.0
-                    );
-                }
-            }
-        }
-
-        None
-    }
-
-    /// Try to find `Novel` by `Novel.settings.window_titles` from the db.
-    pub fn get_by_novel_keywords(&self, window_title: &str) -> Option<Novel> {
-        if let Some(novels) = &self.db.read().novels {
-            let split_title = window_title.split(' ').collect::<Vec<_>>();
-            for novel in novels {
-                if let Some(window_titles) = &novel.settings.window_titles {
-                    // Get by exact match
-                    if window_titles.iter().any(|i| !i.is_empty() && i == window_title) {
-                        return Some(novel.clone());
-                    } else {
-                        if split_checker(&split_title, &novel.title) {
-                            return Some(novel.clone());
-                        }
-
-                        for one_title in window_titles {
-                            if split_checker(&split_title, one_title) {
-                                return Some(novel.clone());
-                            }
-                        }
-                    }
+        for novel in self.library.read().novels() {
+            if let Some(slug) = &novel.slug {
+                if slug == &novel_slug || novel_slug.contains(slug) {
+                    return Some(novel.clone());
                 }
             }
         }
@@ -754,15 +499,7 @@ impl AppOp {
 
     /// Get `Novel` from db by its title
     pub fn get_by_title(&self, window_title: &str) -> Option<Novel> {
-        if let Some(novels) = &self.db.read().novels {
-            for novel in novels {
-                if novel.title.to_lowercase() == window_title.to_lowercase() {
-                    return Some(novel.clone());
-                }
-            }
-        }
-
-        None
+        novel_by_title(self.library.read().novels(), window_title).cloned()
     }
 
     /// Find any number of potential novels based on the supplied title.
@@ -770,7 +507,7 @@ impl AppOp {
     /// TODO: this can be made nicer, I'm sure.
     pub fn find_potential_novels(&self, title: &str) -> Vec<Novel> {
         let mut potentials = vec![];
-        if let Some(novels) = &self.db.read().novels {
+        if let Some(novels) = &self.library.read().db.novels {
             for novel in novels {
                 if let Some(window_titles) = &novel.settings.window_titles {
                     for title in title.split_whitespace() {
@@ -794,18 +531,10 @@ impl AppOp {
         potentials
     }
 
-    /// Add a novel into the db and update it in memory and write to a file.
+    /// Save the changes to a novel in the db and show them in the lists.
     pub fn update_novel_in_db(&mut self, novel: Novel) -> Novel {
-        let db = self.db.read().clone();
-
-        if let Some(mut novels) = db.novels {
-            let index = novels.iter().position(|n| n.id == novel.id).unwrap();
-
-            let _ = mem::replace(&mut novels[index], novel.clone());
-
-            self.db.write().novels = Some(novels);
-            self.save_db_to_file();
-        }
+        let result = self.library.write().edit(novel.clone());
+        self.library_changed(result);
 
         novel
     }
@@ -815,7 +544,8 @@ impl AppOp {
     /// updates the UI with that information if it was found in
     /// the database.
     pub fn currently_reading(&self) {
-        if let Some(last_read) = self.history.read().find_last_read() {
+        let last_read = self.library.read().history.find_last_read();
+        if let Some(last_read) = last_read {
             if let Some(novel) = self.get_by_id(last_read.novel_id) {
                 self.ui.update_currently_reading(&novel);
             }
@@ -823,168 +553,22 @@ impl AppOp {
     }
 
     /// Reading a novel so update the given `Novel` data and return it.
-    pub fn reading_novel(&mut self, novel: &mut Novel, data: &NovelRecognitionData, manual_change: bool) -> Novel {
+    pub fn reading_novel(&mut self, novel: &Novel, data: &NovelRecognitionData, manual_change: bool) -> Novel {
         debug!("appop:reading_novel");
 
-        let volume_num = data.volume;
-        let chapter_num = data.chapter;
-        let side_story_num = data.side_story;
+        let rules = self.settings.read().novel_recognition.progress_rules();
 
-        // Handle updating the data in novel and list
-        let novel_preference = self.settings.read().clone().novel_recognition.chapter_read_preference;
-        // Used to set the chapter read number to either current one or current one - 1
-        let read_modifier = match novel_preference {
-            ChapterReadPreference::Current => 0.0,
-            ChapterReadPreference::Previous => 1.0,
+        let decision = progress::decide(novel, data, &self.library.read().history, rules, manual_change);
+        let change = match decision {
+            ProgressDecision::Changed(change) => change,
+            ProgressDecision::Unchanged => return novel.clone(),
         };
 
-        // If found a chapter title and it is already present in the history
-        // then there is no need to do anything more
-        if let Some(chapter_title) = &data.chapter_title {
-            if let Some(history_item) = self.history.read().find_chapter_title(chapter_title) {
-                if let Some(found_novel) = self.get_by_id(history_item.novel_id) {
-                    if found_novel == novel.clone() {
-                        return novel.clone();
-                    }
-                }
-            }
-        }
-
-        let mut new_chapter_read_num = if manual_change {
-            chapter_num
-        } else if data.chapter == 0.0 && data.reading && data.chapter_title.is_some() {
-            // Add 1 to the chapters read number since something
-            // is being read but no valid chapter number was found
-            novel.settings.content_read.chapters + 1.0
-        } else {
-            chapter_num - read_modifier
-        };
-
-        // Do not set chapter number below 0
-        if new_chapter_read_num < 0.0 {
-            new_chapter_read_num = 0.0;
-        }
-
-        let mut new_side_story_num = if manual_change {
-            side_story_num
-        } else {
-            side_story_num - (read_modifier as i32)
-        };
-
-        // Do not set side story number below 0
-        if new_side_story_num < 0 {
-            new_side_story_num = 0;
-        }
-
-        // If new chapter number is higher then the last available chapter, the novel status
-        // can be considered "completed" and there are side stories that have not been read then assume
-        // this new chapter number is a side story
-        if matches!(&novel.status, NovelStatus::Completed | NovelStatus::Abandoned)
-            && new_chapter_read_num > novel.content.chapters
-            && novel.content.side_stories > 0
-            && novel.settings.content_read.side_stories < novel.content.side_stories
-        {
-            // Maliciously parse f32 into i32, muahaha. It should be correct enough.
-            new_side_story_num = (new_chapter_read_num - novel.content.chapters)
-                .to_string()
-                .split('.')
-                .collect::<Vec<_>>()
-                .get(0)
-                .unwrap()
-                .parse()
-                .unwrap_or(novel.settings.content_read.side_stories + 1);
-            new_chapter_read_num = novel.content.chapters;
-        }
-
-        // Do nothing if the chapter number being read is less or same as
-        // currently saved chapter read number and same for volume
-        if !manual_change
-            && novel.settings.content_read.chapters >= new_chapter_read_num
-            && novel.settings.content_read.volumes >= volume_num
-            && novel.settings.content_read.side_stories >= new_side_story_num
-        {
-            debug!("Volume/Chapter/Side stories read count was too low!");
-            return novel.clone();
-        }
-
-        // If the numbers are not set by user (`exact_num` = probably set by user)
-        // then make sure that the automation does not go backwards in case of a derp
-        if manual_change {
-            novel.settings.content_read.chapters = new_chapter_read_num;
-            novel.settings.content_read.volumes = volume_num;
-            novel.settings.content_read.side_stories = new_side_story_num;
-        } else {
-            // Update the volume/chapter/side story number only if it is larger than the saved one
-            if new_chapter_read_num > novel.settings.content_read.chapters {
-                novel.settings.content_read.chapters = new_chapter_read_num;
-            }
-
-            if volume_num > novel.settings.content_read.volumes {
-                novel.settings.content_read.volumes = volume_num;
-            }
-
-            if new_side_story_num > novel.settings.content_read.side_stories {
-                novel.settings.content_read.side_stories = new_side_story_num;
-            }
-        }
-
-        // Check if the novel status allows for automatic novel list status changing to `Completed`
-        let can_complete = matches!(&novel.status, NovelStatus::Completed | NovelStatus::Abandoned)
-            || self.settings.read().novel_recognition.autocomplete_ongoing;
-
-        // Check if the novel should be moved to "reading" status
-        // Only move novel to reading list from plan to read list
-        // Do not move if the new chapter number was manually changed
-        let move_to_reading = novel.settings.list_status == ListStatus::PlanToRead && !manual_change;
-
-        // Change the list status to `Completed` if all the chapters have been read.
-        // Should never work for `OnGoing` novels.
-        let is_completed = (volume_num >= novel.content.volumes)
-            && (new_chapter_read_num >= novel.content.chapters && novel.content.chapters > 0.0)
-            && (new_side_story_num >= novel.content.side_stories)
-            && can_complete;
-
-        // Edit chapter read count
-        // Get the correct list based on the list status in novel settings
-        let treeview_id = novel.settings.list_status.treeview_id();
-        let treeview = self.ui.builder.get::<gtk::TreeView>(treeview_id);
-        let model = treeview.model().unwrap();
-
-        // Very lazy? method to find the correct iter so the correct list row can be updated
-        for i in 0..(model.iter_n_children(None)) {
-            // Check if this is the correct iter
-            let iter = model.iter_from_string(&i.to_string());
-            if let Some(iter) = iter {
-                // If the novel id in the row matches with the novel id being read then the correct one was found.
-                let novel_id = model.value(&iter, ID_COLUMN).get::<String>().unwrap();
-                if novel_id == novel.id {
-                    self.ui.lists.active_list = novel.settings.list_status;
-                    // Check if the novel should be moved to another list
-                    let old_iter =
-                        if novel.settings.list_status != ListStatus::Completed && is_completed || move_to_reading {
-                            Some(iter)
-                        } else {
-                            None
-                        };
-
-                    if move_to_reading {
-                        novel.settings.list_status = ListStatus::Reading;
-                    } else if is_completed {
-                        novel.settings.list_status = ListStatus::Completed;
-                    }
-
-                    // Update the chapters read count
-                    return self.edit_novel_chapters_read_count(
-                        novel.clone(),
-                        old_iter,
-                        data.chapter_title.clone(),
-                        manual_change,
-                    );
-                }
-            }
-        }
+        // Saves the read count, moves the novel to another list if needed and adds the history entries
+        let result = self.library.write().read_progress(&novel.id, &change, manual_change);
+        self.library_changed(result);
 
-        novel.clone()
+        self.get_by_id(novel.id.clone()).unwrap_or_else(|| novel.clone())
     }
 
     /// Read novel by either opening a file or a webpoge.
@@ -0,0 +1,22 @@
+use crate::appop::AppOp;
+use eris_core::profile::{current_profile, launch_profile};
+use gtk::prelude::GtkWindowExt;
+
+impl AppOp {
+    /// Restart Eris with the `name` profile, `None` being the default one.
+    ///
+    /// Everything is saved on quit as usual, the new profile is created on its first start.
+    pub fn switch_profile(&self, name: Option<&str>) {
+        if name == current_profile() {
+            return;
+        }
+
+        match launch_profile(name) {
+            Ok(_) => self.ui.main_window.close(),
+            Err(e) => {
+                error!("Cannot start Eris with the profile {:?}. {}", name, e);
+                self.ui.notification_dialog(&fl!("profile-switch-error"));
+            }
+        }
+    }
+}
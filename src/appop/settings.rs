@@ -1,12 +1,21 @@
-use crate::app::error::ErisError;
-use crate::app::settings::{ChapterReadPreference, NovelListAction, Settings};
+use crate::app::settings::{ChapterReadPreference, NovelListAction, Settings, StorageBackend};
+use crate::appop::sync::sync_journal;
 use crate::appop::AppOp;
 use crate::utils::gtk::BuilderExtManualCustom;
 use anyhow::Context;
+use eris_core::data_location::move_data_dir;
+use eris_core::error::ErisError;
+use eris_core::profile::profile_dir;
+use eris_core::recognition::{clean_window_title_string, recognize};
+use eris_core::storage;
+use eris_core::title_rules::{read_rules, write_rules, TitleRules};
+use eris_core::titles::TitleSourceKind;
+use eris_core::{DATA_DIR, TITLE_RULES_FILE};
 use gtk::prelude::{
-    CheckMenuItemExt, ComboBoxExt, EntryExt, FileChooserExt, SpinButtonExt, ToggleButtonExt, WidgetExt,
+    CheckMenuItemExt, ComboBoxExt, EntryExt, FileChooserExt, LabelExt, SpinButtonExt, ToggleButtonExt, WidgetExt,
 };
 use parking_lot::RwLock;
+use std::path::PathBuf;
 use std::process::Command;
 use std::sync::Arc;
 
@@ -72,6 +81,9 @@ impl AppOp {
         let novel_recognition_enabled_checkbutton =
             builder.get::<gtk::CheckButton>("novel_recognition_enabled_checkbutton");
         let novel_recognition_delay = builder.get::<gtk::SpinButton>("novel_recognition_delay");
+        let novel_recognition_idle_threshold = builder.get::<gtk::SpinButton>("novel_recognition_idle_threshold");
+        let novel_recognition_focus_only = builder.get::<gtk::CheckButton>("novel_recognition_focus_only");
+        let novel_recognition_auto_confidence = builder.get::<gtk::SpinButton>("novel_recognition_auto_confidence");
         let novel_recognition_read_preference_combobox =
             builder.get::<gtk::ComboBoxText>("novel_recognition_read_preference_combobox");
         let novel_recognition_autocomplete_ongoing =
@@ -120,6 +132,25 @@ impl AppOp {
         new_settings.general.start_minimized = settings_startup_minimized.is_active();
         new_settings.general.check_update = settings_startup_check_update.is_active();
         new_settings.general.window_state_enabled = window_state_enabled.is_active();
+        new_settings.general.backup_retention = builder
+            .get::<gtk::SpinButton>("backup_retention_spinbutton")
+            .value_as_int() as u32;
+        new_settings.general.sync_dir = if builder.get::<gtk::CheckButton>("sync_enabled_checkbutton").is_active() {
+            builder.get::<gtk::FileChooserButton>("sync_dir_chooser").filename()
+        } else {
+            None
+        };
+        new_settings.general.api_enabled = builder.get::<gtk::CheckButton>("api_enabled_checkbutton").is_active();
+        new_settings.general.api_port = builder.get::<gtk::SpinButton>("api_port_spinbutton").value_as_int() as u16;
+        new_settings.general.storage_backend = StorageBackend::from_i32(
+            self.ui
+                .settings_dialog
+                .storage_backend_combobox
+                .active_id()
+                .unwrap()
+                .parse::<i32>()
+                .unwrap(),
+        );
 
         let selected_lang = language.active_id().unwrap().to_string();
         if selected_lang == "none" {
@@ -130,6 +161,9 @@ impl AppOp {
 
         new_settings.novel_recognition.enable = novel_recognition_enabled_checkbutton.is_active();
         new_settings.novel_recognition.delay = novel_recognition_delay.value_as_int() as i64;
+        new_settings.novel_recognition.idle_threshold = novel_recognition_idle_threshold.value_as_int() as i64;
+        new_settings.novel_recognition.focus_only = novel_recognition_focus_only.is_active();
+        new_settings.novel_recognition.auto_confidence = novel_recognition_auto_confidence.value() as f32;
         new_settings.novel_recognition.chapter_read_preference = ChapterReadPreference::from_i32(
             novel_recognition_read_preference_combobox
                 .active_id()
@@ -138,6 +172,15 @@ impl AppOp {
                 .unwrap(),
         );
         new_settings.novel_recognition.autocomplete_ongoing = novel_recognition_autocomplete_ongoing.is_active();
+        new_settings.novel_recognition.title_source = TitleSourceKind::from_i32(
+            self.ui
+                .settings_dialog
+                .title_source_combobox
+                .active_id()
+                .unwrap()
+                .parse::<i32>()
+                .unwrap(),
+        );
         new_settings.novel_recognition.when_novel_go_to_reading = novel_rec_found_go_to_reading.is_active();
         new_settings.novel_recognition.when_not_novel_go_to_reading = novel_rec_not_found_go_to_reading.is_active();
 
@@ -150,6 +193,8 @@ impl AppOp {
         new_settings.novel_recognition.title_keywords = keyword_vec;
         new_settings.novel_recognition.ignore_keywords = ignore_keyword_vec;
 
+        self.save_title_rules();
+
         let old_settings = self.settings.read().clone();
 
         // If nothing was changed then do nothing
@@ -186,13 +231,33 @@ impl AppOp {
 
             if (old_settings.novel_recognition.title_keywords != new_settings.novel_recognition.title_keywords)
                 || (old_settings.novel_recognition.ignore_keywords != new_settings.novel_recognition.ignore_keywords)
+                || (old_settings.novel_recognition.title_source != new_settings.novel_recognition.title_source)
+                || (old_settings.novel_recognition.idle_threshold != new_settings.novel_recognition.idle_threshold)
+                || (old_settings.novel_recognition.focus_only != new_settings.novel_recognition.focus_only)
             {
-                // Restart novel recognition thread if either keywords change, if it is running
+                // Restart novel recognition thread if the keywords, the title source, the idle threshold
+                // or counting only the focused window change, if it is running
                 if state.novel_recognition.is_some() {
                     state.restart_novel_recognition(new_settings.clone());
                 }
             }
 
+            if old_settings.general.api_enabled != new_settings.general.api_enabled
+                || old_settings.general.api_port != new_settings.general.api_port
+            {
+                state.restart_api_server(&new_settings.general);
+            }
+
+            if old_settings.general.sync_dir != new_settings.general.sync_dir
+                || old_settings.general.sync_device != new_settings.general.sync_device
+            {
+                state.library.write().set_journal(sync_journal(&new_settings.general));
+            }
+
+            if old_settings.general.storage_backend != new_settings.general.storage_backend {
+                state.change_storage(&new_settings.general.storage_backend);
+            }
+
             if old_settings.general.open_with_windows != new_settings.general.open_with_windows {
                 #[cfg(target_os = "windows")]
                 state.start_with_windows(new_settings.general.open_with_windows);
@@ -207,34 +272,163 @@ impl AppOp {
         });
     }
 
+    /// Save the title rules from the settings dialog if they were changed.
+    fn save_title_rules(&mut self) {
+        let text = self.ui.settings_dialog.title_rules_text(&self.ui.builder);
+        let path = profile_dir(TITLE_RULES_FILE);
+        match read_rules(&path) {
+            Ok(current) if current == text => return,
+            Ok(_) => {}
+            Err(e) => {
+                // Do not write over a file that could not be read
+                error!("{:#}", e);
+                return;
+            }
+        }
+
+        match write_rules(&path, &text) {
+            Ok(title_rules) => {
+                self.title_rules = title_rules;
+                // The recognition thread has its own copy of the rules
+                if self.novel_recognition.is_some() {
+                    let settings = self.settings.read().clone();
+                    self.restart_novel_recognition(settings);
+                }
+            }
+            Err(e) => {
+                error!("Cannot save the title rules. {:#}", e);
+                self.ui
+                    .notification_dialog(&fl!("settings-reg-title-rules-invalid", error = format!("{:#}", e)));
+            }
+        }
+    }
+
+    /// Show what the title rules in the settings dialog make of the test title.
+    pub fn test_title_rules(&self) {
+        let builder = &self.ui.builder;
+        let result_label = builder.get::<gtk::Label>("title_rules_test_label");
+        let title: String = builder.get::<gtk::Entry>("title_rules_test_entry").text().into();
+
+        let title_rules = match TitleRules::parse(&self.ui.settings_dialog.title_rules_text(builder)) {
+            Ok(title_rules) => title_rules,
+            Err(e) => {
+                result_label.set_text(&format!("{:#}", e));
+                return;
+            }
+        };
+        if title.trim().is_empty() {
+            result_label.set_text("");
+            return;
+        }
+
+        let recognition = clean_window_title_string(Some(title)).and_then(|title| {
+            recognize(
+                self.library.read().novels(),
+                &title_rules,
+                &self.settings.read().novel_recognition.title_markers,
+                self.settings.read().novel_recognition.auto_confidence,
+                &title,
+            )
+        });
+        let result = match recognition {
+            Some(recognition) => fl!(
+                "title-rules-test-result",
+                rule = recognition.rule.unwrap_or_else(|| fl!("title-rules-none")),
+                novel = match (recognition.novel, recognition.matched) {
+                    (Some(novel), _) => novel.title,
+                    (None, Some(matched)) => fl!(
+                        "title-rules-novel-to-confirm",
+                        title = matched.novel_title,
+                        confidence = format!("{:.0}", matched.confidence * 100.0)
+                    ),
+                    (None, None) => fl!("title-rules-novel-not-found", title = recognition.novel_title),
+                },
+                volume = recognition.data.volume.to_string(),
+                chapter = match &recognition.data.chapter_label {
+                    Some(label) => format!("{} ({})", recognition.data.chapter, label),
+                    None => recognition.data.chapter.to_string(),
+                },
+                side_story = recognition.data.side_story.to_string(),
+                chapter_title = recognition.data.chapter_title.unwrap_or_else(|| "-".to_string()),
+                source = recognition.source
+            ),
+            None => fl!("title-rules-not-reading"),
+        };
+        result_label.set_text(&result);
+    }
+
+    /// Save the library with `backend` from now on.
+    ///
+    /// Everything is written into it right away, so it does not show the older data
+    /// it had when it was used last. A read-only library switches on the next start.
+    pub fn change_storage(&mut self, backend: &StorageBackend) {
+        let mut library = self.library.write();
+        if library.db.read_only {
+            return;
+        }
+
+        let previous = library.storage();
+        library.set_storage(storage::open(backend));
+        if let Err(e) = library.write_all() {
+            error!(
+                "Cannot save the library with the {} storage. {:#}",
+                backend.to_string(),
+                e
+            );
+            library.set_storage(previous);
+        }
+    }
+
+    /// Move the data directory with every profile in it to `target`.
+    ///
+    /// The storage is opened again from `target` so nothing saved before the restart
+    /// is lost. The old data directory is removed on the next start.
+    pub fn move_library(&mut self, target: PathBuf) {
+        // Make sure the copy has everything that is in memory
+        let result = self.library.write().write_all();
+        if let Err(e) = result {
+            error!("Cannot save the library before moving it. {:#}", e);
+        }
+
+        if let Err(e) = move_data_dir(&target) {
+            error!("Cannot move the library to {:?}. {}", target, e);
+            self.ui.notification_dialog(&e.to_string());
+            return;
+        }
+
+        let storage = storage::open(&self.settings.read().general.storage_backend);
+        self.library.write().set_storage(storage);
+
+        {
+            let mut settings = self.settings.write();
+            settings.use_current_data_dir();
+            if let Err(e) = settings.write_to_file() {
+                error!("Cannot save settings after moving the library. {:?}", e);
+            }
+        }
+        self.ui.settings_dialog.update(&self.ui.builder, &self.settings.read());
+        self.ui.open_library_moved_message(&target);
+    }
+
     #[cfg(target_os = "windows")]
     pub fn open_data_directory(&mut self) {
-        debug!("Opening dir: {:?}", self.settings.read().general.data_dir);
-        Command::new("explorer")
-            .arg(&self.settings.read().general.data_dir)
-            .spawn()
-            .unwrap();
+        debug!("Opening dir: {:?}", profile_dir(DATA_DIR));
+        Command::new("explorer").arg(profile_dir(DATA_DIR)).spawn().unwrap();
     }
 
     #[cfg(target_os = "linux")]
     pub fn open_data_directory(&mut self) {
-        Command::new("xdg-open")
-            .arg(&self.settings.read().general.data_dir)
-            .spawn()
-            .unwrap();
+        Command::new("xdg-open").arg(profile_dir(DATA_DIR)).spawn().unwrap();
     }
 
     #[cfg(target_os = "macos")]
     pub fn open_data_directory(&mut self) {
-        Command::new("open")
-            .arg(&self.settings.read().general.data_dir)
-            .spawn()
-            .unwrap();
+        Command::new("open").arg(profile_dir(DATA_DIR)).spawn().unwrap();
     }
 
     #[cfg(target_os = "windows")]
     pub fn start_with_windows(&self, add_to_reg: bool) {
-        use crate::APPLICATION_ID;
+        use eris_core::APPLICATION_ID;
         use std::env::current_exe;
         use std::path::Path;
         use winreg::enums::*;
@@ -0,0 +1,66 @@
+use crate::app::settings::GeneralSettings;
+use crate::appop::AppOp;
+use eris_core::sync::{read_other_journals, replay, Journal, SyncConflict, SyncState};
+
+/// Journal of this device in the sync folder, if sync is enabled.
+pub fn sync_journal(settings: &GeneralSettings) -> Option<Journal> {
+    settings
+        .sync_dir
+        .as_ref()
+        .map(|sync_dir| Journal::new(sync_dir, &settings.sync_device))
+}
+
+impl AppOp {
+    /// Replay the journals of the other devices and save the result.
+    /// Returns the fields that were changed on both sides.
+    pub fn sync_replay(&mut self) -> Vec<SyncConflict> {
+        let (sync_dir, device) = {
+            let settings = self.settings.read();
+            match &settings.general.sync_dir {
+                Some(sync_dir) => (sync_dir.clone(), settings.general.sync_device.clone()),
+                None => return vec![],
+            }
+        };
+        // Nothing can be saved into a read-only database
+        let mut library = self.library.write();
+        if library.db.read_only {
+            return vec![];
+        }
+
+        let entries = read_other_journals(&sync_dir, &device);
+        if entries.is_empty() {
+            return vec![];
+        }
+
+        let mut state = SyncState::open();
+        let library = &mut *library;
+        let storage = library.storage();
+        let result = replay(&mut library.db, &library.history, entries, &mut state);
+        debug!(
+            "Sync replayed {} history items, {} conflicts",
+            result.history_added.len(),
+            result.conflicts.len()
+        );
+
+        if result.db_changed {
+            if let Err(e) = storage.write_database(&mut library.db) {
+                error!("Cannot save synced novels. {:#}", e);
+                // Replay again next time
+                return result.conflicts;
+            }
+        }
+        if !result.history_added.is_empty() {
+            let history = &mut library.history;
+            history.items.extend(result.history_added);
+            history.items.sort_by_key(|item| item.time);
+            if let Err(e) = storage.write_history(history) {
+                error!("Cannot save synced history. {:#}", e);
+            }
+        }
+        if let Err(e) = state.write_to_file() {
+            error!("Cannot save sync state. {:#}", e);
+        }
+
+        result.conflicts
+    }
+}
@@ -0,0 +1,407 @@
+use crate::app::settings::Settings;
+use crate::cli::{status_name, CliError, Command, USAGE};
+use chrono::{Local, TimeZone};
+use eris_core::backup::{Backup, BackupReason};
+use eris_core::error::ErisError;
+use eris_core::export::{export_db_to_json, export_history_to_json, read_export, ExportKind, ImportData};
+use eris_core::history::NovelHistoryItem;
+use eris_core::library::Library;
+use eris_core::merge::MergePreview;
+use eris_core::novel::{Novel, NovelSettings};
+use eris_core::parsers::{fetch_novel, novel_url};
+use eris_core::profile::profile_dir;
+use eris_core::progress::ProgressDecision;
+use eris_core::storage;
+use eris_core::sync::Journal;
+use eris_core::title_rules::TitleRules;
+use eris_core::trace::{read_trace, replay, ReplayStep, TitleReplay};
+use eris_core::{NOVEL_UPDATE_COOLDOWN, TITLE_RULES_FILE};
+use serde::Serialize;
+use serde_json::json;
+
+/// The novels and history of the current profile, read the same way the application reads them.
+fn open_library(settings: &Settings) -> Result<Library, CliError> {
+    let storage = storage::open(&settings.general.storage_backend);
+    let (library, recovery) = Library::open(storage)?;
+    if let Some(recovery) = recovery {
+        warn!("Cannot read the novels, changes are not saved. {:?}", recovery.attempts);
+    }
+
+    Ok(match &settings.general.sync_dir {
+        Some(sync_dir) => library.with_journal(Journal::new(sync_dir, &settings.general.sync_device)),
+        None => library,
+    })
+}
+
+/// Find a novel by its id or by its url.
+fn find(library: &Library, id: &str) -> Result<Novel, CliError> {
+    library
+        .find(id)
+        .cloned()
+        .ok_or_else(|| ErisError::NovelNotFound(id.to_string()).into())
+}
+
+/// A novel as it is printed with `--json`.
+#[derive(Serialize)]
+struct NovelSummary<'a> {
+    id: &'a str,
+    title: &'a str,
+    status: &'static str,
+    read: String,
+    available: String,
+    url: Option<&'a str>,
+}
+
+impl<'a> From<&'a Novel> for NovelSummary<'a> {
+    fn from(novel: &'a Novel) -> Self {
+        NovelSummary {
+            id: &novel.id,
+            title: &novel.title,
+            status: status_name(novel.settings.list_status),
+            read: novel.settings.content_read.to_string(false),
+            available: novel.content.to_string(false),
+            url: novel.slug.as_deref(),
+        }
+    }
+}
+
+pub fn run(command: Command, json: bool) -> Result<(), CliError> {
+    if command == Command::Help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let settings = Settings::open()?;
+    let mut library = open_library(&settings)?;
+
+    match command {
+        Command::List { status } => {
+            let novels: Vec<NovelSummary> = library
+                .novels()
+                .iter()
+                .filter(|n| status.map_or(true, |status| n.settings.list_status == status))
+                .map(NovelSummary::from)
+                .collect();
+
+            if json {
+                print_json(&novels)?;
+            } else {
+                for novel in novels {
+                    println!("{}\t{}\t{}\t{}", novel.id, novel.status, novel.read, novel.title);
+                }
+            }
+        }
+        Command::Show { id } => {
+            let novel = find(&library, &id)?;
+
+            if json {
+                print_json(&novel)?;
+            } else {
+                print_novel(&novel);
+            }
+        }
+        Command::Add { url, status, read } => {
+            let url = novel_url(&url);
+            if let Some(novel) = library.find(&url) {
+                return Err(ErisError::NovelExists(novel.id.clone()).into());
+            }
+
+            let mut novel = fetch_novel(&url)?;
+            novel.settings = NovelSettings {
+                list_status: status,
+                content_read: read,
+                ..NovelSettings::default()
+            };
+
+            library.add(novel.clone())?;
+            print_result(json, &novel, &format!("Added {}", novel.title))?;
+        }
+        Command::SetRead { id, read } => {
+            let novel_id = find(&library, &id)?.id;
+            library.mark_read(&novel_id, read, None)?;
+
+            let novel = find(&library, &novel_id)?;
+            print_result(
+                json,
+                &novel,
+                &format!(
+                    "{} read to {}",
+                    novel.title,
+                    novel.settings.content_read.to_string(true).trim()
+                ),
+            )?;
+        }
+        Command::Move { id, status } => {
+            let novel_id = find(&library, &id)?.id;
+            library.move_to(&novel_id, status)?;
+
+            let novel = find(&library, &novel_id)?;
+            print_result(
+                json,
+                &novel,
+                &format!("Moved {} to {}", novel.title, status_name(status)),
+            )?;
+        }
+        Command::Update { ids, all, force } => {
+            if library.db.read_only {
+                return Err(ErisError::ReadOnlyDatabase.into());
+            }
+            let novels = if all {
+                library
+                    .novels()
+                    .iter()
+                    .filter(|n| n.is_slug_supported())
+                    .cloned()
+                    .collect()
+            } else {
+                ids.iter().map(|id| find(&library, id)).collect::<Result<Vec<_>, _>>()?
+            };
+
+            let mut updated = vec![];
+            let mut failed = 0;
+            for old_novel in novels {
+                let url = match &old_novel.slug {
+                    Some(url) => url.clone(),
+                    None => {
+                        eprintln!("{} has no url to update from", old_novel.title);
+                        continue;
+                    }
+                };
+                if !force && old_novel.last_scrape + NOVEL_UPDATE_COOLDOWN > Local::now().timestamp() {
+                    eprintln!("{} was updated less than an hour ago", old_novel.title);
+                    continue;
+                }
+
+                match fetch_novel(&url)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|novel| library.update(novel))
+                {
+                    Ok(_) => {
+                        let novel = find(&library, &old_novel.id)?;
+                        if !json {
+                            println!("Updated {}", novel.title);
+                        }
+                        updated.push(novel);
+                    }
+                    Err(e) => {
+                        eprintln!("Could not update {}. {:#}", old_novel.title, e);
+                        failed += 1;
+                    }
+                }
+            }
+
+            if json {
+                print_json(&updated.iter().map(NovelSummary::from).collect::<Vec<_>>())?;
+            }
+            if failed > 0 {
+                return Err(CliError::Failed(format!("{} novels could not be updated", failed)));
+            }
+        }
+        Command::Export { kind } => {
+            let path = match kind {
+                ExportKind::Database => export_db_to_json(&library.db)?,
+                ExportKind::History => export_history_to_json(&library.history)?,
+            };
+
+            if json {
+                print_json(&json!({ "path": path }))?;
+            } else {
+                println!("{}", path.display());
+            }
+        }
+        Command::Import { file } => {
+            if library.db.read_only {
+                return Err(ErisError::ReadOnlyDatabase.into());
+            }
+            let preview = match read_export(&file)? {
+                ImportData::Database(db) => {
+                    MergePreview::novels(library.novels(), db.novels.as_deref().unwrap_or_default())
+                }
+                ImportData::History(history) => MergePreview::history(&library.history, &history),
+            };
+
+            if !preview.is_empty() {
+                let retention = settings.general.backup_retention;
+                if retention > 0 {
+                    Backup::create(&library.db, &library.history, BackupReason::Import, retention)?;
+                }
+
+                library.apply_merge(&preview)?;
+            }
+
+            let summary = json!({
+                "added": preview.added.len(),
+                "updated": preview.updated.len() + preview.conflicts.len(),
+                "unchanged": preview.unchanged,
+                "history_added": preview.history_added.len(),
+            });
+            if json {
+                print_json(&summary)?;
+            } else {
+                println!(
+                    "Added {} novels, updated {}, {} unchanged and {} history items",
+                    summary["added"], summary["updated"], summary["unchanged"], summary["history_added"]
+                );
+            }
+        }
+        Command::History { novel, limit } => {
+            let novel_id = novel.map(|id| find(&library, &id)).transpose()?.map(|n| n.id);
+            let mut items: Vec<&NovelHistoryItem> = library
+                .history
+                .items
+                .iter()
+                .filter(|item| novel_id.as_ref().map_or(true, |id| &item.novel_id == id))
+                .collect();
+            items.sort_by(|a, b| b.time.cmp(&a.time));
+            items.truncate(limit);
+
+            if json {
+                print_json(&items)?;
+            } else {
+                for item in items {
+                    let time = Local.timestamp_millis(item.time).format("%Y-%m-%d %H:%M");
+                    println!("{}\t{}\t{}", time, item.novel_name, item.detail_string());
+                }
+            }
+        }
+        Command::ReplayTrace { file, db } => {
+            let lines = read_trace(&file)?;
+            let title_rules = TitleRules::open(&profile_dir(TITLE_RULES_FILE))?;
+            // Replaying changes only these copies, never the library
+            let (mut novels, mut history) = match db {
+                Some(db) => match read_export(&db)? {
+                    ImportData::Database(export) => (export.novels.unwrap_or_default(), Default::default()),
+                    ImportData::History(_) => {
+                        return Err(CliError::Failed(format!("{} is not a novels export", db.display())))
+                    }
+                },
+                None => (library.novels().to_vec(), library.history.clone()),
+            };
+
+            let steps = replay(
+                &lines,
+                &title_rules,
+                &settings.novel_recognition.title_markers,
+                settings.novel_recognition.auto_confidence,
+                &mut novels,
+                &mut history,
+                settings.novel_recognition.progress_rules(),
+            );
+
+            if json {
+                print_json(&steps)?;
+            } else {
+                for step in &steps {
+                    print_replay_step(step);
+                }
+            }
+        }
+        Command::Help => unreachable!(),
+    }
+
+    Ok(())
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), CliError> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| CliError::Failed(e.to_string()))?;
+    println!("{}", text);
+
+    Ok(())
+}
+
+/// Print the changed `novel` with `--json`, otherwise the `message`.
+fn print_result(json: bool, novel: &Novel, message: &str) -> Result<(), CliError> {
+    if json {
+        return print_json(&NovelSummary::from(novel));
+    }
+
+    println!("{}", message);
+    Ok(())
+}
+
+fn print_novel(novel: &Novel) {
+    println!("{}", novel.title);
+    println!("  id:        {}", novel.id);
+    println!("  status:    {}", status_name(novel.settings.list_status));
+    println!("  read:      {}", novel.settings.content_read.to_string(true).trim());
+    println!("  available: {}", novel.content.to_string(true).trim());
+    println!("  score:     {}", novel.settings.score);
+    if let Some(url) = &novel.slug {
+        println!("  url:       {}", url);
+    }
+    println!("  last read: {}", novel.settings.last_read_string());
+}
+
+fn print_replay_step(step: &ReplayStep) {
+    let time = Local.timestamp(step.line.time, 0).format("%Y-%m-%d %H:%M:%S");
+    if step.titles.is_empty() {
+        println!("{}\t-", time);
+    }
+    for title in &step.titles {
+        println!("{}\t{}", time, title.title);
+        print_title_replay(title);
+    }
+    for ignored in &step.line.ignored {
+        println!("  ignored:   {} (keyword `{}`)", ignored.title, ignored.keyword);
+    }
+}
+
+fn print_title_replay(step: &TitleReplay) {
+    let data = match &step.data {
+        Some(data) => data,
+        None => {
+            println!("  not a reading title");
+            return;
+        }
+    };
+    println!("  parts:     {}", step.strings.join(" | "));
+    if let Some(rule) = &step.rule {
+        println!("  rule:      {}", rule);
+    }
+    println!(
+        "  read:      v{} c{}{} ss{}{}{}",
+        data.volume,
+        data.chapter,
+        if data.part > 0 {
+            format!(" p{}", data.part)
+        } else {
+            String::new()
+        },
+        data.side_story,
+        data.chapter_label
+            .as_ref()
+            .map(|label| format!(" [{}]", label))
+            .unwrap_or_default(),
+        data.chapter_title
+            .as_ref()
+            .map(|title| format!(" \"{}\"", title))
+            .unwrap_or_default()
+    );
+    if let Some(matched) = &step.matched {
+        println!(
+            "  match:     {} for \"{}\", {:.0}% by {:?}{}",
+            matched.novel_title,
+            matched.text,
+            matched.confidence * 100.0,
+            matched.reason,
+            if step.novel.is_none() { ", to be confirmed" } else { "" }
+        );
+    }
+    match (&step.novel, &step.novel_title) {
+        (Some((id, title)), _) => println!("  novel:     {} ({})", title, id),
+        (None, Some(title)) => println!("  novel:     not found, guessed \"{}\"", title),
+        (None, None) => println!("  novel:     not found"),
+    }
+    match &step.decision {
+        Some(ProgressDecision::Changed(change)) => println!(
+            "  decision:  read to {}{}",
+            change.content_read.to_string(true).trim(),
+            change
+                .move_to
+                .map(|status| format!(", move to {}", status_name(status)))
+                .unwrap_or_default()
+        ),
+        Some(ProgressDecision::Unchanged) => println!("  decision:  unchanged"),
+        None => {}
+    }
+}
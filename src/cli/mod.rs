@@ -0,0 +1,334 @@
+//! Headless command-line interface for managing the library without the GTK window.
+//!
+//! `eris <command> [arguments]` works on the same novels and history files as the
+//! application itself, so it should not be used while the window is open or the
+//! changes may be overwritten. `--json` prints the results as JSON for piping.
+
+mod commands;
+
+use eris_core::error::ErisError;
+use eris_core::export::ExportKind;
+use eris_core::novel::{ListStatus, NovelContentAmount};
+use std::fmt;
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: eris [--profile <name>] [--data-dir <path>] <command> [--json]
+
+Commands:
+  list [--status <status>]              List the novels
+  show <id>                             Show one novel
+  add <url> [--status <status>] [--read <amount>]
+                                        Add a novel from a supported site
+  set-read <id> <amount>                Set what was read, e.g. `v2c34` or `c120ss1`
+  move <id> <status>                    Move a novel to another list
+  update [<id>...] [--all] [--force]    Update novels from their site
+  export <novels|history>               Export to a JSON file in the data directory
+  import <file>                         Merge a JSON export into the library
+  history [--novel <id>] [--limit <n>]  Show the latest history
+  replay-trace <file> [--db <file>]     Run a recognition trace against the library or a novels export
+  help                                  Show this
+
+Statuses: reading, plan-to-read, on-hold, completed, dropped
+Novels can be given by their id or url.";
+
+/// Commands that can be given as the first argument.
+const COMMANDS: &[&str] = &[
+    "list",
+    "show",
+    "add",
+    "set-read",
+    "move",
+    "update",
+    "export",
+    "import",
+    "history",
+    "replay-trace",
+    "help",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    List {
+        status: Option<ListStatus>,
+    },
+    Show {
+        id: String,
+    },
+    Add {
+        url: String,
+        status: ListStatus,
+        read: NovelContentAmount,
+    },
+    SetRead {
+        id: String,
+        read: NovelContentAmount,
+    },
+    Move {
+        id: String,
+        status: ListStatus,
+    },
+    Update {
+        ids: Vec<String>,
+        all: bool,
+        force: bool,
+    },
+    Export {
+        kind: ExportKind,
+    },
+    Import {
+        file: PathBuf,
+    },
+    History {
+        novel: Option<String>,
+        limit: usize,
+    },
+    ReplayTrace {
+        file: PathBuf,
+        db: Option<PathBuf>,
+    },
+    Help,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    /// Arguments were wrong, the usage is shown.
+    Usage(String),
+    Failed(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}\n\n{}", message, USAGE),
+            CliError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<ErisError> for CliError {
+    fn from(err: ErisError) -> Self {
+        CliError::Failed(err.to_string())
+    }
+}
+
+impl From<anyhow::Error> for CliError {
+    fn from(err: anyhow::Error) -> Self {
+        CliError::Failed(format!("{:#}", err))
+    }
+}
+
+/// Is the first argument after the program name a command.
+pub fn is_command(args: &[String]) -> bool {
+    args.get(1).map_or(false, |arg| COMMANDS.contains(&arg.as_str()))
+}
+
+/// Print to the console of the shell Eris was started from. Release builds on Windows
+/// are window programs that do not get a console of their own.
+#[cfg(target_os = "windows")]
+pub fn attach_console() {
+    use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    // Fails if there already is a console or if not started from one, both are fine
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn attach_console() {}
+
+/// Run the command in `args`, program name excluded, and return the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let (command, json) = match parse(args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+
+    match commands::run(command, json) {
+        Ok(_) => 0,
+        Err(e @ CliError::Usage(_)) => {
+            eprintln!("{}", e);
+            2
+        }
+        Err(e) => {
+            eprintln!("eris: {}", e);
+            1
+        }
+    }
+}
+
+/// Parse the command and whether the output should be JSON.
+pub fn parse(args: &[String]) -> Result<(Command, bool), CliError> {
+    let mut json = false;
+    let mut positional = vec![];
+    let mut options: Vec<(&str, Option<&str>)> = vec![];
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--all" | "--force" => options.push((arg.as_str(), None)),
+            "--status" | "--read" | "--novel" | "--limit" | "--db" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| CliError::Usage(format!("`{}` needs a value", arg)))?;
+                options.push((arg.as_str(), Some(value.as_str())));
+            }
+            _ if arg.starts_with("--") => return Err(CliError::Usage(format!("Unknown option `{}`", arg))),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let option = |name: &str| options.iter().find(|(n, _)| *n == name).and_then(|(_, v)| *v);
+    let flag = |name: &str| options.iter().any(|(n, _)| *n == name);
+    let argument = |index: usize, name: &str| {
+        positional
+            .get(index)
+            .map(|arg| arg.to_string())
+            .ok_or_else(|| CliError::Usage(format!("Missing {}", name)))
+    };
+
+    let command = match positional.first().copied().unwrap_or("help") {
+        "list" => Command::List {
+            status: option("--status").map(parse_status).transpose()?,
+        },
+        "show" => Command::Show {
+            id: argument(1, "novel id")?,
+        },
+        "add" => Command::Add {
+            url: argument(1, "url")?,
+            status: option("--status").map(parse_status).transpose()?.unwrap_or_default(),
+            read: option("--read").map(parse_amount).transpose()?.unwrap_or_default(),
+        },
+        "set-read" => Command::SetRead {
+            id: argument(1, "novel id")?,
+            read: parse_amount(&argument(2, "read amount")?)?,
+        },
+        "move" => Command::Move {
+            id: argument(1, "novel id")?,
+            status: parse_status(&argument(2, "status")?)?,
+        },
+        "update" => {
+            let ids: Vec<String> = positional[1..].iter().map(|id| id.to_string()).collect();
+            let all = flag("--all");
+            if ids.is_empty() && !all {
+                return Err(CliError::Usage("Give the novels to update or `--all`".to_string()));
+            }
+
+            Command::Update {
+                ids,
+                all,
+                force: flag("--force"),
+            }
+        }
+        "export" => Command::Export {
+            kind: match argument(1, "what to export")?.as_str() {
+                "novels" => ExportKind::Database,
+                "history" => ExportKind::History,
+                other => return Err(CliError::Usage(format!("Cannot export `{}`", other))),
+            },
+        },
+        "import" => Command::Import {
+            file: PathBuf::from(argument(1, "file")?),
+        },
+        "history" => Command::History {
+            novel: option("--novel").map(|id| id.to_string()),
+            limit: match option("--limit") {
+                Some(limit) => limit
+                    .parse()
+                    .map_err(|_| CliError::Usage(format!("`{}` is not a number", limit)))?,
+                None => 20,
+            },
+        },
+        "replay-trace" => Command::ReplayTrace {
+            file: PathBuf::from(argument(1, "trace file")?),
+            db: option("--db").map(PathBuf::from),
+        },
+        "help" => Command::Help,
+        other => return Err(CliError::Usage(format!("Unknown command `{}`", other))),
+    };
+
+    Ok((command, json))
+}
+
+fn parse_status(value: &str) -> Result<ListStatus, CliError> {
+    match value {
+        "reading" => Ok(ListStatus::Reading),
+        "plan-to-read" => Ok(ListStatus::PlanToRead),
+        "on-hold" => Ok(ListStatus::OnHold),
+        "completed" => Ok(ListStatus::Completed),
+        "dropped" => Ok(ListStatus::Dropped),
+        _ => Err(CliError::Usage(format!("Unknown status `{}`", value))),
+    }
+}
+
+/// Name of the status as it is given on the command line.
+pub fn status_name(status: ListStatus) -> &'static str {
+    match status {
+        ListStatus::Reading => "reading",
+        ListStatus::PlanToRead => "plan-to-read",
+        ListStatus::OnHold => "on-hold",
+        ListStatus::Completed => "completed",
+        ListStatus::Dropped => "dropped",
+    }
+}
+
+fn parse_amount(value: &str) -> Result<NovelContentAmount, CliError> {
+    NovelContentAmount::parse(value).ok_or_else(|| {
+        CliError::Usage(format!(
+            "`{}` is not a read amount, use something like `v2c34ss1`",
+            value
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            parse(&args("set-read some-novel v2c34")).unwrap(),
+            (
+                Command::SetRead {
+                    id: "some-novel".to_string(),
+                    read: NovelContentAmount::new(2, 34.0, 0),
+                },
+                false
+            )
+        );
+        assert_eq!(
+            parse(&args("list --json --status on-hold")).unwrap(),
+            (
+                Command::List {
+                    status: Some(ListStatus::OnHold)
+                },
+                true
+            )
+        );
+        assert!(matches!(
+            parse(&args("update a b --force")).unwrap().0,
+            Command::Update { ids, all: false, force: true } if ids.len() == 2
+        ));
+        assert_eq!(
+            parse(&args("replay-trace trace.jsonl --db novels.json")).unwrap().0,
+            Command::ReplayTrace {
+                file: PathBuf::from("trace.jsonl"),
+                db: Some(PathBuf::from("novels.json")),
+            }
+        );
+
+        assert!(matches!(parse(&args("update")), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&args("replay-trace")), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&args("move a finished")), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&args("set-read a chapter")), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&args("list --status")), Err(CliError::Usage(_))));
+    }
+}
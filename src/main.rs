@@ -10,7 +10,6 @@ extern crate window_titles;
 extern crate cascade;
 extern crate bincode;
 extern crate chrono;
-extern crate sanitize_filename;
 extern crate serde;
 extern crate slug;
 extern crate webbrowser;
@@ -21,12 +20,15 @@ use const_format::formatcp;
 use std::error::Error;
 use std::fs;
 use std::fs::File;
-use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use crate::app::localize::localizer;
 use crate::app::settings::Settings;
-use crate::utils::data_dir;
+use eris_core::data_location::remove_moved_from_dirs;
+use eris_core::paths::{data_dir, portable_data_dir, set_data_dir, DataDirSource};
+use eris_core::profile::{application_id, profile_dir, set_profile};
+use eris_core::{DATA_DIR, DATA_IMAGE_DIR};
 use gtk::prelude::*;
 use i18n_embed::unic_langid::LanguageIdentifier;
 use i18n_embed::DesktopLanguageRequester;
@@ -36,19 +38,17 @@ mod utils;
 pub mod actions;
 pub mod app;
 pub mod appop;
+mod cli;
 pub mod ui;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const UPDATE_LINK: &str = formatcp!("{}releases/latest", env!("CARGO_PKG_REPOSITORY"));
 
-pub const DATA_DIR: &str = "data";
 pub const LOG_FILE: &str = formatcp!("{DATA_DIR}/eris.log");
-pub const CONFIG_NAME: &str = formatcp!("{DATA_DIR}/eris.conf");
-pub const DB_FILE: &str = formatcp!("{DATA_DIR}/db/eris.db");
-pub const HISTORY_FILE: &str = formatcp!("{DATA_DIR}/eris.history");
+pub const CONFIG_NAME: &str = formatcp!("{DATA_DIR}/eris.toml");
+/// Settings file from before they were saved as TOML.
+pub const BINCODE_CONFIG_NAME: &str = formatcp!("{DATA_DIR}/eris.conf");
 pub const STATE_CONFIG_NAME: &str = formatcp!("{DATA_DIR}/eris.state");
-pub const DATA_IMAGE_DIR: &str = formatcp!("{DATA_DIR}/db/images");
-pub const APPLICATION_ID: &str = "com.github.temeez.eris";
 
 fn setup_logging() -> Result<(), fern::InitError> {
     #[cfg(debug_assertions)]
@@ -56,7 +56,7 @@ fn setup_logging() -> Result<(), fern::InitError> {
     #[cfg(not(debug_assertions))]
     let log_level = log::LevelFilter::Info;
 
-    let path = Path::new(&data_dir(LOG_FILE)).to_path_buf();
+    let path = profile_dir(LOG_FILE);
 
     // Clear the log file if it exists
     if path.as_path().exists() {
@@ -64,17 +64,7 @@ fn setup_logging() -> Result<(), fern::InitError> {
         f.set_len(0).expect("Cannot set file length to 0");
     }
 
-    let settings_data = Settings::open().expect("Failed to open settings file.");
-    // Get either the language from settings or the system default one
-    let requested_languages: Vec<LanguageIdentifier> = if let Some(language) = &settings_data.general.language {
-        vec![LanguageIdentifier::from_str(language).unwrap()]
-    } else {
-        DesktopLanguageRequester::requested_languages()
-    };
-
-    if let Err(error) = localizer().select(&requested_languages) {
-        error!("Cannot load language. {:?}", error);
-    }
+    select_language();
 
     fern::Dispatch::new()
         // Perform allocation-free log formatting
@@ -89,6 +79,7 @@ fn setup_logging() -> Result<(), fern::InitError> {
         })
         .level(log::LevelFilter::Info)
         .level_for("eris", log_level)
+        .level_for("eris_core", log_level)
         .chain(std::io::stdout())
         .chain(fern::log_file(path)?)
         .apply()?;
@@ -96,34 +87,105 @@ fn setup_logging() -> Result<(), fern::InitError> {
     Ok(())
 }
 
+/// Use either the language from settings or the system default one.
+fn select_language() {
+    let settings_data = Settings::open().expect("Failed to open settings file.");
+    let requested_languages: Vec<LanguageIdentifier> = if let Some(language) = &settings_data.general.language {
+        vec![LanguageIdentifier::from_str(language).unwrap()]
+    } else {
+        DesktopLanguageRequester::requested_languages()
+    };
+
+    for localizer in [localizer(), eris_core::localize::localizer()] {
+        if let Err(error) = localizer.select(&requested_languages) {
+            error!("Cannot load language. {:?}", error);
+        }
+    }
+}
+
+/// Warnings and errors of the command-line interface go to stderr, stdout is for the results.
+fn setup_cli_logging() -> Result<(), fern::InitError> {
+    fern::Dispatch::new()
+        .format(|out, message, record| out.finish(format_args!("eris: {}: {}", record.level(), message)))
+        .level(log::LevelFilter::Warn)
+        .chain(std::io::stderr())
+        .apply()?;
+
+    Ok(())
+}
+
 fn generate_dirs() {
-    if !&data_dir(DATA_IMAGE_DIR).exists() {
-        match fs::create_dir_all(&data_dir(DATA_IMAGE_DIR)) {
+    if !&profile_dir(DATA_IMAGE_DIR).exists() {
+        match fs::create_dir_all(&profile_dir(DATA_IMAGE_DIR)) {
             Ok(_) => {}
             Err(e) => {
                 error!("{}", e);
                 panic!(
                     "Could not create directory `{}`",
-                    &data_dir(DATA_IMAGE_DIR).to_str().unwrap()
+                    &profile_dir(DATA_IMAGE_DIR).to_str().unwrap()
                 );
             }
         }
     }
 }
 
+/// Take the arguments handled by Eris out of the command line and
+/// return the rest for GTK.
+///
+/// `--profile <name>` or `--profile=<name>` selects the library profile.
+/// `--data-dir <path>` or `--data-dir=<path>` uses another data directory and
+/// `--portable` the `data` directory next to the executable.
+fn parse_args() -> Vec<String> {
+    let mut args = std::env::args();
+    let mut gtk_args: Vec<String> = args.next().into_iter().collect();
+    let mut profile = None;
+
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            profile = args.next();
+        } else if let Some(name) = arg.strip_prefix("--profile=") {
+            profile = Some(name.to_string());
+        } else if arg == "--data-dir" {
+            if let Some(dir) = args.next() {
+                set_data_dir(PathBuf::from(dir), DataDirSource::Flag);
+            }
+        } else if let Some(dir) = arg.strip_prefix("--data-dir=") {
+            set_data_dir(PathBuf::from(dir), DataDirSource::Flag);
+        } else if arg == "--portable" {
+            set_data_dir(portable_data_dir(), DataDirSource::Flag);
+        } else {
+            gtk_args.push(arg);
+        }
+    }
+    set_profile(profile.as_deref());
+
+    gtk_args
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let gtk_args = parse_args();
     // Verify that the folder exists and create it if not
     generate_dirs();
+    // After the library was moved the old data directory is no longer in use
+    remove_moved_from_dirs();
+
+    if cli::is_command(&gtk_args) {
+        cli::attach_console();
+        setup_cli_logging().expect("failed to initialize logging.");
+        select_language();
+        std::process::exit(cli::run(&gtk_args[1..]));
+    }
+
     // The settings file location needs to exist for `setup_logging()`
     setup_logging().expect("failed to initialize logging.");
 
-    let application = gtk::Application::new(Some(APPLICATION_ID), gio::ApplicationFlags::FLAGS_NONE);
+    let application = gtk::Application::new(Some(&application_id()), gio::ApplicationFlags::FLAGS_NONE);
 
     application.connect_startup(|application| {
         app::on_startup(application);
     });
 
-    application.run();
+    application.run_with_args(&gtk_args);
 
     Ok(())
 }
@@ -0,0 +1,93 @@
+use crate::app::AppRuntime;
+use crate::ui::UI;
+use eris_core::backup::BackupInfo;
+use gtk::prelude::*;
+use gtk::{DialogFlags, ResponseType};
+
+impl UI {
+    /// List the backups with their novel and history counts and restore the selected one.
+    pub fn open_restore_backup_dialog(&self, backups: Vec<BackupInfo>, app_runtime: &AppRuntime) {
+        if backups.is_empty() {
+            self.notification_dialog(&fl!("backup-none"));
+            return;
+        }
+
+        let list_box = cascade! {
+            gtk::ListBox::new();
+            ..set_selection_mode(gtk::SelectionMode::Single);
+        };
+        for backup in &backups {
+            let text = fl!(
+                "backup-row",
+                time = backup.created_string(),
+                reason = backup.reason.to_string(),
+                novels = backup.novel_count,
+                history = backup.history_count
+            );
+            list_box.add(&cascade! {
+                gtk::Label::new(Some(&text));
+                ..set_xalign(0.0);
+                ..set_margin_top(6);
+                ..set_margin_bottom(6);
+                ..set_margin_start(6);
+                ..set_margin_end(6);
+            });
+        }
+        list_box.select_row(list_box.row_at_index(0).as_ref());
+
+        let scrolled_window = cascade! {
+            gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+            ..set_min_content_height(300);
+            ..set_vexpand(true);
+            ..add(&list_box);
+        };
+
+        let dialog = cascade! {
+            gtk::Dialog::with_buttons(
+                Some(&fl!("backup-restore-title")),
+                Some(&self.main_window),
+                DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+                &[
+                    (fl!("cancel-button").as_str(), ResponseType::Cancel),
+                    (fl!("backup-restore").as_str(), ResponseType::Accept),
+                ]
+            );
+            ..set_default_width(480);
+        };
+        cascade! {
+            dialog.content_area();
+            ..set_spacing(6);
+            ..add(&cascade! {
+                gtk::Label::new(Some(&fl!("backup-restore-text")));
+                ..set_xalign(0.0);
+                ..set_line_wrap(true);
+                ..set_margin_start(6);
+                ..set_margin_end(6);
+            });
+            ..add(&scrolled_window);
+        };
+
+        dialog.connect_response(glib::clone!(@strong app_runtime => move |dialog, response_type| {
+            if response_type == ResponseType::Accept {
+                let selected = list_box
+                    .selected_row()
+                    .and_then(|row| backups.get(row.index() as usize))
+                    .map(|backup| backup.path.clone());
+
+                if let Some(path) = selected {
+                    app_runtime.update_state_with(move |state| match state.restore_backup(path) {
+                        Ok(_) => state.ui.open_post_import_message(&state.app_runtime),
+                        Err(e) => {
+                            error!("Could not restore backup. {:#}", e);
+                            state.ui.notification_dialog(&e.to_string());
+                        }
+                    });
+                }
+            }
+
+            dialog.close();
+        }));
+
+        dialog.show_all();
+    }
+}
@@ -0,0 +1,53 @@
+use crate::app::AppRuntime;
+use crate::ui::UI;
+use gtk::prelude::*;
+use gtk::{ButtonsType, DialogFlags, FileChooserAction, MessageType, ResponseType};
+use std::path::Path;
+
+impl UI {
+    /// Ask for an empty folder and move the library there.
+    pub fn open_move_library_dialog(&self, app_runtime: &AppRuntime) {
+        let dialog = gtk::FileChooserDialog::with_buttons(
+            Some(&fl!("move-library-title")),
+            Some(&self.main_window),
+            FileChooserAction::SelectFolder,
+            &[
+                (fl!("cancel-button").as_str(), ResponseType::Cancel),
+                (fl!("move-dir-button").as_str(), ResponseType::Accept),
+            ],
+        );
+
+        dialog.connect_response(glib::clone!(@strong app_runtime => move |dialog, response_type| {
+            if response_type == ResponseType::Accept {
+                if let Some(target) = dialog.filename() {
+                    app_runtime.update_state_with(move |state| state.move_library(target));
+                }
+            }
+
+            dialog.close();
+        }));
+
+        dialog.show_all();
+    }
+
+    /// Tell that the library was moved and quit once the message is closed.
+    pub fn open_library_moved_message(&self, target: &Path) {
+        let main_window = self.main_window.clone();
+
+        cascade! {
+            gtk::MessageDialog::new(
+                Some(&self.main_window),
+                DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+                MessageType::Info,
+                ButtonsType::Ok,
+                &fl!("library-moved-text", path = target.display().to_string())
+            );
+            ..set_title(&fl!("success"));
+            ..connect_response(move |dialog, _| {
+                dialog.close();
+                main_window.close();
+            });
+            ..show_all();
+        };
+    }
+}
@@ -0,0 +1,58 @@
+use crate::app::AppRuntime;
+use crate::ui::UI;
+use eris_core::database::DatabaseRecovery;
+use gtk::prelude::*;
+use gtk::{ButtonsType, DialogFlags, MessageType, ResponseType};
+
+impl UI {
+    /// Tell the user that the database file could not be read, what was tried
+    /// and offer to import the latest JSON export if there is one.
+    pub fn open_database_recovery_message(&self, recovery: &DatabaseRecovery, app_runtime: &AppRuntime) {
+        let (message_type, text) = if let Some(version) = recovery.newer_version {
+            (
+                MessageType::Error,
+                fl!("database-newer-version-text", version = version),
+            )
+        } else if recovery.restored_from_backup {
+            (MessageType::Warning, fl!("database-restored-text"))
+        } else {
+            (MessageType::Error, fl!("database-read-only-text"))
+        };
+        let attempts = format!("{}\n{}", fl!("database-recovery-tried"), recovery.attempts.join("\n"));
+
+        let dialog = cascade! {
+            gtk::MessageDialog::new(
+                Some(&self.main_window),
+                DialogFlags::DESTROY_WITH_PARENT,
+                message_type,
+                ButtonsType::Ok,
+                &text
+            );
+            ..set_title(&fl!("database-recovery-title"));
+            ..set_secondary_text(Some(&attempts));
+        };
+
+        if let Some(json_export) = &recovery.json_export {
+            let file_name = json_export
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            dialog.add_button(&fl!("database-import-export", file = file_name), ResponseType::Accept);
+        }
+
+        let json_export = recovery.json_export.clone();
+        dialog.connect_response(glib::clone!(@strong app_runtime => move |dialog, response_type| {
+            dialog.close();
+
+            if response_type == ResponseType::Accept {
+                if let Some(json_export) = json_export.clone() {
+                    app_runtime.update_state_with(move |state| {
+                        state.recover_db_from_json(json_export);
+                    });
+                }
+            }
+        }));
+
+        dialog.show_all();
+    }
+}
@@ -1,6 +1,6 @@
-use crate::app::novel::{NovelFile, ReadAmount};
 use crate::app::AppRuntime;
 use crate::utils::gtk::BuilderExtManualCustom;
+use eris_core::novel::{NovelFile, ReadAmount};
 use gdk::gdk_pixbuf::{InterpType, Pixbuf};
 use gtk::prelude::WidgetExtManual;
 use gtk::prelude::*;
@@ -1,11 +1,11 @@
-use crate::app::novel::Novel;
 use crate::app::settings::Settings;
 use crate::app::AppRuntime;
 use crate::ui::novel_list::{
-    action_open_novel_dialog, add_columns, list_sort_datetime, set_list_actions, Column, COLUMN_COUNT, COLUMN_TYPES,
-    ID_COLUMN,
+    action_open_novel_dialog, add_columns, list_sort_datetime, set_list_actions, status_pix, Column, COLUMN_COUNT,
+    COLUMN_TYPES, ID_COLUMN,
 };
 use crate::utils::gtk::BuilderExtManualCustom;
+use eris_core::novel::Novel;
 use gdk::cairo::glib::SignalHandlerId;
 use gio::prelude::*;
 use gtk::{prelude::*, SortColumn};
@@ -172,7 +172,7 @@ impl FilterList {
             let values: [(u32, &dyn ToValue); COLUMN_COUNT] = [
                 (ID_COLUMN as u32, &novel.id),
                 (Column::Status as u32, &novel.status.to_string()),
-                (Column::StatusIcon as u32, &novel.status_pix()),
+                (Column::StatusIcon as u32, &status_pix(novel)),
                 (Column::OriginalLanguage as u32, &novel.orig_lang()),
                 (Column::Title as u32, &novel.title()),
                 (Column::VolumesRead as u32, &novel.settings.content_read.volumes),
@@ -194,7 +194,7 @@ impl FilterList {
         let values: [(u32, &dyn ToValue); COLUMN_COUNT] = [
             (ID_COLUMN as u32, &novel.id),
             (Column::Status as u32, &novel.status.to_string()),
-            (Column::StatusIcon as u32, &novel.status_pix()),
+            (Column::StatusIcon as u32, &status_pix(novel)),
             (Column::OriginalLanguage as u32, &novel.orig_lang()),
             (Column::Title as u32, &novel.title()),
             (Column::VolumesRead as u32, &novel.settings.content_read.volumes),
@@ -231,7 +231,7 @@ impl FilterList {
             self.list
                 .set_value(iter, Column::Status as u32, &novel.status.to_str().to_value());
             self.list
-                .set_value(iter, Column::StatusIcon as u32, &novel.status_pix().to_value());
+                .set_value(iter, Column::StatusIcon as u32, &status_pix(novel).to_value());
             self.list
                 .set_value(iter, Column::Title as u32, &novel.title().to_value());
             self.list
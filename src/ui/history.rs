@@ -1,8 +1,8 @@
-use crate::app::history::NovelHistoryItem;
 use crate::app::settings::Settings;
 use crate::app::AppRuntime;
 use crate::ui::novel_list::{action_open_novel_dialog, set_list_actions, ID_COLUMN};
 use crate::utils::gtk::BuilderExtManualCustom;
+use eris_core::history::NovelHistoryItem;
 use gdk::cairo::glib::SignalHandlerId;
 use gio::prelude::*;
 use glib::bitflags::_core::cmp::Ordering;
@@ -0,0 +1,118 @@
+use crate::app::AppRuntime;
+use crate::ui::UI;
+use eris_core::merge::MergePreview;
+use gtk::prelude::*;
+use gtk::{ButtonsType, DialogFlags, MessageType, ResponseType};
+
+impl UI {
+    /// Show what an import would add, update and where it conflicts with the
+    /// current library. The merge is only applied if the user accepts it.
+    pub fn open_merge_preview_dialog(&self, preview: MergePreview, app_runtime: &AppRuntime) {
+        if preview.is_empty() {
+            self.notification_dialog(&fl!("merge-nothing"));
+            return;
+        }
+
+        let summary = fl!(
+            "merge-summary",
+            added = preview.added.len(),
+            updated = preview.updated.len(),
+            conflicts = preview.conflicts.len(),
+            unchanged = preview.unchanged,
+            history = preview.history_added.len()
+        );
+
+        let mut lines = vec![];
+        for novel in &preview.added {
+            lines.push(fl!("merge-added", title = novel.title.clone()));
+        }
+        for novel in &preview.updated {
+            lines.push(fl!(
+                "merge-updated",
+                title = novel.title.clone(),
+                read = novel.chapters_read_str()
+            ));
+        }
+        for conflict in &preview.conflicts {
+            let fields: Vec<String> = conflict.fields.iter().map(|f| f.to_string()).collect();
+            lines.push(fl!(
+                "merge-conflict",
+                title = conflict.merged.title.clone(),
+                fields = fields.join(", ")
+            ));
+        }
+
+        let list_box = cascade! {
+            gtk::ListBox::new();
+            ..set_selection_mode(gtk::SelectionMode::None);
+        };
+        for line in &lines {
+            list_box.add(&cascade! {
+                gtk::Label::new(Some(line));
+                ..set_xalign(0.0);
+                ..set_margin_top(3);
+                ..set_margin_bottom(3);
+                ..set_margin_start(6);
+                ..set_margin_end(6);
+            });
+        }
+
+        let scrolled_window = cascade! {
+            gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+            ..set_min_content_height(300);
+            ..set_vexpand(true);
+            ..add(&list_box);
+        };
+
+        let dialog = cascade! {
+            gtk::Dialog::with_buttons(
+                Some(&fl!("merge-title")),
+                Some(&self.main_window),
+                DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+                &[
+                    (fl!("cancel-button").as_str(), ResponseType::Cancel),
+                    (fl!("merge-apply").as_str(), ResponseType::Accept),
+                ]
+            );
+            ..set_default_width(520);
+        };
+        cascade! {
+            dialog.content_area();
+            ..set_spacing(6);
+            ..add(&cascade! {
+                gtk::Label::new(Some(&summary));
+                ..set_xalign(0.0);
+                ..set_line_wrap(true);
+                ..set_margin_start(6);
+                ..set_margin_end(6);
+            });
+            ..add(&scrolled_window);
+        };
+
+        dialog.connect_response(glib::clone!(@strong app_runtime => move |dialog, response_type| {
+            if response_type == ResponseType::Accept {
+                let preview = preview.clone();
+                app_runtime.update_state_with(move |state| state.apply_merge(preview));
+            }
+
+            dialog.close();
+        }));
+
+        dialog.show_all();
+    }
+
+    pub fn open_post_merge_message(&self) {
+        cascade! {
+            gtk::MessageDialog::new(
+                Some(&self.main_window),
+                DialogFlags::DESTROY_WITH_PARENT,
+                MessageType::Info,
+                ButtonsType::Ok,
+                &fl!("merge-done-text")
+            );
+            ..set_title(&fl!("success"));
+            ..connect_response(|dialog, _| dialog.close());
+            ..show_all();
+        };
+    }
+}
@@ -1,14 +1,20 @@
 mod about_dialog;
+mod backup_dialog;
+mod data_location;
+mod database_recovery;
 mod exporter;
 mod file_new_dialog;
 mod filter;
 pub(crate) mod history;
+mod merge_preview;
 pub(crate) mod new_dialog;
 mod notifcation_dialog;
 mod novel_dialog;
 pub(crate) mod novel_list;
+mod profiles;
 mod reading_now;
 mod settings_dialog;
+mod sync_conflicts;
 
 pub use self::novel_list::NovelList;
 use gdk::gdk_pixbuf::Pixbuf;
@@ -18,19 +24,19 @@ use std::io::Cursor;
 use std::time::Instant;
 
 use crate::app::localize::available_languages;
-use crate::app::novel::{Novel, NovelStatus, NovelType};
-use crate::app::settings::{ChapterReadPreference, Settings};
+use crate::app::settings::{ChapterReadPreference, Settings, StorageBackend};
 use crate::app::AppRuntime;
 use crate::ui::file_new_dialog::FileNewNovelDialog;
 use crate::ui::filter::FilterList;
 use crate::ui::history::HistoryList;
 use crate::ui::new_dialog::NewNovelDialog;
 use crate::ui::novel_dialog::NovelDialog;
-use crate::ui::novel_list::ListStatus;
 use crate::ui::settings_dialog::SettingsDialog;
 use crate::utils::gtk::BuilderExtManualCustom;
 use crate::utils::Resources;
 use crate::UPDATE_LINK;
+use eris_core::novel::{ListStatus, Novel, NovelStatus, NovelType};
+use eris_core::titles::TitleSourceKind;
 use gtk::prelude::*;
 
 pub struct UI {
@@ -90,6 +96,7 @@ impl UI {
         builder.label_i18n("reading_source_label", &(fl!("novel-source") + ":"));
         builder.label_i18n("not_found_label", &fl!("not-found"));
         builder.label_i18n("not_found_suggestion_label", &fl!("not-found-suggestion"));
+        builder.label_i18n("pending_label", &fl!("pending-confirmations"));
         builder.label_i18n("previously_read_label", &(fl!("previously-read") + ":"));
         builder.label_i18n("currently_reading_label", &fl!("currently-reading"));
         builder.label_i18n("menu_update_label", &fl!("menu-update"));
@@ -106,6 +113,8 @@ impl UI {
         builder.menu_item_i18n("menu_help", &fl!("menu-help"));
         builder.menu_item_i18n("menu_new", &fl!("menu-new"));
         builder.menu_item_i18n("menu_save", &fl!("menu-save"));
+        builder.menu_item_i18n("menu_restore_backup", &fl!("menu-restore-backup"));
+        builder.menu_item_i18n("menu_profiles", &fl!("menu-profiles"));
         builder.menu_item_i18n("menu_quit", &fl!("menu-quit"));
         builder.menu_item_i18n("menu_settings", &fl!("settings"));
         builder.menu_item_i18n("show_reading_checkmenuitem", &fl!("menu-show-reading"));
@@ -198,6 +207,8 @@ impl UI {
                 "Actions",
             ],
         );
+        self.populate_combobox(&self.settings_dialog.storage_backend_combobox, &StorageBackend::vec());
+        self.populate_combobox(&self.settings_dialog.title_source_combobox, &TitleSourceKind::vec());
         self.populate_language_combobox(&self.settings_dialog.language_combobox, &available_languages());
 
         debug!("UI init doned");
@@ -223,7 +234,8 @@ impl UI {
 
         btn_continue_reading.connect_button_release_event(glib::clone!(@strong app_runtime => move |_, _| {
             app_runtime.update_state_with(move |state| {
-                if let Some(last_read) = state.history.clone().read().find_last_read() {
+                let last_read = state.library.read().history.find_last_read();
+                if let Some(last_read) = last_read {
                     if let Some(novel) = state.get_by_id(last_read.novel_id) {
                         state.read_novel(novel);
                     }
@@ -272,11 +284,12 @@ impl UI {
                 // Clear the history list
                 state.ui.history.list_clear();
                 // Now load all the entries into it
-                state.ui.history.populate_columns(&state.history.read().items);
+                let library = state.library.read();
+                state.ui.history.populate_columns(&library.history.items);
                 // Tell user the cool stuff
                 label_load_history.set_text(&format!(
                     "Loaded {:?} history entries in {:?}",
-                    &state.history.read().items.len(),
+                    &library.history.items.len(),
                     load_start.elapsed()
                 ));
             });
@@ -357,6 +370,8 @@ impl UI {
         self.novel_dialog
             .info_notebook
             .set_page(settings.list.open_info_behavior);
+
+        self.init_profiles_menu();
     }
 
     /// Show reading now notebook page.
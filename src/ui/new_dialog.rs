@@ -1,7 +1,7 @@
-use crate::app::novel::NovelContentAmount;
 use crate::app::AppRuntime;
-use crate::appop::parsers::{novel_title_to_slug, NovelParser};
 use crate::utils::gtk::BuilderExtManualCustom;
+use eris_core::novel::NovelContentAmount;
+use eris_core::parsers::{novel_title_to_slug, NovelParser};
 use gtk::prelude::WidgetExtManual;
 use gtk::prelude::*;
 use gtk::{Dialog, ResponseType};
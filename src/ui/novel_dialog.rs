@@ -1,10 +1,11 @@
-use crate::app::novel::{Novel, NovelContentAmount, NovelSettings, NovelStatus, NovelType};
-use crate::app::{AppRuntime, NOVEL_UPDATE_COOLDOWN};
-use crate::data_dir;
-use crate::ui::novel_list::ListStatus;
+use crate::app::AppRuntime;
+use crate::ui::novel_list::{open_slug, ListStatusExt};
 use crate::utils::gtk::BuilderExtManualCustom;
 use crate::utils::nil_str;
 use chrono::Local;
+use eris_core::novel::{ListStatus, Novel, NovelContentAmount, NovelSettings, NovelStatus, NovelType};
+use eris_core::paths::data_dir;
+use eris_core::NOVEL_UPDATE_COOLDOWN;
 use gdk::ModifierType;
 use glib::SignalHandlerId;
 use gtk::gdk_pixbuf::Pixbuf;
@@ -401,7 +402,7 @@ impl NovelDialog {
 
             let novel_clone = novel.clone();
             let handler = novel_source_slug_label.connect_activate_link(move |_, _| {
-                novel_clone.open_slug();
+                open_slug(&novel_clone);
 
                 gtk::Inhibit(true)
             });
@@ -1,10 +1,12 @@
-use crate::app::novel::Novel;
+use eris_core::novel::{ListStatus, Novel};
 
 use crate::app::settings::{NovelListAction, Settings, Sorting};
 use crate::app::AppRuntime;
 use crate::appop::messages::SortingMessage;
 use crate::utils::gtk::BuilderExtManualCustom;
+use crate::utils::Resources;
 use chrono::NaiveDateTime;
+use gdk::gdk_pixbuf::Pixbuf;
 use gdk::{DragAction, EventButton, ModifierType};
 use gio::prelude::*;
 use glib::{SignalHandlerId, Type};
@@ -13,6 +15,7 @@ use gtk::{prelude::*, Adjustment, DestDefaults, SortColumn, TreePath, TreeView};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::io::Cursor;
 use std::ops::{Index, IndexMut};
 
 pub const LIST_COUNT: i32 = 5;
@@ -33,61 +36,16 @@ pub const COLUMN_TYPES: [Type; COLUMN_COUNT] = [
     Type::STRING,
 ];
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
-#[repr(i32)]
-pub enum ListStatus {
-    Reading = 0,
-    PlanToRead,
-    OnHold,
-    Completed,
-    Dropped,
-}
-
-impl Default for ListStatus {
-    fn default() -> Self {
-        ListStatus::PlanToRead
-    }
-}
-
-impl ToString for ListStatus {
-    fn to_string(&self) -> String {
-        match self {
-            ListStatus::Reading => fl!("reading"),
-            ListStatus::PlanToRead => fl!("plan-to-read"),
-            ListStatus::OnHold => fl!("on-hold"),
-            ListStatus::Completed => fl!("completed"),
-            ListStatus::Dropped => fl!("dropped"),
-        }
-    }
+/// Widgets of the lists that belong to a `ListStatus`.
+pub trait ListStatusExt: Sized {
+    fn combo_box_id(&self) -> &str;
+    fn from_combo_box_id(id: &str) -> Self;
+    fn from_name(name: &str) -> Self;
+    fn treeview_id(&self) -> &'static str;
 }
 
-impl ListStatus {
-    pub fn vec() -> Vec<String> {
-        vec![
-            ListStatus::Reading.to_string(),
-            ListStatus::PlanToRead.to_string(),
-            ListStatus::OnHold.to_string(),
-            ListStatus::Completed.to_string(),
-            ListStatus::Dropped.to_string(),
-        ]
-    }
-
-    pub fn from_i32(i: i32) -> ListStatus {
-        match i {
-            0 => ListStatus::Reading,
-            1 => ListStatus::PlanToRead,
-            2 => ListStatus::OnHold,
-            3 => ListStatus::Completed,
-            4 => ListStatus::Dropped,
-            _ => ListStatus::Reading,
-        }
-    }
-
-    pub fn to_i32(self) -> i32 {
-        self.to_owned() as i32
-    }
-
-    pub(crate) fn combo_box_id(&self) -> &str {
+impl ListStatusExt for ListStatus {
+    fn combo_box_id(&self) -> &str {
         match *self {
             ListStatus::Reading => "0",
             ListStatus::PlanToRead => "1",
@@ -97,7 +55,7 @@ impl ListStatus {
         }
     }
 
-    pub(crate) fn from_combo_box_id(id: &str) -> ListStatus {
+    fn from_combo_box_id(id: &str) -> ListStatus {
         match id {
             "0" => ListStatus::Reading,
             "1" => ListStatus::PlanToRead,
@@ -108,7 +66,7 @@ impl ListStatus {
         }
     }
 
-    pub fn from_name(name: &str) -> ListStatus {
+    fn from_name(name: &str) -> ListStatus {
         match name {
             "reading" => ListStatus::Reading,
             "plan_to_read" => ListStatus::PlanToRead,
@@ -119,7 +77,7 @@ impl ListStatus {
         }
     }
 
-    pub fn treeview_id(&self) -> &'static str {
+    fn treeview_id(&self) -> &'static str {
         match *self {
             ListStatus::Reading => "TreeView1",
             ListStatus::PlanToRead => "TreeView2",
@@ -130,6 +88,20 @@ impl ListStatus {
     }
 }
 
+/// If the `Novel` has `slug` then open it in browser.
+pub fn open_slug(novel: &Novel) {
+    if let Some(slug) = &novel.slug {
+        if webbrowser::open(slug).is_ok() {}
+    }
+}
+
+/// Icon of the publishing status of `novel` for the status column.
+pub fn status_pix(novel: &Novel) -> Pixbuf {
+    let file = format!("icons/{}.png", novel.status.to_str());
+    let resource = Resources::get(&file).unwrap().data;
+    Pixbuf::from_read(Cursor::new(resource)).expect("Cannot load pixbuf from resource.")
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 #[repr(i32)]
 pub enum Column {
@@ -543,7 +515,7 @@ impl NovelList {
             let values: [(u32, &dyn ToValue); COLUMN_COUNT] = [
                 (ID_COLUMN as u32, &novel.id),
                 (Column::Status as u32, &novel.status.to_string()),
-                (Column::StatusIcon as u32, &novel.status_pix()),
+                (Column::StatusIcon as u32, &status_pix(novel)),
                 (Column::OriginalLanguage as u32, &novel.orig_lang()),
                 (Column::Title as u32, &novel.title()),
                 (Column::ChaptersRead as u32, &novel.chapters_read_str()),
@@ -605,7 +577,7 @@ impl NovelList {
         let values: [(u32, &dyn ToValue); COLUMN_COUNT] = [
             (ID_COLUMN as u32, &novel.id),
             (Column::Status as u32, &novel.status.to_string()),
-            (Column::StatusIcon as u32, &novel.status_pix()),
+            (Column::StatusIcon as u32, &status_pix(novel)),
             (Column::OriginalLanguage as u32, &novel.orig_lang()),
             (Column::Title as u32, &novel.title()),
             (Column::ChaptersRead as u32, &novel.chapters_read_str()),
@@ -741,7 +713,7 @@ impl NovelList {
 
             // Updated the columns on the selected row
             list.set_value(iter, Column::Status as u32, &novel.status.to_str().to_value());
-            list.set_value(iter, Column::StatusIcon as u32, &novel.status_pix().to_value());
+            list.set_value(iter, Column::StatusIcon as u32, &status_pix(novel).to_value());
             list.set_value(iter, Column::OriginalLanguage as u32, &novel.orig_lang().to_value());
             list.set_value(iter, Column::Title as u32, &novel.title().to_value());
             list.set_value(iter, Column::ChaptersRead as u32, &novel.chapters_read_str().to_value());
@@ -937,7 +909,7 @@ pub fn action_open_menu(app_runtime: &AppRuntime, tree: &TreeView, treeview_inde
         let novel_id = novel_id.clone();
         app_runtime.update_state_with(move |state| {
             if let Some(novel) = state.get_by_id(novel_id) {
-                novel.open_slug();
+                open_slug(&novel);
             }
         });
     }));
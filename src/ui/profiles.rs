@@ -0,0 +1,93 @@
+use crate::app::AppRuntime;
+use crate::ui::UI;
+use crate::utils::gtk::BuilderExtManualCustom;
+use eris_core::profile::{current_profile, list_profiles, profile_name, DEFAULT_PROFILE};
+use gtk::prelude::*;
+use gtk::{DialogFlags, ResponseType};
+
+impl UI {
+    /// Fill the profiles menu with the default profile, every other profile and an item for creating one.
+    /// The profile in use is shown but cannot be selected.
+    pub fn init_profiles_menu(&self) {
+        let menu = self.builder.get::<gtk::Menu>("menu_profiles_menu");
+        let current = current_profile().unwrap_or(DEFAULT_PROFILE);
+
+        let profiles = std::iter::once(DEFAULT_PROFILE.to_string()).chain(list_profiles());
+        for profile in profiles {
+            let label = if profile == DEFAULT_PROFILE {
+                fl!("profile-default")
+            } else {
+                profile.clone()
+            };
+            let label = if profile == current {
+                fl!("profile-current", name = label)
+            } else {
+                label
+            };
+
+            menu.append(&cascade! {
+                gtk::MenuItem::with_label(&label);
+                ..set_sensitive(profile != current);
+                ..set_action_name(Some("app.switch_profile"));
+                ..set_action_target_value(Some(&profile.to_variant()));
+            });
+        }
+
+        menu.append(&gtk::SeparatorMenuItem::new());
+        menu.append(&cascade! {
+            gtk::MenuItem::with_label(&fl!("profile-new"));
+            ..set_action_name(Some("app.new_profile"));
+        });
+        menu.show_all();
+    }
+
+    /// Ask for the name of a new profile and switch to it.
+    pub fn open_new_profile_dialog(&self, app_runtime: &AppRuntime) {
+        let entry = cascade! {
+            gtk::Entry::new();
+            ..set_activates_default(true);
+            ..set_margin_start(6);
+            ..set_margin_end(6);
+        };
+
+        let dialog = cascade! {
+            gtk::Dialog::with_buttons(
+                Some(&fl!("profile-new-title")),
+                Some(&self.main_window),
+                DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+                &[
+                    (fl!("cancel-button").as_str(), ResponseType::Cancel),
+                    (fl!("profile-create").as_str(), ResponseType::Accept),
+                ]
+            );
+            ..set_default_width(360);
+            ..set_default_response(ResponseType::Accept);
+        };
+        cascade! {
+            dialog.content_area();
+            ..set_spacing(6);
+            ..add(&cascade! {
+                gtk::Label::new(Some(&fl!("profile-new-text")));
+                ..set_xalign(0.0);
+                ..set_line_wrap(true);
+                ..set_margin_start(6);
+                ..set_margin_end(6);
+            });
+            ..add(&entry);
+        };
+
+        dialog.connect_response(
+            glib::clone!(@strong app_runtime, @weak entry => move |dialog, response_type| {
+                let name = entry.text();
+                if response_type == ResponseType::Accept && !name.trim().is_empty() {
+                    let name = profile_name(&name);
+                    app_runtime.update_state_with(move |state| state.switch_profile(name.as_deref()));
+                }
+
+                dialog.close();
+            }),
+        );
+
+        dialog.show_all();
+    }
+}
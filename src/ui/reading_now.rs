@@ -1,10 +1,11 @@
-use crate::app::novel::{Novel, NovelStatus};
 use crate::ui::UI;
+use eris_core::novel::{Novel, NovelStatus};
 
 use crate::app::AppRuntime;
-use crate::appop::novel_recognition::NovelRecognitionData;
-use crate::data_dir;
+use crate::ui::novel_list::open_slug;
 use crate::utils::gtk::BuilderExtManualCustom;
+use eris_core::paths::data_dir;
+use eris_core::recognition::{MatchReason, NovelMatch, NovelRecognitionData};
 use gdk::pango::WrapMode;
 use gtk::gdk_pixbuf::Pixbuf;
 use gtk::prelude::*;
@@ -173,7 +174,7 @@ impl UI {
 
                 let novel_clone = novel.clone();
                 let handler = reading_novel_slug.connect_activate_link(move |_, _| {
-                    novel_clone.open_slug();
+                    open_slug(&novel_clone);
 
                     gtk::Inhibit(true)
                 });
@@ -329,4 +330,66 @@ impl UI {
 
         potential_novels_box.show_all();
     }
+
+    /// Show the novel matches that wait for a confirmation, hidden if there are none.
+    pub fn show_pending_confirmations(&self, pending: &[NovelMatch], app_runtime: AppRuntime) {
+        let pending_box = self.builder.get::<gtk::Box>("pending_box");
+        let pending_matches_box = self.builder.get::<gtk::Box>("pending_matches_box");
+
+        for child in pending_matches_box.children() {
+            pending_matches_box.remove(&child);
+        }
+        pending_box.set_visible(!pending.is_empty());
+
+        for matched in pending {
+            let reason = match matched.reason {
+                MatchReason::Title => fl!("match-reason-title"),
+                MatchReason::Keyword => fl!("match-reason-keyword"),
+                MatchReason::Similar => fl!("match-reason-similar"),
+            };
+            let label = cascade! {
+                gtk::Label::new(None);
+                ..set_markup(&fl!(
+                    "pending-confirmation",
+                    novel = glib::markup_escape_text(&matched.novel_title).to_string(),
+                    text = glib::markup_escape_text(&matched.text).to_string(),
+                    confidence = format!("{:.0}", matched.confidence * 100.0),
+                    reason = reason
+                ));
+                ..set_wrap(true);
+                ..set_wrap_mode(WrapMode::Word);
+                ..set_xalign(0.0);
+            };
+
+            let confirm_btn = cascade! {
+                gtk::Button::with_label(&fl!("pending-confirm"));
+                ..connect_clicked(glib::clone!(@strong app_runtime, @strong matched => move |_| {
+                    let matched = matched.clone();
+                    app_runtime.update_state_with(move |state| {
+                        state.confirm_match(&matched.text, &matched.novel_id);
+                    });
+                }));
+            };
+            let dismiss_btn = cascade! {
+                gtk::Button::with_label(&fl!("pending-dismiss"));
+                ..connect_clicked(glib::clone!(@strong app_runtime, @strong matched => move |_| {
+                    let matched = matched.clone();
+                    app_runtime.update_state_with(move |state| {
+                        state.dismiss_match(&matched.text, &matched.novel_id);
+                    });
+                }));
+            };
+
+            let hbox = cascade! {
+                gtk::Box::new(Orientation::Horizontal, 10);
+                ..pack_start(&label, true, true, 0);
+                ..pack_end(&dismiss_btn, false, false, 0);
+                ..pack_end(&confirm_btn, false, false, 0);
+            };
+
+            pending_matches_box.add(&hbox);
+        }
+
+        pending_matches_box.show_all();
+    }
 }
@@ -2,6 +2,9 @@ use crate::app::settings::{NovelListAction, Settings};
 use crate::app::AppRuntime;
 use crate::ui::novel_list::Column;
 use crate::utils::gtk::BuilderExtManualCustom;
+use eris_core::profile::profile_dir;
+use eris_core::title_rules::{read_rules, TEMPLATE};
+use eris_core::{DATA_DIR, TITLE_RULES_FILE};
 use gtk::prelude::WidgetExtManual;
 use gtk::prelude::*;
 use gtk::Dialog;
@@ -15,6 +18,8 @@ pub struct SettingsDialog {
     pub preference_combobox: gtk::ComboBoxText,
     pub language_combobox: gtk::ComboBoxText,
     pub novel_info_tabs_combobox: gtk::ComboBoxText,
+    pub storage_backend_combobox: gtk::ComboBoxText,
+    pub title_source_combobox: gtk::ComboBoxText,
 }
 
 impl SettingsDialog {
@@ -39,6 +44,7 @@ impl SettingsDialog {
         builder.button_i18n("settings_btn_default", &fl!("default-settings-button"));
         builder.button_i18n("settings_btn_file_clear", &fl!("unset-button"));
         builder.button_i18n("settings_btn_open_dir", &fl!("open-dir-button"));
+        builder.button_i18n("settings_btn_move_dir", &fl!("move-dir-button"));
 
         builder.label_i18n("application_settings_label", &fl!("settings-app"));
         builder.label_i18n("novel_list_label", &fl!("novel-list"));
@@ -75,13 +81,45 @@ impl SettingsDialog {
             "settings_reg_autocomplete_ongoing",
             &(fl!("settings-reg-autocomplete-ongoing") + ":"),
         );
+        builder.label_i18n(
+            "settings_reg_title_source_label",
+            &(fl!("settings-reg-title-source") + ":"),
+        );
         builder.label_i18n("settings_reg_enable_label", &(fl!("settings-reg-enable-feature") + ":"));
         builder.label_i18n("settings_rec_delay_label", &(fl!("settings-reg-delay") + ":"));
         builder.label_i18n("settings_rec_delay_info_label", &fl!("settings-reg-delay-info"));
+        builder.label_i18n(
+            "settings_rec_idle_threshold_label",
+            &(fl!("settings-reg-idle-threshold") + ":"),
+        );
+        builder.label_i18n(
+            "settings_rec_idle_threshold_info_label",
+            &fl!("settings-reg-idle-threshold-info"),
+        );
+        builder.label_i18n("settings_rec_focus_only_label", &(fl!("settings-reg-focus-only") + ":"));
+        builder
+            .get::<gtk::CheckButton>("novel_recognition_focus_only")
+            .set_tooltip_text(Some(&fl!("settings-reg-focus-only-hint")));
 
         builder.label_i18n("settings_adv_label", &fl!("settings-reg-advanced-title"));
         builder.label_i18n("settings_keywords_label", &(fl!("settings-reg-keywords") + ":"));
         builder.label_i18n("settings_ignore_label", &(fl!("settings-reg-ignore-keywords") + ":"));
+        builder
+            .get::<gtk::Entry>("novel_recognition_ignore_keywords_entry")
+            .set_tooltip_text(Some(&fl!("settings-reg-ignore-keywords-hint")));
+        builder.label_i18n(
+            "settings_auto_confidence_label",
+            &(fl!("settings-reg-auto-confidence") + ":"),
+        );
+        builder.label_i18n(
+            "settings_auto_confidence_info_label",
+            &fl!("settings-reg-auto-confidence-info"),
+        );
+        builder.label_i18n("settings_title_rules_label", &fl!("settings-reg-title-rules-title"));
+        builder.label_i18n("settings_title_rules_hint_label", &fl!("settings-reg-title-rules-hint"));
+        builder
+            .get::<gtk::Entry>("title_rules_test_entry")
+            .set_placeholder_text(Some(&fl!("settings-reg-title-rules-test")));
         builder.label_i18n("settings_behavior_label", &fl!("settings-rec-behavior"));
         builder.label_i18n("settings_rec_when_rec_label", &(fl!("settings-rec-when") + ":"));
         builder.label_i18n("settings_rec_when_not_rec_label", &(fl!("settings-rec-when-not") + ":"));
@@ -107,11 +145,17 @@ impl SettingsDialog {
         builder.checkbutton_i18n("first_tab_always_checkbox", &fl!("first-tab-always"));
         builder.checkbutton_i18n("novel_recognition_enabled_checkbutton", &fl!("yes"));
         builder.checkbutton_i18n("novel_recognition_autocomplete_ongoing", &fl!("yes"));
+        builder.checkbutton_i18n("novel_recognition_focus_only", &fl!("yes"));
         builder.checkbutton_i18n("novel_rec_found_go_to_reading", &fl!("go-to-reading-now"));
         builder.checkbutton_i18n("novel_rec_not_found_go_to_reading", &fl!("go-to-reading-now"));
 
         builder.label_i18n("settings_data_label", &fl!("settings-data"));
         builder.label_i18n("settings_data_dir_label", &fl!("settings-data-dir"));
+        builder.label_i18n("settings_storage_label", &fl!("settings-data-storage"));
+        builder.label_i18n("settings_move_library_label", &fl!("settings-move-library"));
+        builder.label_i18n("settings_backup_retention_label", &fl!("settings-backup-retention"));
+        builder.checkbutton_i18n("sync_enabled_checkbutton", &fl!("settings-sync-enabled"));
+        builder.checkbutton_i18n("api_enabled_checkbutton", &fl!("settings-api-enabled"));
 
         let action_list = NovelListAction::vec();
 
@@ -130,6 +174,8 @@ impl SettingsDialog {
         let preference_combobox = builder.get::<gtk::ComboBoxText>("novel_recognition_read_preference_combobox");
         let language_combobox = builder.get::<gtk::ComboBoxText>("language_combobox");
         let novel_info_tabs_combobox = builder.get::<gtk::ComboBoxText>("first_tab_behavior_combobox");
+        let storage_backend_combobox = builder.get::<gtk::ComboBoxText>("storage_backend_combobox");
+        let title_source_combobox = builder.get::<gtk::ComboBoxText>("novel_recognition_title_source_combobox");
 
         SettingsDialog {
             dialog,
@@ -138,6 +184,8 @@ impl SettingsDialog {
             preference_combobox,
             language_combobox,
             novel_info_tabs_combobox,
+            storage_backend_combobox,
+            title_source_combobox,
         }
     }
 
@@ -185,6 +233,29 @@ impl SettingsDialog {
                 state.open_data_directory();
             });
         }));
+
+        // Test the title rules as they are typed
+        let title_rules_buffer = builder.get::<gtk::TextView>("title_rules_textview").buffer().unwrap();
+        title_rules_buffer.connect_changed(glib::clone!(@strong app_runtime => move |_| {
+            app_runtime.update_state_with(|state| {
+                state.test_title_rules();
+            });
+        }));
+        builder.get::<gtk::Entry>("title_rules_test_entry").connect_changed(
+            glib::clone!(@strong app_runtime => move |_| {
+                app_runtime.update_state_with(|state| {
+                    state.test_title_rules();
+                });
+            }),
+        );
+
+        let settings_btn_move_dir = builder.get::<gtk::Button>("settings_btn_move_dir");
+
+        settings_btn_move_dir.connect_clicked(glib::clone!(@strong app_runtime => move |_| {
+            app_runtime.update_state_with(move |state| {
+                state.ui.open_move_library_dialog(&state.app_runtime);
+            });
+        }));
     }
 
     pub fn update(&self, builder: &gtk::Builder, settings: &Settings) {
@@ -261,6 +332,9 @@ impl SettingsDialog {
         let novel_recognition_enabled_checkbutton =
             builder.get::<gtk::CheckButton>("novel_recognition_enabled_checkbutton");
         let novel_recognition_delay = builder.get::<gtk::SpinButton>("novel_recognition_delay");
+        let novel_recognition_idle_threshold = builder.get::<gtk::SpinButton>("novel_recognition_idle_threshold");
+        let novel_recognition_focus_only = builder.get::<gtk::CheckButton>("novel_recognition_focus_only");
+        let novel_recognition_auto_confidence = builder.get::<gtk::SpinButton>("novel_recognition_auto_confidence");
         let novel_recognition_read_preference_combobox =
             builder.get::<gtk::ComboBoxText>("novel_recognition_read_preference_combobox");
         let novel_recognition_autocomplete_ongoing =
@@ -275,17 +349,60 @@ impl SettingsDialog {
 
         novel_recognition_enabled_checkbutton.set_active(settings.novel_recognition.enable);
         novel_recognition_delay.set_value(settings.novel_recognition.delay as f64);
+        novel_recognition_idle_threshold.set_value(settings.novel_recognition.idle_threshold as f64);
+        novel_recognition_focus_only.set_active(settings.novel_recognition.focus_only);
+        novel_recognition_auto_confidence.set_value(settings.novel_recognition.auto_confidence as f64);
         novel_recognition_read_preference_combobox.set_active_id(Some(
             settings.novel_recognition.chapter_read_preference.to_string().as_str(),
         ));
         novel_recognition_autocomplete_ongoing.set_active(settings.novel_recognition.autocomplete_ongoing);
+        self.title_source_combobox
+            .set_active_id(Some(&settings.novel_recognition.title_source.to_i32().to_string()));
         novel_recognition_found_go_to_reading.set_active(settings.novel_recognition.when_novel_go_to_reading);
         novel_recognition_not_found_go_to_reading.set_active(settings.novel_recognition.when_not_novel_go_to_reading);
         novel_recognition_title_keywords_entry.set_text(&settings.novel_recognition.title_keywords.join(","));
         novel_recognition_ignore_keywords_entry.set_text(&settings.novel_recognition.ignore_keywords.join(","));
+        let title_rules = read_rules(&profile_dir(TITLE_RULES_FILE)).unwrap_or_else(|e| {
+            error!("{:#}", e);
+            TEMPLATE.to_string()
+        });
+        builder
+            .get::<gtk::TextView>("title_rules_textview")
+            .buffer()
+            .unwrap()
+            .set_text(&title_rules);
 
         let data_dir_label = builder.get::<gtk::Label>("data_dir_label");
 
-        data_dir_label.set_label(settings.general.data_dir.to_str().unwrap());
+        data_dir_label.set_label(&profile_dir(DATA_DIR).to_string_lossy());
+        self.storage_backend_combobox
+            .set_active_id(Some(&settings.general.storage_backend.to_i32().to_string()));
+        builder
+            .get::<gtk::SpinButton>("backup_retention_spinbutton")
+            .set_value(settings.general.backup_retention as f64);
+        let sync_dir_chooser = builder.get::<gtk::FileChooserButton>("sync_dir_chooser");
+        builder
+            .get::<gtk::CheckButton>("sync_enabled_checkbutton")
+            .set_active(settings.general.sync_dir.is_some());
+        if let Some(sync_dir) = &settings.general.sync_dir {
+            sync_dir_chooser.set_filename(sync_dir);
+        } else {
+            sync_dir_chooser.unselect_all();
+        }
+        builder
+            .get::<gtk::CheckButton>("api_enabled_checkbutton")
+            .set_active(settings.general.api_enabled);
+        builder
+            .get::<gtk::SpinButton>("api_port_spinbutton")
+            .set_value(settings.general.api_port as f64);
+    }
+    /// Title rules as they are written in the dialog.
+    pub fn title_rules_text(&self, builder: &gtk::Builder) -> String {
+        let buffer = builder.get::<gtk::TextView>("title_rules_textview").buffer().unwrap();
+
+        buffer
+            .text(&buffer.start_iter(), &buffer.end_iter(), false)
+            .unwrap_or_else(|| "".into())
+            .to_string()
     }
 }
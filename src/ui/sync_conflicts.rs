@@ -0,0 +1,40 @@
+use crate::ui::UI;
+use eris_core::sync::SyncConflict;
+use gtk::prelude::*;
+use gtk::{ButtonsType, DialogFlags, MessageType};
+
+impl UI {
+    /// List the fields that were changed both here and on another device since the last sync.
+    pub fn open_sync_conflicts_message(&self, conflicts: &[SyncConflict]) {
+        let lines: Vec<String> = conflicts
+            .iter()
+            .map(|c| {
+                let kept = if c.local_kept {
+                    fl!("sync-kept-local")
+                } else {
+                    fl!("sync-kept-remote", device = c.device.clone())
+                };
+                fl!(
+                    "sync-conflict",
+                    title = c.novel_title.clone(),
+                    field = c.field.to_string(),
+                    kept = kept
+                )
+            })
+            .collect();
+
+        cascade! {
+            gtk::MessageDialog::new(
+                Some(&self.main_window),
+                DialogFlags::DESTROY_WITH_PARENT,
+                MessageType::Warning,
+                ButtonsType::Ok,
+                &fl!("sync-conflicts-text")
+            );
+            ..set_title(&fl!("sync-conflicts-title"));
+            ..set_secondary_text(Some(&lines.join("\n")));
+            ..connect_response(|dialog, _| dialog.close());
+            ..show_all();
+        };
+    }
+}
@@ -1,8 +1,6 @@
 pub(crate) mod gtk;
 
 use rust_embed::RustEmbed;
-use std::env::current_exe;
-use std::path::PathBuf;
 
 macro_rules! hashmap {
     ($( $key: expr => $val: expr ),*) => {{
@@ -30,59 +28,6 @@ macro_rules! fl {
 #[folder = "resources/"]
 pub struct Resources;
 
-pub fn working_dir(file: &str) -> String {
-    #[cfg(debug_assertions)]
-    let pop_count = 3;
-
-    #[cfg(all(not(debug_assertions), target_os = "windows"))]
-    let pop_count = 2;
-
-    #[cfg(all(not(debug_assertions), target_os = "linux"))]
-    let pop_count = 1;
-
-    #[cfg(all(not(debug_assertions), target_os = "macos"))]
-    let pop_count = 1;
-
-    let dir = match current_exe() {
-        Ok(mut path) => {
-            for _ in 0..pop_count {
-                path.pop();
-            }
-            path
-        }
-        Err(e) => {
-            // Is this even possible?
-            error!("Could not get current_exe path. {:?}", e);
-            PathBuf::new()
-        }
-    };
-
-    if let Some(filepath) = dir.join(file).to_str() {
-        return filepath.to_string();
-    }
-
-    "".to_string()
-}
-
-#[cfg(debug_assertions)]
-pub fn data_dir(file: &str) -> PathBuf {
-    PathBuf::from(working_dir(file))
-}
-
-#[cfg(not(debug_assertions))]
-pub fn data_dir(file: &str) -> PathBuf {
-    // Use local data dir if it exists
-    if PathBuf::from(&working_dir("data")).exists() {
-        return PathBuf::from(&working_dir(file));
-    }
-    // Otherwise use OS specific "data" directory
-    if let Some(dir) = dirs::data_dir() {
-        return dir.join("Eris").join(file);
-    }
-
-    return PathBuf::new();
-}
-
 /// Check if the string is empty (or "0") and return "-" if true
 /// otherwise return the actual string.
 /// This is for GUI so the value field has something instead of emptiness.
@@ -93,34 +38,3 @@ pub fn nil_str(value: &str) -> String {
 
     value.to_string()
 }
-
-/// Caplitaze the `str` and return it as `String`.
-pub fn capitalize_str(s: &str) -> String {
-    let mut c = s.chars();
-    match c.next() {
-        None => String::new(),
-        Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
-    }
-}
-
-/// Return true if part(s) of a longer string is in a shorter one. Lie.. reverse of "is `foo` in `foobar`".
-///
-/// Example: `Novel Name chapter 12` is in `Novel Name`.
-///
-///
-pub fn split_checker(split_source: &[&str], target: &str) -> bool {
-    let mut count = 0;
-    let split_target = target.split(' ').collect::<Vec<_>>();
-
-    for i in 0..split_target.len() {
-        if split_source.get(i).unwrap_or(&"").to_lowercase() == split_target.get(i).unwrap().to_lowercase() {
-            count += 1;
-
-            if count == split_target.len() {
-                return true;
-            }
-        }
-    }
-
-    false
-}